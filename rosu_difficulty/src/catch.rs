@@ -0,0 +1,83 @@
+use rosu_parser::{
+    beatmap::{Beatmap, catch::{catcher_width, CatchObjectKind}, sections::GameMode},
+    mods::Mods,
+};
+
+use self::{movement::Movement, object::CatchDifficultyObject};
+
+pub mod object;
+pub mod movement;
+
+const STAR_SCALING_FACTOR: f64 = 0.153;
+
+/// star rating of a catch map and what went into it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatchDifficultyAttributes {
+    pub stars: f64,
+    pub approach_rate: f64, //rate adjusted
+    pub n_fruits: usize,
+    pub n_droplets: usize,
+    pub n_tiny_droplets: usize,
+    pub n_bananas: usize,
+    pub is_convert: bool,
+}
+
+impl CatchDifficultyAttributes {
+
+    /// fruits and droplets, tiny droplets and bananas don't give combo
+    pub fn max_combo(&self) -> usize {
+        self.n_fruits + self.n_droplets
+    }
+}
+
+/// star rating calculation for catch maps and osu!standard maps played in catch
+pub struct CatchStars<'a> {
+    beatmap: &'a Beatmap,
+    mods: Mods,
+}
+
+impl<'a> CatchStars<'a> {
+
+    pub fn new(beatmap: &'a Beatmap) -> Self {
+        Self {
+            beatmap,
+            mods: Mods::empty(),
+        }
+    }
+
+    pub fn mods(mut self, mods: Mods) -> Self {
+        self.mods = mods;
+        self
+    }
+
+    pub fn calculate(&self) -> CatchDifficultyAttributes {
+        let mut map_attributes = self.beatmap.difficulty_attributes().with_mods(self.mods);
+        map_attributes.mode = GameMode::Catch;
+        let clock_rate = map_attributes.clock_rate;
+
+        let objects = self.beatmap.catch_objects(map_attributes.circle_size, self.mods.contains(Mods::HARD_ROCK));
+        let palpable: Vec<_> = objects.iter().copied().filter(|o| o.is_palpable()).collect();
+        let count = |kind| objects.iter().filter(|o| o.kind == kind).count();
+
+        //above CS 5.5 the catcher counts as even smaller, nobody catches with the very edge
+        let mut half_catcher_width = catcher_width(map_attributes.circle_size) / 2.0;
+        half_catcher_width *= 1.0 - (map_attributes.circle_size - 5.5).max(0.0) * 0.0625;
+
+        let difficulty_objects = CatchDifficultyObject::create_all(&palpable, clock_rate, half_catcher_width);
+
+        let mut movement = Movement::new(clock_rate);
+        for current in &difficulty_objects {
+            movement.process(current, &difficulty_objects);
+        }
+
+        CatchDifficultyAttributes {
+            stars: movement.difficulty_value().sqrt() * STAR_SCALING_FACTOR,
+            approach_rate: map_attributes.effective_approach_rate(),
+            n_fruits: count(CatchObjectKind::Fruit),
+            n_droplets: count(CatchObjectKind::Droplet),
+            n_tiny_droplets: count(CatchObjectKind::TinyDroplet),
+            n_bananas: count(CatchObjectKind::Banana),
+            is_convert: self.beatmap.mode() != GameMode::Catch,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use crate::strain::{self, SectionPeaks};
+
+use super::object::CatchDifficultyObject;
+
+const SKILL_MULTIPLIER: f64 = 900.0;
+const STRAIN_DECAY_BASE: f64 = 0.2;
+const DECAY_WEIGHT: f64 = 0.94;
+const SECTION_LENGTH: f64 = 750.0;
+
+const ABSOLUTE_PLAYER_POSITIONING_ERROR: f32 = 16.0;
+const NORMALISED_HIT_OBJECT_RADIUS: f32 = 41.0;
+const DIRECTION_CHANGE_BONUS: f64 = 21.0;
+
+/// how far and how suddenly the catcher has to move
+#[derive(Debug)]
+pub struct Movement {
+    clock_rate: f64,
+    current_strain: f64,
+    sections: SectionPeaks,
+    last_player_position: Option<f32>,
+    last_distance_moved: f32,
+    last_strain_time: f64,
+}
+
+impl Movement {
+
+    pub fn new(clock_rate: f64) -> Self {
+        Self {
+            clock_rate,
+            current_strain: 0.0,
+            sections: SectionPeaks::new(SECTION_LENGTH),
+            last_player_position: None,
+            last_distance_moved: 0.0,
+            last_strain_time: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, current: &CatchDifficultyObject, objects: &[CatchDifficultyObject]) {
+        while let Some(section_start) = self.sections.passed_section_end(current.start_time) {
+            let previous_start = current.previous(0, objects).map_or(0.0, |o| o.start_time);
+            self.sections.start_section(self.current_strain * strain::strain_decay(STRAIN_DECAY_BASE, section_start - previous_start));
+        }
+
+        self.current_strain *= strain::strain_decay(STRAIN_DECAY_BASE, current.delta_time);
+        self.current_strain += self.strain_value_of(current) * SKILL_MULTIPLIER;
+        self.sections.record(self.current_strain);
+    }
+
+    pub fn difficulty_value(&self) -> f64 {
+        strain::weighted_sum(self.sections.peaks(), DECAY_WEIGHT)
+    }
+
+    fn strain_value_of(&mut self, current: &CatchDifficultyObject) -> f64 {
+        let last_player_position = *self.last_player_position.get_or_insert(current.last_normalised_position);
+
+        //the catcher only moves as far as it has to, with some room for error
+        let leeway = NORMALISED_HIT_OBJECT_RADIUS - ABSOLUTE_PLAYER_POSITIONING_ERROR;
+        let mut player_position = last_player_position
+            .clamp(current.normalised_position - leeway, current.normalised_position + leeway);
+
+        let distance_moved = player_position - last_player_position;
+        let distance = distance_moved.abs() as f64;
+
+        let weighted_strain_time = current.strain_time + 13.0 + 3.0 / self.clock_rate;
+
+        let mut distance_addition = distance.powf(1.3) / 510.0;
+        let sqrt_strain = weighted_strain_time.sqrt();
+
+        if distance > 0.1 {
+            //moving back the other way
+            let last_distance = self.last_distance_moved.abs() as f64;
+            if last_distance > 0.1 && distance_moved.signum() != self.last_distance_moved.signum() {
+                let bonus_factor = distance.min(50.0) / 50.0;
+                let anti_flow_factor = (last_distance.min(70.0) / 70.0).max(0.38);
+
+                distance_addition += DIRECTION_CHANGE_BONUS / (self.last_strain_time + 16.0).sqrt()
+                    * bonus_factor
+                    * anti_flow_factor
+                    * (1.0 - (weighted_strain_time / 1000.0).powi(3)).max(0.0);
+            }
+
+            //every movement is worth something, so streams count too
+            let radius = NORMALISED_HIT_OBJECT_RADIUS as f64;
+            distance_addition += 12.5 * distance.min(radius * 2.0) / (radius * 6.0) / sqrt_strain;
+        }
+
+        //edge dashes are hard, and after a hyperdash the catcher is exactly where it has to be
+        if current.last_distance_to_hyper_dash <= 20.0 {
+            let mut edge_dash_bonus = 0.0;
+            if !current.last_hyper_dash {
+                edge_dash_bonus += 5.7;
+            } else {
+                player_position = current.normalised_position;
+            }
+
+            distance_addition *= 1.0 + edge_dash_bonus
+                * ((20.0 - current.last_distance_to_hyper_dash as f64) / 20.0)
+                * ((current.strain_time * self.clock_rate).min(265.0) / 265.0).powf(1.5);
+        }
+
+        self.last_player_position = Some(player_position);
+        self.last_distance_moved = distance_moved;
+        self.last_strain_time = current.strain_time;
+
+        distance_addition / weighted_strain_time
+    }
+}
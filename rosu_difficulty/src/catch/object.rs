@@ -0,0 +1,50 @@
+use rosu_parser::beatmap::catch::CatchObject;
+
+/// everything in a catch map is scaled so that objects have this radius
+const NORMALISED_HIT_OBJECT_RADIUS: f32 = 41.0;
+
+/// an object and the one before it, scaled so every circle size plays the same, at the clock rate
+#[derive(Debug, Clone)]
+pub struct CatchDifficultyObject {
+    pub index: usize,
+    pub start_time: f64,
+    pub delta_time: f64,
+    pub strain_time: f64, //at least 40 ms
+    pub normalised_position: f32,
+    pub last_normalised_position: f32,
+    pub last_hyper_dash: bool,
+    pub last_distance_to_hyper_dash: f32,
+}
+
+impl CatchDifficultyObject {
+
+    /// `objects` are only the fruits and droplets
+    pub fn create_all(objects: &[CatchObject], clock_rate: f64, half_catcher_width: f64) -> Vec<CatchDifficultyObject> {
+        let scaling_factor = NORMALISED_HIT_OBJECT_RADIUS / half_catcher_width as f32;
+
+        objects.windows(2)
+            .enumerate()
+            .map(|(index, window)| {
+                let (last, current) = (&window[0], &window[1]);
+                let delta_time = (current.start_time - last.start_time) / clock_rate;
+
+                CatchDifficultyObject {
+                    index,
+                    start_time: current.start_time / clock_rate,
+                    delta_time,
+                    strain_time: delta_time.max(40.0),
+                    normalised_position: current.x * scaling_factor,
+                    last_normalised_position: last.x * scaling_factor,
+                    last_hyper_dash: last.hyper_dash,
+                    last_distance_to_hyper_dash: last.distance_to_hyper_dash,
+                }
+            })
+            .collect()
+    }
+
+    /// the difficulty object `n` before this one
+    pub fn previous<'a>(&self, n: usize, objects: &'a [CatchDifficultyObject]) -> Option<&'a CatchDifficultyObject> {
+        let i = self.index.checked_sub(n + 1)?;
+        objects.get(i)
+    }
+}
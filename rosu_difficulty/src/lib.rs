@@ -0,0 +1,89 @@
+//star rating of every mode, and performance of osu!standard scores
+use rosu_parser::{beatmap::{Beatmap, sections::GameMode}, mods::Mods};
+
+use self::{
+    catch::{CatchDifficultyAttributes, CatchStars},
+    mania::{ManiaDifficultyAttributes, ManiaStars},
+    osu::{OsuDifficultyAttributes, OsuStars},
+    taiko::{TaikoDifficultyAttributes, TaikoStars},
+};
+
+pub mod strain;
+pub mod osu;
+pub mod taiko;
+pub mod catch;
+pub mod mania;
+
+/// star rating of a map in whichever mode it was calculated for
+#[derive(Debug, Clone, PartialEq)]
+pub enum DifficultyAttributes {
+    Osu(OsuDifficultyAttributes),
+    Taiko(TaikoDifficultyAttributes),
+    Catch(CatchDifficultyAttributes),
+    Mania(ManiaDifficultyAttributes),
+}
+
+impl DifficultyAttributes {
+
+    pub fn stars(&self) -> f64 {
+        match self {
+            DifficultyAttributes::Osu(attributes) => attributes.stars,
+            DifficultyAttributes::Taiko(attributes) => attributes.stars,
+            DifficultyAttributes::Catch(attributes) => attributes.stars,
+            DifficultyAttributes::Mania(attributes) => attributes.stars,
+        }
+    }
+
+    pub fn mode(&self) -> GameMode {
+        match self {
+            DifficultyAttributes::Osu(_) => GameMode::Osu,
+            DifficultyAttributes::Taiko(_) => GameMode::Taiko,
+            DifficultyAttributes::Catch(_) => GameMode::Catch,
+            DifficultyAttributes::Mania(_) => GameMode::Mania,
+        }
+    }
+}
+
+/// star rating calculation in the map's own mode, or for an osu!standard map converted to another
+pub struct Stars<'a> {
+    beatmap: &'a Beatmap,
+    mods: Mods,
+    mode: Option<GameMode>,
+}
+
+impl<'a> Stars<'a> {
+
+    pub fn new(beatmap: &'a Beatmap) -> Self {
+        Self {
+            beatmap,
+            mods: Mods::empty(),
+            mode: None,
+        }
+    }
+
+    pub fn mods(mut self, mods: Mods) -> Self {
+        self.mods = mods;
+        self
+    }
+
+    /// only osu!standard maps can be converted, other maps stay in their own mode
+    pub fn mode(mut self, mode: GameMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    pub fn calculate(&self) -> DifficultyAttributes {
+        let map_mode = self.beatmap.mode();
+        let mode = match self.mode {
+            Some(mode) if map_mode == GameMode::Osu => mode,
+            _ => map_mode,
+        };
+
+        match mode {
+            GameMode::Osu => DifficultyAttributes::Osu(OsuStars::new(self.beatmap).mods(self.mods).calculate()),
+            GameMode::Taiko => DifficultyAttributes::Taiko(TaikoStars::new(self.beatmap).mods(self.mods).calculate()),
+            GameMode::Catch => DifficultyAttributes::Catch(CatchStars::new(self.beatmap).mods(self.mods).calculate()),
+            GameMode::Mania => DifficultyAttributes::Mania(ManiaStars::new(self.beatmap).mods(self.mods).calculate()),
+        }
+    }
+}
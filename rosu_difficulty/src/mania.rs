@@ -0,0 +1,100 @@
+use rosu_parser::{beatmap::{Beatmap, sections::GameMode}, mods::Mods};
+
+use self::{object::ManiaDifficultyObject, strain::Strain};
+
+pub mod object;
+pub mod strain;
+
+const STAR_SCALING_FACTOR: f64 = 0.018;
+
+/// star rating of a mania map and what went into it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ManiaDifficultyAttributes {
+    pub stars: f64,
+    pub great_hit_window: f64, //rate adjusted
+    pub key_count: usize,
+    pub n_notes: usize,
+    pub n_holds: usize,
+    pub is_convert: bool,
+}
+
+/// star rating calculation for mania maps and osu!standard maps played in mania
+pub struct ManiaStars<'a> {
+    beatmap: &'a Beatmap,
+    mods: Mods,
+}
+
+impl<'a> ManiaStars<'a> {
+
+    pub fn new(beatmap: &'a Beatmap) -> Self {
+        Self {
+            beatmap,
+            mods: Mods::empty(),
+        }
+    }
+
+    pub fn mods(mut self, mods: Mods) -> Self {
+        self.mods = mods;
+        self
+    }
+
+    pub fn calculate(&self) -> ManiaDifficultyAttributes {
+        let is_convert = self.beatmap.mode() != GameMode::Mania;
+
+        let beatmap = self.beatmap.convert(GameMode::Mania, self.mods)
+            .unwrap_or_else(|| self.beatmap.clone());
+
+        let mut map_attributes = beatmap.difficulty_attributes();
+        map_attributes.mode = GameMode::Mania;
+        let key_count = object::key_count(&map_attributes);
+        let od = map_attributes.overall_difficulty;
+
+        let map_attributes = map_attributes.with_mods(self.mods);
+        let clock_rate = map_attributes.clock_rate;
+
+        let objects = object::mania_objects(&beatmap, key_count);
+        let difficulty_objects = ManiaDifficultyObject::create_all(&objects, clock_rate);
+
+        let mut strain = Strain::new(key_count);
+        for current in &difficulty_objects {
+            strain.process(current, &difficulty_objects);
+        }
+
+        let great_hit_window = great_hit_window(od, is_convert, self.mods, clock_rate);
+
+        ManiaDifficultyAttributes {
+            stars: strain.difficulty_value() * STAR_SCALING_FACTOR,
+            great_hit_window,
+            key_count,
+            n_notes: objects.iter().filter(|o| !o.is_hold()).count(),
+            n_holds: objects.iter().filter(|o| o.is_hold()).count(),
+            is_convert,
+        }
+    }
+}
+
+/// the 300 window the way the game's calculator works it out. converts get fixed windows,
+/// HR and EZ scale the window instead of od, and so do DT and HT before the rate is taken out again
+fn great_hit_window(od: f64, is_convert: bool, mods: Mods, clock_rate: f64) -> f64 {
+    let mut window = match is_convert {
+        true if od.round_ties_even() > 4.0 => 34.0,
+        true => 47.0,
+        false => 34.0 + 3.0 * (10.0 - od).clamp(0.0, 10.0),
+    };
+
+    if mods.contains(Mods::HARD_ROCK) {
+        window /= 1.4;
+    } else if mods.contains(Mods::EASY) {
+        window *= 1.4;
+    }
+
+    //nightcore always comes with DT
+    if mods.contains(Mods::DOUBLE_TIME) {
+        window *= 1.5;
+    } else if mods.contains(Mods::HALF_TIME) {
+        window *= 0.75;
+    }
+
+    //the game truncated the window at the rate to whole ms
+    (((window * clock_rate) as i64) as f64 / clock_rate).ceil()
+}
@@ -0,0 +1,102 @@
+use rosu_parser::beatmap::{
+    Beatmap,
+    attributes::DifficultyAttributes,
+    pos::PLAYFIELD_WIDTH,
+    sections::ObjectParams,
+    slider::Slider,
+};
+
+use rosu_parser::sort;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ManiaObject {
+    pub column: usize,
+    pub start_time: f64,
+    pub end_time: f64, //same as the start for notes
+    hold: bool,
+}
+
+impl ManiaObject {
+
+    pub fn is_hold(&self) -> bool {
+        self.hold
+    }
+}
+
+/// the key count is the circle size, converts have theirs picked by the converter
+pub fn key_count(attributes: &DifficultyAttributes) -> usize {
+    attributes.circle_size.round_ties_even().max(1.0) as usize
+}
+
+/// notes and holds in the order the game sorts them, by start time and then with its legacy sort
+/// on the rounded start time.
+///
+/// converts have to be converted first, the column comes from the x position
+pub fn mania_objects(beatmap: &Beatmap, key_count: usize) -> Vec<ManiaObject> {
+    let timing = beatmap.timing_map();
+    let column_width = PLAYFIELD_WIDTH as f64 / key_count as f64;
+
+    let mut objects: Vec<ManiaObject> = beatmap.hit_objects.iter()
+        .flatten()
+        .map(|hit_object| {
+            let start_time = hit_object.time as f64;
+            let column = ((hit_object.x as f64 / column_width).floor() as usize).min(key_count - 1);
+
+            let end_time = match &hit_object.object_params {
+                ObjectParams::None => None,
+                ObjectParams::EndTime(end_time) => Some(*end_time as f64),
+                ObjectParams::Slider(_) => Slider::new(beatmap, &timing, hit_object)
+                    .map(|slider| slider.timing.end_time()),
+            };
+
+            ManiaObject {
+                column,
+                start_time,
+                end_time: end_time.unwrap_or(start_time),
+                hold: end_time.is_some(),
+            }
+        })
+        .collect();
+
+    objects.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    sort::legacy_sort(&mut objects, |a, b| {
+        (a.start_time.round_ties_even() as i64).cmp(&(b.start_time.round_ties_even() as i64))
+    });
+    objects
+}
+
+/// an object and the one before it, at the clock rate
+#[derive(Debug, Clone)]
+pub struct ManiaDifficultyObject {
+    pub index: usize,
+    pub column: usize,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub delta_time: f64,
+}
+
+impl ManiaDifficultyObject {
+
+    pub fn create_all(objects: &[ManiaObject], clock_rate: f64) -> Vec<ManiaDifficultyObject> {
+        objects.windows(2)
+            .enumerate()
+            .map(|(index, window)| {
+                let (last, current) = (&window[0], &window[1]);
+
+                ManiaDifficultyObject {
+                    index,
+                    column: current.column,
+                    start_time: current.start_time / clock_rate,
+                    end_time: current.end_time / clock_rate,
+                    delta_time: (current.start_time - last.start_time) / clock_rate,
+                }
+            })
+            .collect()
+    }
+
+    /// the difficulty object `n` before this one
+    pub fn previous<'a>(&self, n: usize, objects: &'a [ManiaDifficultyObject]) -> Option<&'a ManiaDifficultyObject> {
+        let i = self.index.checked_sub(n + 1)?;
+        objects.get(i)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::strain::{self, SectionPeaks};
+
+use super::object::ManiaDifficultyObject;
+
+const INDIVIDUAL_DECAY_BASE: f64 = 0.125;
+const OVERALL_DECAY_BASE: f64 = 0.3;
+/// releases further apart than this are hard to time together
+const RELEASE_THRESHOLD: f64 = 24.0;
+
+/// how hard the notes are for each finger and for both hands together
+#[derive(Debug)]
+pub struct Strain {
+    sections: SectionPeaks,
+    start_times: Vec<f64>,
+    end_times: Vec<f64>,
+    individual_strains: Vec<f64>,
+    individual_strain: f64,
+    overall_strain: f64,
+}
+
+impl Strain {
+
+    pub fn new(columns: usize) -> Self {
+        Self {
+            sections: SectionPeaks::default(),
+            start_times: vec![0.0; columns],
+            end_times: vec![0.0; columns],
+            individual_strains: vec![0.0; columns],
+            individual_strain: 0.0,
+            overall_strain: 1.0,
+        }
+    }
+
+    pub fn process(&mut self, current: &ManiaDifficultyObject, objects: &[ManiaDifficultyObject]) {
+        while let Some(section_start) = self.sections.passed_section_end(current.start_time) {
+            let previous_start = current.previous(0, objects).map_or(0.0, |o| o.start_time);
+            let offset = section_start - previous_start;
+            self.sections.start_section(
+                self.individual_strain * strain::strain_decay(INDIVIDUAL_DECAY_BASE, offset)
+                    + self.overall_strain * strain::strain_decay(OVERALL_DECAY_BASE, offset)
+            );
+        }
+
+        let strain = self.strain_value_of(current);
+        self.sections.record(strain);
+    }
+
+    pub fn difficulty_value(&self) -> f64 {
+        strain::weighted_sum(self.sections.peaks(), strain::DECAY_WEIGHT)
+    }
+
+    fn strain_value_of(&mut self, current: &ManiaDifficultyObject) -> f64 {
+        let start_time = current.start_time;
+        let end_time = current.end_time;
+        let column = current.column;
+
+        let mut is_overlapping = false;
+        let mut closest_end_time = (end_time - start_time).abs();
+        let mut hold_factor = 1.0; //something else is being held
+        let mut hold_addition = 0.0; //this hold has to be released awkwardly
+
+        for &other_end in &self.end_times {
+            is_overlapping |= other_end - start_time > 1.0 && end_time - other_end > 1.0;
+
+            if other_end - end_time > 1.0 {
+                hold_factor = 1.25;
+            }
+
+            closest_end_time = closest_end_time.min((end_time - other_end).abs());
+        }
+
+        //releasing several notes together is as easy as releasing one
+        if is_overlapping {
+            hold_addition = 1.0 / (1.0 + (0.5 * (RELEASE_THRESHOLD - closest_end_time)).exp());
+        }
+
+        self.individual_strains[column] *= strain::strain_decay(INDIVIDUAL_DECAY_BASE, start_time - self.start_times[column]);
+        self.individual_strains[column] += 2.0 * hold_factor;
+
+        //chords count as hard as their hardest column
+        self.individual_strain = if current.delta_time <= 1.0 {
+            self.individual_strain.max(self.individual_strains[column])
+        } else {
+            self.individual_strains[column]
+        };
+
+        self.overall_strain *= strain::strain_decay(OVERALL_DECAY_BASE, current.delta_time);
+        self.overall_strain += (1.0 + hold_addition) * hold_factor;
+
+        self.start_times[column] = start_time;
+        self.end_times[column] = end_time;
+
+        self.individual_strain + self.overall_strain
+    }
+}
@@ -0,0 +1,148 @@
+use rosu_parser::{beatmap::{Beatmap, attributes::DifficultyAttributes}, mods::Mods};
+
+use self::{
+    aim::Aim,
+    flashlight::Flashlight,
+    object::DifficultyObject,
+    performance::PERFORMANCE_BASE_MULTIPLIER,
+    speed::Speed,
+    strain::StrainSkill,
+};
+
+pub mod object;
+pub mod strain;
+pub mod aim;
+pub mod speed;
+pub mod flashlight;
+pub mod performance;
+
+const DIFFICULTY_MULTIPLIER: f64 = 0.0675;
+
+/// star rating of an osu!standard map and what went into it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OsuDifficultyAttributes {
+    pub stars: f64,
+    pub aim: f64,
+    pub speed: f64,
+    pub flashlight: f64, //0 without FL
+    pub slider_factor: f64, //aim without sliders relative to aim with them
+    pub speed_note_count: f64,
+    pub approach_rate: f64, //rate adjusted
+    pub overall_difficulty: f64, //rate adjusted
+    pub hp_drain_rate: f64,
+    pub max_combo: usize,
+    pub n_circles: usize,
+    pub n_sliders: usize,
+    pub n_spinners: usize,
+}
+
+/// star rating calculation, with mods if any are set
+pub struct OsuStars<'a> {
+    beatmap: &'a Beatmap,
+    mods: Mods,
+}
+
+impl<'a> OsuStars<'a> {
+
+    pub fn new(beatmap: &'a Beatmap) -> Self {
+        Self {
+            beatmap,
+            mods: Mods::empty(),
+        }
+    }
+
+    pub fn mods(mut self, mods: Mods) -> Self {
+        self.mods = mods;
+        self
+    }
+
+    pub fn calculate(&self) -> OsuDifficultyAttributes {
+        let mods = self.mods;
+
+        let mut beatmap = self.beatmap.clone();
+        beatmap.apply_mods(mods);
+
+        let map_attributes = beatmap.difficulty_attributes();
+        let clock_rate = mods.clock_rate();
+        let great_window = map_attributes.hit_windows()
+            .map_or(0.0, |windows| windows.great);
+
+        let hidden = mods.contains(Mods::HIDDEN);
+        let objects = object::osu_objects(&beatmap, &map_attributes, hidden);
+        let difficulty_objects = DifficultyObject::create_all(&objects, clock_rate, great_window);
+
+        let mut aim = Aim::new(true);
+        let mut aim_no_sliders = Aim::new(false);
+        let mut speed = Speed::default();
+        let mut flashlight = Flashlight::new(hidden);
+
+        for current in &difficulty_objects {
+            aim.process(current, &difficulty_objects);
+            aim_no_sliders.process(current, &difficulty_objects);
+            speed.process(current, &difficulty_objects);
+            flashlight.process(current, &difficulty_objects);
+        }
+
+        let mut aim_rating = aim.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER;
+        let aim_rating_no_sliders = aim_no_sliders.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER;
+        let mut speed_rating = speed.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER;
+        let speed_note_count = speed.relevant_note_count();
+
+        let mut flashlight_rating = 0.0;
+        if mods.contains(Mods::FLASHLIGHT) {
+            flashlight_rating = flashlight.difficulty_value().sqrt() * DIFFICULTY_MULTIPLIER;
+        }
+
+        let slider_factor = if aim_rating > 0.0 { aim_rating_no_sliders / aim_rating } else { 1.0 };
+
+        if mods.contains(Mods::TOUCH_DEVICE) {
+            aim_rating = aim_rating.powf(0.8);
+            flashlight_rating = flashlight_rating.powf(0.8);
+        }
+
+        if mods.contains(Mods::RELAX) {
+            aim_rating *= 0.9;
+            speed_rating = 0.0;
+            flashlight_rating *= 0.7;
+        }
+
+        let base_aim_performance = (5.0 * (aim_rating / 0.0675).max(1.0) - 4.0).powi(3) / 100000.0;
+        let base_speed_performance = (5.0 * (speed_rating / 0.0675).max(1.0) - 4.0).powi(3) / 100000.0;
+        let mut base_flashlight_performance = 0.0;
+        if mods.contains(Mods::FLASHLIGHT) {
+            base_flashlight_performance = flashlight_rating.powi(2) * 25.0;
+        }
+
+        let base_performance = (base_aim_performance.powf(1.1)
+            + base_speed_performance.powf(1.1)
+            + base_flashlight_performance.powf(1.1))
+            .powf(1.0 / 1.1);
+
+        let stars = if base_performance > 0.00001 {
+            PERFORMANCE_BASE_MULTIPLIER.cbrt() * 0.027 * ((100000.0 / 2.0_f64.powf(1.0 / 1.1) * base_performance).cbrt() + 4.0)
+        } else {
+            0.0
+        };
+
+        let rate_attributes = DifficultyAttributes {
+            clock_rate,
+            ..map_attributes
+        };
+
+        OsuDifficultyAttributes {
+            stars,
+            aim: aim_rating,
+            speed: speed_rating,
+            flashlight: flashlight_rating,
+            slider_factor,
+            speed_note_count,
+            approach_rate: rate_attributes.effective_approach_rate(),
+            overall_difficulty: rate_attributes.effective_overall_difficulty(),
+            hp_drain_rate: map_attributes.hp_drain_rate,
+            max_combo: objects.iter().map(|o| o.combo).sum(),
+            n_circles: objects.iter().filter(|o| !o.is_slider() && !o.is_spinner()).count(),
+            n_sliders: objects.iter().filter(|o| o.is_slider()).count(),
+            n_spinners: objects.iter().filter(|o| o.is_spinner()).count(),
+        }
+    }
+}
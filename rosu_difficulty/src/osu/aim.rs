@@ -0,0 +1,149 @@
+use std::f64::consts::PI;
+
+use super::{object::DifficultyObject, strain::{self, SectionPeaks, StrainSkill}};
+
+const SKILL_MULTIPLIER: f64 = 23.55;
+const STRAIN_DECAY_BASE: f64 = 0.15;
+
+const WIDE_ANGLE_MULTIPLIER: f64 = 1.5;
+const ACUTE_ANGLE_MULTIPLIER: f64 = 1.95;
+const SLIDER_MULTIPLIER: f64 = 1.35;
+const VELOCITY_CHANGE_MULTIPLIER: f64 = 0.75;
+
+/// how hard the cursor movement is, optionally counting the movement inside sliders
+#[derive(Debug, Default)]
+pub struct Aim {
+    with_sliders: bool,
+    current_strain: f64,
+    sections: SectionPeaks,
+}
+
+impl Aim {
+
+    pub fn new(with_sliders: bool) -> Self {
+        Self {
+            with_sliders,
+            ..Default::default()
+        }
+    }
+
+    pub fn difficulty_value(&self) -> f64 {
+        strain::difficulty_value(&self.sections, 10, 1.06)
+    }
+}
+
+impl StrainSkill for Aim {
+
+    fn section_peaks(&mut self) -> &mut SectionPeaks {
+        &mut self.sections
+    }
+
+    fn strain_value_at(&mut self, current: &DifficultyObject, objects: &[DifficultyObject]) -> f64 {
+        self.current_strain *= strain::strain_decay(STRAIN_DECAY_BASE, current.delta_time);
+        self.current_strain += evaluate(current, objects, self.with_sliders) * SKILL_MULTIPLIER;
+        self.current_strain
+    }
+
+    fn initial_strain(&self, time: f64, current: &DifficultyObject, objects: &[DifficultyObject]) -> f64 {
+        let previous_start = current.previous(0, objects).map_or(0.0, |o| o.start_time);
+        self.current_strain * strain::strain_decay(STRAIN_DECAY_BASE, time - previous_start)
+    }
+}
+
+fn evaluate(current: &DifficultyObject, objects: &[DifficultyObject], with_sliders: bool) -> f64 {
+    let (last, last_last) = match (current.previous(0, objects), current.previous(1, objects)) {
+        (Some(last), Some(last_last)) => (last, last_last),
+        _ => return 0.0,
+    };
+
+    if current.base.is_spinner() || last.base.is_spinner() {
+        return 0.0;
+    }
+
+    //sliders are as hard as the faster of jumping or following them into the next object
+    let mut curr_velocity = current.lazy_jump_distance / current.strain_time;
+    if last.base.is_slider() && with_sliders {
+        let travel_velocity = last.travel_distance / last.travel_time;
+        let movement_velocity = current.minimum_jump_distance / current.minimum_jump_time;
+        curr_velocity = curr_velocity.max(movement_velocity + travel_velocity);
+    }
+
+    let mut prev_velocity = last.lazy_jump_distance / last.strain_time;
+    if last_last.base.is_slider() && with_sliders {
+        let travel_velocity = last_last.travel_distance / last_last.travel_time;
+        let movement_velocity = last.minimum_jump_distance / last.minimum_jump_time;
+        prev_velocity = prev_velocity.max(movement_velocity + travel_velocity);
+    }
+
+    let mut wide_angle_bonus = 0.0;
+    let mut acute_angle_bonus = 0.0;
+    let mut velocity_change_bonus = 0.0;
+
+    let mut aim_strain = curr_velocity;
+
+    //angles only matter when the rhythm stays the same
+    if current.strain_time.max(last.strain_time) < 1.25 * current.strain_time.min(last.strain_time) {
+        if let (Some(curr_angle), Some(last_angle), Some(last_last_angle)) = (current.angle, last.angle, last_last.angle) {
+            let angle_bonus = curr_velocity.min(prev_velocity);
+
+            wide_angle_bonus = wide_angle(curr_angle);
+            acute_angle_bonus = acute_angle(curr_angle);
+
+            //only buff acute angles at high bpm, and not for overlapping notes
+            if current.strain_time > 100.0 {
+                acute_angle_bonus = 0.0;
+            } else {
+                acute_angle_bonus *= acute_angle(last_angle)
+                    * angle_bonus.min(125.0 / current.strain_time)
+                    * (PI / 2.0 * ((100.0 - current.strain_time) / 25.0).min(1.0)).sin().powi(2)
+                    * (PI / 2.0 * (current.lazy_jump_distance.clamp(50.0, 100.0) - 50.0) / 50.0).sin().powi(2);
+            }
+
+            //repeating the same angle is easier
+            wide_angle_bonus *= angle_bonus * (1.0 - wide_angle_bonus.min(wide_angle(last_angle).powi(3)));
+            acute_angle_bonus *= 0.5 + 0.5 * (1.0 - acute_angle_bonus.min(acute_angle(last_last_angle).powi(3)));
+        }
+    }
+
+    if prev_velocity.max(curr_velocity) != 0.0 {
+        //use the jump distances without slider bonuses, sliders are rewarded on their own
+        prev_velocity = (last.lazy_jump_distance + last_last.travel_distance) / last.strain_time;
+        curr_velocity = (current.lazy_jump_distance + last.travel_distance) / current.strain_time;
+
+        let dist_ratio = (PI / 2.0 * (prev_velocity - curr_velocity).abs() / prev_velocity.max(curr_velocity))
+            .sin()
+            .powi(2);
+
+        //overlapping notes get less of a bonus
+        let overlap_velocity_buff = (125.0 / current.strain_time.min(last.strain_time))
+            .min((prev_velocity - curr_velocity).abs());
+
+        velocity_change_bonus = overlap_velocity_buff * dist_ratio;
+
+        //penalise rhythm changes
+        velocity_change_bonus *= (current.strain_time.min(last.strain_time) / current.strain_time.max(last.strain_time)).powi(2);
+    }
+
+    let slider_bonus = if last.base.is_slider() {
+        last.travel_distance / last.travel_time
+    } else {
+        0.0
+    };
+
+    aim_strain += (acute_angle_bonus * ACUTE_ANGLE_MULTIPLIER)
+        .max(wide_angle_bonus * WIDE_ANGLE_MULTIPLIER + velocity_change_bonus * VELOCITY_CHANGE_MULTIPLIER);
+
+    if with_sliders {
+        aim_strain += slider_bonus * SLIDER_MULTIPLIER;
+    }
+
+    aim_strain
+}
+
+fn wide_angle(angle: f64) -> f64 {
+    (3.0 / 4.0 * (angle.clamp(PI / 6.0, 5.0 / 6.0 * PI) - PI / 6.0)).sin().powi(2)
+}
+
+fn acute_angle(angle: f64) -> f64 {
+    1.0 - wide_angle(angle)
+}
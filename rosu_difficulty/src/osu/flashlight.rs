@@ -0,0 +1,123 @@
+use super::object::DifficultyObject;
+use super::strain::{self, SectionPeaks, StrainSkill};
+
+const SKILL_MULTIPLIER: f64 = 0.052;
+const STRAIN_DECAY_BASE: f64 = 0.15;
+
+const MAX_OPACITY_BONUS: f64 = 0.4;
+const HIDDEN_BONUS: f64 = 0.2;
+const MIN_VELOCITY: f64 = 0.5;
+const SLIDER_MULTIPLIER: f64 = 1.3;
+const MIN_ANGLE_MULTIPLIER: f64 = 0.2;
+
+/// how hard the map is to read through the flashlight, from the recent objects' distances
+#[derive(Debug, Default)]
+pub struct Flashlight {
+    hidden: bool,
+    current_strain: f64,
+    sections: SectionPeaks,
+}
+
+impl Flashlight {
+
+    pub fn new(hidden: bool) -> Self {
+        Self {
+            hidden,
+            ..Default::default()
+        }
+    }
+
+    /// every section counts fully, long maps are harder to memorise
+    pub fn difficulty_value(&self) -> f64 {
+        self.sections.peaks().sum::<f64>() * 1.06
+    }
+}
+
+impl StrainSkill for Flashlight {
+
+    fn section_peaks(&mut self) -> &mut SectionPeaks {
+        &mut self.sections
+    }
+
+    fn strain_value_at(&mut self, current: &DifficultyObject, objects: &[DifficultyObject]) -> f64 {
+        self.current_strain *= strain::strain_decay(STRAIN_DECAY_BASE, current.delta_time);
+        self.current_strain += evaluate(current, objects, self.hidden) * SKILL_MULTIPLIER;
+        self.current_strain
+    }
+
+    fn initial_strain(&self, time: f64, current: &DifficultyObject, objects: &[DifficultyObject]) -> f64 {
+        let previous_start = current.previous(0, objects).map_or(0.0, |o| o.start_time);
+        self.current_strain * strain::strain_decay(STRAIN_DECAY_BASE, time - previous_start)
+    }
+}
+
+fn evaluate(current: &DifficultyObject, objects: &[DifficultyObject], hidden: bool) -> f64 {
+    if current.base.is_spinner() {
+        return 0.0;
+    }
+
+    let base = current.base;
+    let scaling_factor = 52.0 / base.radius;
+
+    let mut small_dist_nerf = 1.0;
+    let mut cumulative_strain_time = 0.0;
+    let mut result = 0.0;
+    let mut last = current;
+    let mut angle_repeat_count = 0.0;
+
+    for i in 0..current.index.min(10) {
+        let previous = match current.previous(i, objects) {
+            Some(previous) => previous,
+            None => break,
+        };
+
+        if !previous.base.is_spinner() {
+            let jump_distance = (base.position - previous.base.end_position).length() as f64;
+            cumulative_strain_time += last.strain_time;
+
+            //closer objects are easier to see
+            if i == 0 {
+                small_dist_nerf = (jump_distance / 75.0).min(1.0);
+            }
+
+            //stacked objects are easy to follow
+            let stack_nerf = ((previous.lazy_jump_distance / scaling_factor) / 25.0).min(1.0);
+
+            //objects that have already faded out are harder to remember
+            let opacity_bonus = 1.0 + MAX_OPACITY_BONUS * (1.0 - base.opacity_at(previous.base.start_time, hidden));
+
+            result += stack_nerf * opacity_bonus * scaling_factor * jump_distance / cumulative_strain_time;
+
+            if let (Some(previous_angle), Some(angle)) = (previous.angle, current.angle) {
+                //repeated angles are easy to anticipate
+                if (previous_angle - angle).abs() < 0.02 {
+                    angle_repeat_count += (1.0 - 0.1 * i as f64).max(0.0);
+                }
+            }
+        }
+
+        last = previous;
+    }
+
+    result = (small_dist_nerf * result).powi(2);
+
+    if hidden {
+        result *= 1.0 + HIDDEN_BONUS;
+    }
+
+    result *= MIN_ANGLE_MULTIPLIER + (1.0 - MIN_ANGLE_MULTIPLIER) / (angle_repeat_count + 1.0);
+
+    let mut slider_bonus = 0.0;
+    if let Some(slider) = &base.slider {
+        //fast sliders are harder to follow in the dark
+        let pixel_travel_distance = slider.lazy_travel_distance / scaling_factor;
+        slider_bonus = (pixel_travel_distance / current.travel_time - MIN_VELOCITY).max(0.0).sqrt();
+        slider_bonus *= pixel_travel_distance;
+
+        if slider.repeat_count > 0 {
+            slider_bonus /= (slider.repeat_count + 1) as f64;
+        }
+    }
+
+    result + slider_bonus * SLIDER_MULTIPLIER
+}
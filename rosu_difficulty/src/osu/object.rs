@@ -0,0 +1,312 @@
+use rosu_parser::beatmap::{
+    Beatmap,
+    attributes::DifficultyAttributes,
+    pos::Pos2,
+    sections::{HitObjectKind, ObjectParams},
+    slider::Slider,
+};
+
+/// jumps are measured as if every circle had this radius
+pub const NORMALISED_RADIUS: f64 = 50.0;
+
+/// objects closer together than this are treated as this far apart, in ms
+const MIN_DELTA_TIME: f64 = 25.0;
+
+const MAXIMUM_SLIDER_RADIUS: f64 = NORMALISED_RADIUS * 2.4;
+const ASSUMED_SLIDER_RADIUS: f64 = NORMALISED_RADIUS * 1.8;
+
+/// a hit object with mods and stacking applied, times are still in map time
+#[derive(Debug, Clone)]
+pub struct OsuObject {
+    pub kind: HitObjectKind,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub position: Pos2, //stacked
+    pub end_position: Pos2, //stacked, where a slider really ends
+    pub radius: f64,
+    pub preempt: f64,
+    pub fade_in: f64,
+    pub combo: usize, //sliders also count their ticks, repeats and tail
+    pub slider: Option<SliderCursor>,
+}
+
+/// how little the cursor has to move to follow a slider
+#[derive(Debug, Clone)]
+pub struct SliderCursor {
+    pub lazy_end_position: Pos2,
+    pub lazy_travel_distance: f64,
+    pub lazy_travel_time: f64,
+    pub repeat_count: usize,
+}
+
+impl OsuObject {
+
+    pub fn is_slider(&self) -> bool {
+        matches!(self.kind, HitObjectKind::Slider)
+    }
+
+    pub fn is_spinner(&self) -> bool {
+        matches!(self.kind, HitObjectKind::Spinner)
+    }
+
+    /// how visible the object is at `time`, hidden fades it out again before it's hit
+    pub fn opacity_at(&self, time: f64, hidden: bool) -> f64 {
+        if time > self.start_time {
+            return 0.0;
+        }
+
+        let fade_in_start = self.start_time - self.preempt;
+        let fade_in = ((time - fade_in_start) / self.fade_in).clamp(0.0, 1.0);
+
+        if hidden {
+            let fade_out_start = fade_in_start + self.fade_in;
+            let fade_out = ((time - fade_out_start) / (self.preempt * 0.3)).clamp(0.0, 1.0);
+            return fade_in.min(1.0 - fade_out);
+        }
+        fade_in
+    }
+}
+
+/// objects of a map that already has HR/EZ applied
+pub fn osu_objects(beatmap: &Beatmap, attributes: &DifficultyAttributes, hidden: bool) -> Vec<OsuObject> {
+    let timing = beatmap.timing_map();
+    let stacking = beatmap.stacking();
+
+    let radius = attributes.radius();
+    let preempt = attributes.preempt();
+    let fade_in = if hidden { preempt * 0.4 } else { attributes.fade_in() };
+
+    beatmap.hit_objects.iter()
+        .flatten()
+        .zip(stacking)
+        .map(|(hit_object, stack)| {
+            let head = Pos2::new(hit_object.x as f32, hit_object.y as f32);
+            let stack_offset = stack.position - head;
+            let start_time = hit_object.time as f64;
+
+            let mut object = OsuObject {
+                kind: hit_object.kind,
+                start_time,
+                end_time: start_time,
+                position: stack.position,
+                end_position: stack.position,
+                radius,
+                preempt,
+                fade_in,
+                combo: 1,
+                slider: None,
+            };
+
+            match Slider::new(beatmap, &timing, hit_object) {
+                Some(slider) => {
+                    object.end_time = slider.timing.end_time();
+                    object.end_position = slider.end_position() + stack_offset;
+                    object.combo = 1 + slider.timing.ticks.len() + slider.timing.spans;
+                    object.slider = Some(slider_cursor(&slider, stack_offset, radius));
+                }
+                None => {
+                    if let ObjectParams::EndTime(end_time) = hit_object.object_params {
+                        object.end_time = end_time as f64;
+                    }
+                }
+            }
+
+            object
+        })
+        .collect()
+}
+
+/// follows the slider with the cursor only as far as the follow circle forces it to
+fn slider_cursor(slider: &Slider, stack_offset: Pos2, radius: f64) -> SliderCursor {
+    let timing = &slider.timing;
+
+    //every part of the slider that has to be followed, head first
+    let mut nested: Vec<(f64, Pos2, bool)> = slider.tick_positions()
+        .map(|(time, position)| (time, position, false))
+        .chain(timing.repeat_times().enumerate().map(|(i, time)| {
+            let progress = if i % 2 == 0 { 1.0 } else { 0.0 };
+            (time, slider.path.position_at(progress), true)
+        }))
+        .collect();
+    nested.push((timing.legacy_last_tick_time(), slider.end_position(), false));
+    nested.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let lazy_travel_time = timing.legacy_last_tick_time() - timing.start_time;
+
+    let mut end_progress = lazy_travel_time / timing.span_duration;
+    end_progress = if end_progress % 2.0 >= 1.0 {
+        1.0 - end_progress % 1.0
+    } else {
+        end_progress % 1.0
+    };
+    let mut lazy_end_position = slider.path.position_at(end_progress) + stack_offset;
+
+    let head = slider.path.position_at(0.0) + stack_offset;
+    let mut cursor = head;
+    let mut lazy_travel_distance = 0.0;
+    let scaling_factor = NORMALISED_RADIUS / radius;
+
+    for (i, (_, position, is_repeat)) in nested.iter().enumerate() {
+        let is_tail = i == nested.len() - 1;
+        let mut movement = (*position + stack_offset) - cursor;
+        let mut required_movement = ASSUMED_SLIDER_RADIUS;
+
+        //the tail is judged leniently, so the cursor can stay wherever is closer
+        if is_tail {
+            let lazy_movement = lazy_end_position - cursor;
+            if lazy_movement.length() < movement.length() {
+                movement = lazy_movement;
+            }
+        } else if *is_repeat {
+            required_movement = NORMALISED_RADIUS;
+        }
+
+        let mut movement_length = scaling_factor * movement.length() as f64;
+        if movement_length > required_movement {
+            let fraction = (movement_length - required_movement) / movement_length;
+            cursor += movement * fraction as f32;
+            movement_length *= fraction;
+            lazy_travel_distance += movement_length;
+        }
+
+        if is_tail {
+            lazy_end_position = cursor;
+        }
+    }
+
+    //repeats are worth more until they get strain of their own
+    let repeat_count = timing.spans - 1;
+    lazy_travel_distance *= (1.0 + repeat_count as f64 / 2.5).powf(1.0 / 2.5);
+
+    SliderCursor {
+        lazy_end_position,
+        lazy_travel_distance,
+        lazy_travel_time,
+        repeat_count,
+    }
+}
+
+/// an object and how it relates to the ones before it, at the clock rate
+#[derive(Debug, Clone)]
+pub struct DifficultyObject<'a> {
+    pub base: &'a OsuObject,
+    pub index: usize,
+    pub start_time: f64,
+    pub delta_time: f64,
+    pub strain_time: f64,
+    pub hit_window_great: f64, //both sides
+    pub lazy_jump_distance: f64,
+    pub minimum_jump_distance: f64,
+    pub minimum_jump_time: f64,
+    pub travel_distance: f64,
+    pub travel_time: f64,
+    pub angle: Option<f64>,
+}
+
+impl<'a> DifficultyObject<'a> {
+
+    /// every object from the second on, the first one has nothing to be measured against
+    pub fn create_all(objects: &'a [OsuObject], clock_rate: f64, great_window: f64) -> Vec<Self> {
+        (1..objects.len())
+            .map(|i| {
+                let last_last = if i > 1 { Some(&objects[i - 2]) } else { None };
+                Self::new(&objects[i], &objects[i - 1], last_last, i - 1, clock_rate, great_window)
+            })
+            .collect()
+    }
+
+    fn new(
+        base: &'a OsuObject,
+        last: &OsuObject,
+        last_last: Option<&OsuObject>,
+        index: usize,
+        clock_rate: f64,
+        great_window: f64) -> Self {
+
+        let delta_time = (base.start_time - last.start_time) / clock_rate;
+        let strain_time = delta_time.max(MIN_DELTA_TIME);
+
+        let mut object = Self {
+            base,
+            index,
+            start_time: base.start_time / clock_rate,
+            delta_time,
+            strain_time,
+            hit_window_great: 2.0 * great_window / clock_rate,
+            lazy_jump_distance: 0.0,
+            minimum_jump_distance: 0.0,
+            minimum_jump_time: 0.0,
+            travel_distance: 0.0,
+            travel_time: 0.0,
+            angle: None,
+        };
+        object.set_distances(last, last_last, clock_rate);
+        object
+    }
+
+    fn set_distances(&mut self, last: &OsuObject, last_last: Option<&OsuObject>, clock_rate: f64) {
+        let base = self.base;
+
+        if let Some(slider) = &base.slider {
+            self.travel_distance = slider.lazy_travel_distance;
+            self.travel_time = (slider.lazy_travel_time / clock_rate).max(MIN_DELTA_TIME);
+        }
+
+        if base.is_spinner() || last.is_spinner() {
+            return;
+        }
+
+        let mut scaling_factor = NORMALISED_RADIUS / base.radius;
+        //small circles are harder to hit than their size suggests
+        if base.radius < 30.0 {
+            let small_circle_bonus = (30.0 - base.radius).min(5.0) / 50.0;
+            scaling_factor *= 1.0 + small_circle_bonus;
+        }
+
+        let last_cursor_position = end_cursor_position(last);
+
+        self.lazy_jump_distance = ((base.position - last_cursor_position).length() as f64) * scaling_factor;
+        self.minimum_jump_time = self.strain_time;
+        self.minimum_jump_distance = self.lazy_jump_distance;
+
+        if let Some(last_slider) = &last.slider {
+            let last_travel_time = (last_slider.lazy_travel_time / clock_rate).max(MIN_DELTA_TIME);
+            self.minimum_jump_time = (self.strain_time - last_travel_time).max(MIN_DELTA_TIME);
+
+            //the cursor may have left the slider anywhere in its follow circle
+            let tail_jump_distance = (last.end_position - base.position).length() as f64 * scaling_factor;
+            self.minimum_jump_distance = (self.lazy_jump_distance - (MAXIMUM_SLIDER_RADIUS - ASSUMED_SLIDER_RADIUS))
+                .min(tail_jump_distance - MAXIMUM_SLIDER_RADIUS)
+                .max(0.0);
+        }
+
+        if let Some(last_last) = last_last.filter(|o| !o.is_spinner()) {
+            let last_last_cursor_position = end_cursor_position(last_last);
+
+            let v1 = last_last_cursor_position - last.position;
+            let v2 = base.position - last_cursor_position;
+
+            let dot = v1.dot(v2) as f64;
+            let det = (v1.x * v2.y - v1.y * v2.x) as f64;
+
+            self.angle = Some(det.atan2(dot).abs());
+        }
+    }
+
+    /// the object `i` places before this one
+    pub fn previous<'b>(&self, i: usize, objects: &'b [DifficultyObject<'a>]) -> Option<&'b DifficultyObject<'a>> {
+        self.index.checked_sub(i + 1).map(|index| &objects[index])
+    }
+
+    /// the object `i` places after this one
+    pub fn next<'b>(&self, i: usize, objects: &'b [DifficultyObject<'a>]) -> Option<&'b DifficultyObject<'a>> {
+        objects.get(self.index + i + 1)
+    }
+}
+
+fn end_cursor_position(object: &OsuObject) -> Pos2 {
+    match &object.slider {
+        Some(slider) => slider.lazy_end_position,
+        None => object.position,
+    }
+}
@@ -0,0 +1,429 @@
+use rosu_parser::{beatmap::Beatmap, mods::Mods, replay::Replay};
+
+use super::{OsuDifficultyAttributes, OsuStars};
+
+pub(crate) const PERFORMANCE_BASE_MULTIPLIER: f64 = 1.14;
+
+/// accuracies the player shows pp for
+pub const PROJECTED_ACCURACIES: [f64; 4] = [0.95, 0.98, 0.99, 1.0];
+
+/// judgements and combo of an osu!standard play
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OsuScore {
+    pub n300: usize,
+    pub n100: usize,
+    pub n50: usize,
+    pub n_misses: usize,
+    pub max_combo: usize,
+}
+
+impl OsuScore {
+
+    pub fn from_replay(replay: &Replay) -> Self {
+        Self {
+            n300: replay.n300,
+            n100: replay.n100,
+            n50: replay.n50,
+            n_misses: replay.n_misses,
+            max_combo: replay.max_combo,
+        }
+    }
+
+    /// a play with `n_misses` misses that gets as close to `accuracy` as the object count allows
+    pub fn with_accuracy(accuracy: f64, total_hits: usize, n_misses: usize, max_combo: usize) -> Self {
+        let n_misses = n_misses.min(total_hits);
+        let remaining = total_hits - n_misses;
+        let target = (accuracy.clamp(0.0, 1.0) * (total_hits * 6) as f64).round() as usize;
+
+        //start from all 300s and take away points, 4 for each 100 and 1 more for each 50
+        let missing = (remaining * 6).saturating_sub(target);
+        let mut n100 = ((missing as f64 / 4.0).round() as usize).min(remaining);
+        let mut n50 = 0;
+
+        if n100 == remaining {
+            n50 = missing.saturating_sub(remaining * 4).min(remaining);
+            n100 -= n50;
+        }
+
+        Self {
+            n300: remaining - n100 - n50,
+            n100,
+            n50,
+            n_misses,
+            max_combo,
+        }
+    }
+
+    pub fn total_hits(&self) -> usize {
+        self.n300 + self.n100 + self.n50 + self.n_misses
+    }
+
+    /// from 0 to 1
+    pub fn accuracy(&self) -> f64 {
+        let total = self.total_hits();
+        if total == 0 {
+            return 0.0;
+        }
+        (self.n300 * 6 + self.n100 * 2 + self.n50) as f64 / (total * 6) as f64
+    }
+}
+
+/// pp of a play and what it's made of
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OsuPerformanceAttributes {
+    pub pp: f64,
+    pub aim: f64,
+    pub speed: f64,
+    pub accuracy: f64,
+    pub flashlight: f64,
+    pub effective_miss_count: f64,
+    pub difficulty: OsuDifficultyAttributes,
+}
+
+/// pp calculation for a score on a map, an SS with the given mods if there's no score
+pub struct OsuPerformance<'a> {
+    beatmap: &'a Beatmap,
+    mods: Mods,
+    score: Option<OsuScore>,
+    difficulty: Option<OsuDifficultyAttributes>,
+}
+
+impl<'a> OsuPerformance<'a> {
+
+    pub fn new(beatmap: &'a Beatmap) -> Self {
+        Self {
+            beatmap,
+            mods: Mods::empty(),
+            score: None,
+            difficulty: None,
+        }
+    }
+
+    pub fn mods(mut self, mods: Mods) -> Self {
+        self.mods = mods;
+        self
+    }
+
+    pub fn score(mut self, score: OsuScore) -> Self {
+        self.score = Some(score);
+        self
+    }
+
+    /// the replay's judgements, combo and mods
+    pub fn replay(self, replay: &Replay) -> Self {
+        self.mods(replay.mods).score(OsuScore::from_replay(replay))
+    }
+
+    /// reuses star rating calculated before, it has to be for the same mods
+    pub fn difficulty(mut self, difficulty: OsuDifficultyAttributes) -> Self {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    pub fn calculate(&self) -> OsuPerformanceAttributes {
+        let difficulty = self.difficulty_attributes();
+        let score = self.score.unwrap_or(OsuScore {
+            n300: difficulty.n_circles + difficulty.n_sliders + difficulty.n_spinners,
+            max_combo: difficulty.max_combo,
+            ..Default::default()
+        });
+        calculate(difficulty, self.mods, score)
+    }
+
+    /// the score with its misses turned into 300s and full combo
+    pub fn if_fc(&self) -> OsuPerformanceAttributes {
+        let difficulty = self.difficulty_attributes();
+        let score = self.score.unwrap_or_default();
+        let total_hits = difficulty.n_circles + difficulty.n_sliders + difficulty.n_spinners;
+
+        let fc = OsuScore {
+            n300: total_hits.saturating_sub(score.n100 + score.n50),
+            n100: score.n100,
+            n50: score.n50,
+            n_misses: 0,
+            max_combo: difficulty.max_combo,
+        };
+        calculate(difficulty, self.mods, fc)
+    }
+
+    /// full combo pp at each accuracy
+    pub fn accuracy_projections(&self, accuracies: &[f64]) -> Vec<(f64, OsuPerformanceAttributes)> {
+        let difficulty = self.difficulty_attributes();
+        let total_hits = difficulty.n_circles + difficulty.n_sliders + difficulty.n_spinners;
+
+        accuracies.iter()
+            .map(|accuracy| {
+                let score = OsuScore::with_accuracy(*accuracy, total_hits, 0, difficulty.max_combo);
+                (*accuracy, calculate(difficulty.clone(), self.mods, score))
+            })
+            .collect()
+    }
+
+    fn difficulty_attributes(&self) -> OsuDifficultyAttributes {
+        match &self.difficulty {
+            Some(difficulty) => difficulty.clone(),
+            None => OsuStars::new(self.beatmap).mods(self.mods).calculate(),
+        }
+    }
+}
+
+fn calculate(difficulty: OsuDifficultyAttributes, mods: Mods, score: OsuScore) -> OsuPerformanceAttributes {
+    let total_hits = score.total_hits() as f64;
+    let mut effective_miss_count = effective_miss_count(&difficulty, &score);
+
+    let mut multiplier = PERFORMANCE_BASE_MULTIPLIER;
+
+    if mods.contains(Mods::NO_FAIL) {
+        multiplier *= (1.0 - 0.02 * effective_miss_count).max(0.9);
+    }
+
+    if mods.contains(Mods::SPUN_OUT) && total_hits > 0.0 {
+        multiplier *= 1.0 - (difficulty.n_spinners as f64 / total_hits).powf(0.85);
+    }
+
+    //relax doesn't punish 100s and 50s, so they count as misses on high od
+    if mods.contains(Mods::RELAX) {
+        let od = difficulty.overall_difficulty;
+        let ok_multiplier = if od > 0.0 { (1.0 - (od / 13.33).powf(1.8)).max(0.0) } else { 1.0 };
+        let meh_multiplier = if od > 0.0 { (1.0 - (od / 13.33).powi(5)).max(0.0) } else { 1.0 };
+        effective_miss_count = (effective_miss_count
+            + score.n100 as f64 * ok_multiplier
+            + score.n50 as f64 * meh_multiplier)
+            .min(total_hits);
+    }
+
+    let play = Play {
+        difficulty: &difficulty,
+        mods,
+        score,
+        total_hits,
+        accuracy: score.accuracy(),
+        effective_miss_count,
+    };
+
+    let aim = play.aim_value();
+    let speed = play.speed_value();
+    let accuracy = play.accuracy_value();
+    let flashlight = play.flashlight_value();
+
+    let pp = (aim.powf(1.1) + speed.powf(1.1) + accuracy.powf(1.1) + flashlight.powf(1.1))
+        .powf(1.0 / 1.1)
+        * multiplier;
+
+    OsuPerformanceAttributes {
+        pp,
+        aim,
+        speed,
+        accuracy,
+        flashlight,
+        effective_miss_count,
+        difficulty,
+    }
+}
+
+/// misses plus the slider breaks the combo suggests
+fn effective_miss_count(difficulty: &OsuDifficultyAttributes, score: &OsuScore) -> f64 {
+    let mut combo_based_miss_count = 0.0;
+
+    if difficulty.n_sliders > 0 {
+        let full_combo_threshold = difficulty.max_combo as f64 - 0.1 * difficulty.n_sliders as f64;
+        if (score.max_combo as f64) < full_combo_threshold {
+            combo_based_miss_count = full_combo_threshold / (score.max_combo as f64).max(1.0);
+        }
+    }
+
+    combo_based_miss_count = combo_based_miss_count.min((score.n100 + score.n50 + score.n_misses) as f64);
+    combo_based_miss_count.max(score.n_misses as f64)
+}
+
+struct Play<'a> {
+    difficulty: &'a OsuDifficultyAttributes,
+    mods: Mods,
+    score: OsuScore,
+    total_hits: f64,
+    accuracy: f64,
+    effective_miss_count: f64,
+}
+
+impl Play<'_> {
+
+    fn aim_value(&self) -> f64 {
+        let attributes = self.difficulty;
+        let mut aim_value = difficulty_to_performance(attributes.aim);
+
+        let length_bonus = self.length_bonus();
+        aim_value *= length_bonus;
+
+        if self.effective_miss_count > 0.0 {
+            aim_value *= 0.97 * (1.0 - (self.effective_miss_count / self.total_hits).powf(0.775))
+                .powf(self.effective_miss_count);
+        }
+
+        aim_value *= self.combo_scaling_factor();
+
+        let approach_rate = attributes.approach_rate;
+        let mut approach_rate_factor = 0.0;
+        if approach_rate > 10.33 {
+            approach_rate_factor = 0.3 * (approach_rate - 10.33);
+        } else if approach_rate < 8.0 {
+            approach_rate_factor = 0.05 * (8.0 - approach_rate);
+        }
+        if self.mods.contains(Mods::RELAX) {
+            approach_rate_factor = 0.0;
+        }
+
+        aim_value *= 1.0 + approach_rate_factor * length_bonus;
+
+        if self.mods.contains(Mods::HIDDEN) {
+            aim_value *= 1.0 + 0.04 * (12.0 - approach_rate);
+        }
+
+        //assume 15% of sliders are hard enough to drop their ends
+        let estimate_difficult_sliders = attributes.n_sliders as f64 * 0.15;
+        if attributes.n_sliders > 0 {
+            let estimate_slider_ends_dropped = ((self.score.n100 + self.score.n50 + self.score.n_misses) as f64)
+                .min(attributes.max_combo as f64 - self.score.max_combo as f64)
+                .clamp(0.0, estimate_difficult_sliders);
+            let slider_nerf_factor = (1.0 - attributes.slider_factor)
+                * (1.0 - estimate_slider_ends_dropped / estimate_difficult_sliders).powi(3)
+                + attributes.slider_factor;
+            aim_value *= slider_nerf_factor;
+        }
+
+        aim_value *= self.accuracy;
+        aim_value *= 0.98 + attributes.overall_difficulty.powi(2) / 2500.0;
+
+        aim_value
+    }
+
+    fn speed_value(&self) -> f64 {
+        if self.mods.contains(Mods::RELAX) {
+            return 0.0;
+        }
+
+        let attributes = self.difficulty;
+        let mut speed_value = difficulty_to_performance(attributes.speed);
+
+        let length_bonus = self.length_bonus();
+        speed_value *= length_bonus;
+
+        if self.effective_miss_count > 0.0 {
+            speed_value *= 0.97 * (1.0 - (self.effective_miss_count / self.total_hits).powf(0.775))
+                .powf(self.effective_miss_count.powf(0.875));
+        }
+
+        speed_value *= self.combo_scaling_factor();
+
+        let approach_rate = attributes.approach_rate;
+        let mut approach_rate_factor = 0.0;
+        if approach_rate > 10.33 {
+            approach_rate_factor = 0.3 * (approach_rate - 10.33);
+        }
+
+        speed_value *= 1.0 + approach_rate_factor * length_bonus;
+
+        if self.mods.contains(Mods::HIDDEN) {
+            speed_value *= 1.0 + 0.04 * (12.0 - approach_rate);
+        }
+
+        //accuracy on the notes that are hard to tap, assuming the worst
+        let score = &self.score;
+        let relevant_total_diff = self.total_hits - attributes.speed_note_count;
+        let relevant_n300 = (score.n300 as f64 - relevant_total_diff).max(0.0);
+        let relevant_n100 = (score.n100 as f64 - (relevant_total_diff - score.n300 as f64).max(0.0)).max(0.0);
+        let relevant_n50 = (score.n50 as f64 - (relevant_total_diff - (score.n300 + score.n100) as f64).max(0.0)).max(0.0);
+        let relevant_accuracy = if attributes.speed_note_count == 0.0 {
+            0.0
+        } else {
+            (relevant_n300 * 6.0 + relevant_n100 * 2.0 + relevant_n50) / (attributes.speed_note_count * 6.0)
+        };
+
+        let od = attributes.overall_difficulty;
+        speed_value *= (0.95 + od.powi(2) / 750.0)
+            * ((self.accuracy + relevant_accuracy) / 2.0).powf((14.5 - od.max(8.0)) / 2.0);
+
+        //50s are punished once there are more than a few
+        let n50 = score.n50 as f64;
+        let allowed_n50 = self.total_hits / 500.0;
+        speed_value *= 0.99_f64.powf(if n50 < allowed_n50 { 0.0 } else { n50 - allowed_n50 });
+
+        speed_value
+    }
+
+    fn accuracy_value(&self) -> f64 {
+        if self.mods.contains(Mods::RELAX) {
+            return 0.0;
+        }
+
+        let attributes = self.difficulty;
+        let score = &self.score;
+
+        //only circles are judged on timing, sliders too in score v2
+        let mut objects_with_accuracy = attributes.n_circles;
+        if self.mods.contains(Mods::SCORE_V2) {
+            objects_with_accuracy += attributes.n_sliders;
+        }
+
+        let mut better_accuracy = 0.0;
+        if objects_with_accuracy > 0 {
+            let n300 = score.n300 as f64 - (self.total_hits - objects_with_accuracy as f64);
+            better_accuracy = ((n300 * 6.0 + score.n100 as f64 * 2.0 + score.n50 as f64)
+                / (objects_with_accuracy as f64 * 6.0))
+                .max(0.0);
+        }
+
+        let mut accuracy_value = 1.52163_f64.powf(attributes.overall_difficulty) * better_accuracy.powi(24) * 2.83;
+        accuracy_value *= (objects_with_accuracy as f64 / 1000.0).powf(0.3).min(1.15);
+
+        if self.mods.contains(Mods::HIDDEN) {
+            accuracy_value *= 1.08;
+        }
+        if self.mods.contains(Mods::FLASHLIGHT) {
+            accuracy_value *= 1.02;
+        }
+
+        accuracy_value
+    }
+
+    fn flashlight_value(&self) -> f64 {
+        if !self.mods.contains(Mods::FLASHLIGHT) {
+            return 0.0;
+        }
+
+        let attributes = self.difficulty;
+        let mut flashlight_value = attributes.flashlight.powi(2) * 25.0;
+
+        if self.effective_miss_count > 0.0 {
+            flashlight_value *= 0.97 * (1.0 - (self.effective_miss_count / self.total_hits).powf(0.775))
+                .powf(self.effective_miss_count.powf(0.875));
+        }
+
+        flashlight_value *= self.combo_scaling_factor();
+
+        //longer maps are harder to memorise
+        flashlight_value *= 0.7 + 0.1 * (self.total_hits / 200.0).min(1.0)
+            + if self.total_hits > 200.0 { 0.2 * ((self.total_hits - 200.0) / 200.0).min(1.0) } else { 0.0 };
+
+        flashlight_value *= 0.5 + self.accuracy / 2.0;
+        flashlight_value *= 0.98 + attributes.overall_difficulty.powi(2) / 2500.0;
+
+        flashlight_value
+    }
+
+    fn length_bonus(&self) -> f64 {
+        0.95 + 0.4 * (self.total_hits / 2000.0).min(1.0)
+            + if self.total_hits > 2000.0 { (self.total_hits / 2000.0).log10() * 0.5 } else { 0.0 }
+    }
+
+    fn combo_scaling_factor(&self) -> f64 {
+        let max_combo = self.difficulty.max_combo;
+        if max_combo == 0 {
+            return 1.0;
+        }
+        ((self.score.max_combo as f64).powf(0.8) / (max_combo as f64).powf(0.8)).min(1.0)
+    }
+}
+
+fn difficulty_to_performance(difficulty: f64) -> f64 {
+    (5.0 * (difficulty / 0.0675).max(1.0) - 4.0).powi(3) / 100000.0
+}
@@ -0,0 +1,188 @@
+use std::f64::consts::PI;
+
+use super::object::DifficultyObject;
+use super::strain::{self, SectionPeaks, StrainSkill};
+
+const SKILL_MULTIPLIER: f64 = 1375.0;
+const STRAIN_DECAY_BASE: f64 = 0.3;
+
+const SINGLE_SPACING_THRESHOLD: f64 = 125.0;
+const MIN_SPEED_BONUS: f64 = 75.0; //about 200 bpm 1/4
+const SPEED_BALANCING_FACTOR: f64 = 40.0;
+
+const HISTORY_TIME_MAX: f64 = 5000.0;
+const RHYTHM_MULTIPLIER: f64 = 0.75;
+
+/// how hard the tapping is, from how fast and how irregular it gets
+#[derive(Debug, Default)]
+pub struct Speed {
+    current_strain: f64,
+    current_rhythm: f64,
+    object_strains: Vec<f64>,
+    sections: SectionPeaks,
+}
+
+impl Speed {
+
+    pub fn difficulty_value(&self) -> f64 {
+        strain::difficulty_value(&self.sections, 5, 1.04)
+    }
+
+    /// how many objects count as hard to tap, weighted by their strain
+    pub fn relevant_note_count(&self) -> f64 {
+        let max_strain = self.object_strains.iter().copied().fold(0.0, f64::max);
+        if max_strain == 0.0 {
+            return 0.0;
+        }
+
+        self.object_strains.iter()
+            .map(|strain| 1.0 / (1.0 + (-(strain / max_strain * 12.0 - 6.0)).exp()))
+            .sum()
+    }
+}
+
+impl StrainSkill for Speed {
+
+    fn section_peaks(&mut self) -> &mut SectionPeaks {
+        &mut self.sections
+    }
+
+    fn strain_value_at(&mut self, current: &DifficultyObject, objects: &[DifficultyObject]) -> f64 {
+        self.current_strain *= strain::strain_decay(STRAIN_DECAY_BASE, current.strain_time);
+        self.current_strain += evaluate_speed(current, objects) * SKILL_MULTIPLIER;
+
+        self.current_rhythm = evaluate_rhythm(current, objects);
+
+        let total_strain = self.current_strain * self.current_rhythm;
+        self.object_strains.push(total_strain);
+        total_strain
+    }
+
+    fn initial_strain(&self, time: f64, current: &DifficultyObject, objects: &[DifficultyObject]) -> f64 {
+        let previous_start = current.previous(0, objects).map_or(0.0, |o| o.start_time);
+        (self.current_strain * self.current_rhythm) * strain::strain_decay(STRAIN_DECAY_BASE, time - previous_start)
+    }
+}
+
+fn evaluate_speed(current: &DifficultyObject, objects: &[DifficultyObject]) -> f64 {
+    if current.base.is_spinner() {
+        return 0.0;
+    }
+
+    let mut strain_time = current.strain_time;
+
+    //double tapping two notes is easier the closer together they are compared to the hit window
+    let mut doubletapness = 1.0;
+    if let Some(next) = current.next(0, objects) {
+        let curr_delta_time = current.delta_time.max(1.0);
+        let next_delta_time = next.delta_time.max(1.0);
+        let delta_difference = (next_delta_time - curr_delta_time).abs();
+        let speed_ratio = curr_delta_time / curr_delta_time.max(delta_difference);
+        let window_ratio = (curr_delta_time / current.hit_window_great).min(1.0).powi(2);
+        doubletapness = speed_ratio.powf(1.0 - window_ratio);
+    }
+
+    //cap deltatime to the od 300 window
+    strain_time /= ((strain_time / current.hit_window_great) / 0.93).clamp(0.92, 1.0);
+
+    let mut speed_bonus = 1.0;
+    if strain_time < MIN_SPEED_BONUS {
+        speed_bonus = 1.0 + 0.75 * ((MIN_SPEED_BONUS - strain_time) / SPEED_BALANCING_FACTOR).powi(2);
+    }
+
+    let travel_distance = current.previous(0, objects).map_or(0.0, |o| o.travel_distance);
+    let distance = SINGLE_SPACING_THRESHOLD.min(travel_distance + current.minimum_jump_distance);
+
+    (speed_bonus + speed_bonus * (distance / SINGLE_SPACING_THRESHOLD).powf(3.5)) * doubletapness / strain_time
+}
+
+/// rewards changes in rhythm, and less so when they repeat
+fn evaluate_rhythm(current: &DifficultyObject, objects: &[DifficultyObject]) -> f64 {
+    if current.base.is_spinner() {
+        return 0.0;
+    }
+
+    let mut previous_island_size = 0;
+    let mut rhythm_complexity_sum = 0.0;
+    let mut island_size = 1;
+    let mut start_ratio = 0.0;
+    let mut first_delta_switch = false;
+
+    let historical_note_count = current.index.min(32);
+
+    let previous = |i: usize| current.previous(i, objects).unwrap();
+
+    let mut rhythm_start = 0;
+    while rhythm_start + 2 < historical_note_count
+        && current.start_time - previous(rhythm_start).start_time < HISTORY_TIME_MAX {
+        rhythm_start += 1;
+    }
+
+    for i in (1..=rhythm_start).rev() {
+        let curr = previous(i - 1);
+        let prev = previous(i);
+        let last = previous(i + 1);
+
+        //older notes matter less
+        let mut historical_decay = (HISTORY_TIME_MAX - (current.start_time - curr.start_time)) / HISTORY_TIME_MAX;
+        historical_decay = ((historical_note_count - i) as f64 / historical_note_count as f64).min(historical_decay);
+
+        let curr_delta = curr.strain_time;
+        let prev_delta = prev.strain_time;
+        let last_delta = last.strain_time;
+
+        let curr_ratio = 1.0 + 6.0 * (PI / (prev_delta.min(curr_delta) / prev_delta.max(curr_delta))).sin().powi(2).min(0.5);
+
+        let window_penalty = (((prev_delta - curr_delta).abs() - curr.hit_window_great * 0.3).max(0.0)
+            / (curr.hit_window_great * 0.3))
+            .min(1.0);
+
+        let mut effective_ratio = window_penalty * curr_ratio;
+
+        if first_delta_switch {
+            if !(prev_delta > 1.25 * curr_delta || prev_delta * 1.25 < curr_delta) {
+                //still in the same rhythm, the island grows
+                if island_size < 7 {
+                    island_size += 1;
+                }
+            } else {
+                if curr.base.is_slider() {
+                    effective_ratio *= 0.125;
+                }
+                if prev.base.is_slider() {
+                    effective_ratio *= 0.25;
+                }
+                if previous_island_size == island_size {
+                    effective_ratio *= 0.25;
+                }
+                if previous_island_size % 2 == island_size % 2 {
+                    effective_ratio *= 0.5;
+                }
+                //slowing down again is easier
+                if last_delta > prev_delta + 10.0 && prev_delta > curr_delta + 10.0 {
+                    effective_ratio *= 0.125;
+                }
+
+                rhythm_complexity_sum += (effective_ratio * start_ratio).sqrt()
+                    * historical_decay
+                    * ((4 + island_size) as f64).sqrt() / 2.0
+                    * ((4 + previous_island_size) as f64).sqrt() / 2.0;
+
+                start_ratio = effective_ratio;
+                previous_island_size = island_size;
+
+                if prev_delta * 1.25 < curr_delta {
+                    first_delta_switch = false;
+                }
+                island_size = 1;
+            }
+        } else if prev_delta > 1.25 * curr_delta {
+            //the rhythm sped up, start counting
+            first_delta_switch = true;
+            start_ratio = effective_ratio;
+            island_size = 1;
+        }
+    }
+
+    (4.0 + rhythm_complexity_sum * RHYTHM_MULTIPLIER).sqrt() / 2.0
+}
@@ -0,0 +1,48 @@
+use crate::strain::{DECAY_WEIGHT, weighted_sum};
+
+use super::object::DifficultyObject;
+
+pub use crate::strain::{SectionPeaks, strain_decay};
+
+/// a skill whose strain builds up with hard objects and decays over time
+pub trait StrainSkill {
+
+    fn section_peaks(&mut self) -> &mut SectionPeaks;
+
+    /// the strain right after `current`
+    fn strain_value_at(&mut self, current: &DifficultyObject, objects: &[DifficultyObject]) -> f64;
+
+    /// the strain at `time`, decayed from the object before `current`
+    fn initial_strain(&self, time: f64, current: &DifficultyObject, objects: &[DifficultyObject]) -> f64;
+
+    fn process(&mut self, current: &DifficultyObject, objects: &[DifficultyObject]) {
+        while let Some(section_start) = self.section_peaks().passed_section_end(current.start_time) {
+            let initial_strain = self.initial_strain(section_start, current, objects);
+            self.section_peaks().start_section(initial_strain);
+        }
+
+        let strain = self.strain_value_at(current, objects);
+        self.section_peaks().record(strain);
+    }
+}
+
+/// weighted sum of the section peaks, with the hardest few sections toned down
+/// so that a single spike doesn't decide the rating
+pub fn difficulty_value(peaks: &SectionPeaks, reduced_section_count: usize, difficulty_multiplier: f64) -> f64 {
+    const REDUCED_STRAIN_BASELINE: f64 = 0.75;
+
+    let mut strains: Vec<f64> = peaks.peaks().filter(|p| *p > 0.0).collect();
+    strains.sort_by(|a, b| b.total_cmp(a));
+
+    for (i, strain) in strains.iter_mut().take(reduced_section_count).enumerate() {
+        let t = (i as f64 / reduced_section_count as f64).clamp(0.0, 1.0);
+        let scale = lerp(1.0, 10.0, t).log10();
+        *strain *= lerp(REDUCED_STRAIN_BASELINE, 1.0, scale);
+    }
+
+    weighted_sum(strains.into_iter(), DECAY_WEIGHT) * difficulty_multiplier
+}
+
+fn lerp(start: f64, end: f64, t: f64) -> f64 {
+    start + (end - start) * t
+}
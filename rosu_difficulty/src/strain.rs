@@ -0,0 +1,76 @@
+/// strain peaks are taken over sections of this many ms, unless a mode says otherwise
+pub const SECTION_LENGTH: f64 = 400.0;
+
+/// each weaker section counts this much less than the one before it, unless a mode says otherwise
+pub const DECAY_WEIGHT: f64 = 0.9;
+
+/// the hardest strain of every section, and the section being filled
+#[derive(Debug)]
+pub struct SectionPeaks {
+    peaks: Vec<f64>,
+    current_peak: f64,
+    current_end: Option<f64>,
+    section_length: f64,
+}
+
+impl Default for SectionPeaks {
+    fn default() -> Self {
+        Self::new(SECTION_LENGTH)
+    }
+}
+
+impl SectionPeaks {
+
+    pub fn new(section_length: f64) -> Self {
+        Self {
+            peaks: Vec::new(),
+            current_peak: 0.0,
+            current_end: None,
+            section_length,
+        }
+    }
+
+    /// every section peak including the unfinished one
+    pub fn peaks(&self) -> impl Iterator<Item = f64> + '_ {
+        self.peaks.iter().copied().chain(std::iter::once(self.current_peak))
+    }
+
+    /// the end of the current section if `time` is past it, the first call decides where sections start
+    pub fn passed_section_end(&mut self, time: f64) -> Option<f64> {
+        let section_length = self.section_length;
+        let end = *self.current_end.get_or_insert_with(|| (time / section_length).ceil() * section_length);
+        (time > end).then_some(end)
+    }
+
+    /// closes the current section, the next one starts at `initial_strain`
+    pub fn start_section(&mut self, initial_strain: f64) {
+        self.peaks.push(self.current_peak);
+        self.current_peak = initial_strain;
+        if let Some(end) = &mut self.current_end {
+            *end += self.section_length;
+        }
+    }
+
+    pub fn record(&mut self, strain: f64) {
+        self.current_peak = self.current_peak.max(strain);
+    }
+}
+
+/// how quickly strain wears off, `base` is what's left after a second
+pub fn strain_decay(base: f64, ms: f64) -> f64 {
+    base.powf(ms / 1000.0)
+}
+
+/// sum of the strains from the hardest down, each one weighted `decay_weight` times the one before
+pub fn weighted_sum(strains: impl Iterator<Item = f64>, decay_weight: f64) -> f64 {
+    let mut strains: Vec<f64> = strains.collect();
+    strains.sort_by(|a, b| b.total_cmp(a));
+
+    let mut weight = 1.0;
+    let mut difficulty = 0.0;
+    for strain in strains {
+        difficulty += strain * weight;
+        weight *= decay_weight;
+    }
+    difficulty
+}
@@ -0,0 +1,129 @@
+use rosu_parser::{beatmap::{Beatmap, sections::GameMode}, mods::Mods};
+
+use crate::strain::{self, DECAY_WEIGHT};
+
+use self::{
+    colour::Colour,
+    object::TaikoDifficultyObject,
+    rhythm::Rhythm,
+    stamina::Stamina,
+};
+
+pub mod object;
+pub mod colour_encoding;
+pub mod colour;
+pub mod rhythm;
+pub mod stamina;
+
+const FINAL_MULTIPLIER: f64 = 0.0625;
+const COLOUR_SKILL_MULTIPLIER: f64 = 0.375 * FINAL_MULTIPLIER;
+const RHYTHM_SKILL_MULTIPLIER: f64 = 0.2 * FINAL_MULTIPLIER;
+const STAMINA_SKILL_MULTIPLIER: f64 = 0.375 * FINAL_MULTIPLIER;
+const DIFFICULTY_MULTIPLIER: f64 = 1.35;
+
+/// star rating of a taiko map and what went into it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaikoDifficultyAttributes {
+    pub stars: f64,
+    pub colour: f64,
+    pub rhythm: f64,
+    pub stamina: f64,
+    pub great_hit_window: f64, //rate adjusted
+    pub max_combo: usize,
+    pub is_convert: bool,
+}
+
+/// star rating calculation for taiko maps and osu!standard maps played in taiko
+pub struct TaikoStars<'a> {
+    beatmap: &'a Beatmap,
+    mods: Mods,
+}
+
+impl<'a> TaikoStars<'a> {
+
+    pub fn new(beatmap: &'a Beatmap) -> Self {
+        Self {
+            beatmap,
+            mods: Mods::empty(),
+        }
+    }
+
+    pub fn mods(mut self, mods: Mods) -> Self {
+        self.mods = mods;
+        self
+    }
+
+    pub fn calculate(&self) -> TaikoDifficultyAttributes {
+        let is_convert = self.beatmap.mode() != GameMode::Taiko;
+
+        let mut map_attributes = self.beatmap.difficulty_attributes().with_mods(self.mods);
+        map_attributes.mode = GameMode::Taiko;
+        let clock_rate = map_attributes.clock_rate;
+
+        let beatmap = self.beatmap.convert(GameMode::Taiko, self.mods)
+            .unwrap_or_else(|| self.beatmap.clone());
+        let objects = object::taiko_objects(&beatmap);
+        let difficulty_objects = TaikoDifficultyObject::create_all(&objects, clock_rate);
+
+        let mut colour = Colour::default();
+        let mut rhythm = Rhythm::default();
+        let mut stamina = Stamina::default();
+
+        for current in &difficulty_objects {
+            colour.process(current, &difficulty_objects);
+            rhythm.process(current, &difficulty_objects);
+            stamina.process(current, &difficulty_objects);
+        }
+
+        let colour_rating = colour.difficulty_value() * COLOUR_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+        let rhythm_rating = rhythm.difficulty_value() * RHYTHM_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+        let stamina_rating = stamina.difficulty_value() * STAMINA_SKILL_MULTIPLIER * DIFFICULTY_MULTIPLIER;
+
+        //sections where every skill is hard at once count for more
+        let combined_peaks = colour.peaks()
+            .zip(rhythm.peaks())
+            .zip(stamina.peaks())
+            .map(|((colour, rhythm), stamina)| {
+                let peak = norm(1.5, &[colour * COLOUR_SKILL_MULTIPLIER, stamina * STAMINA_SKILL_MULTIPLIER]);
+                norm(2.0, &[peak, rhythm * RHYTHM_SKILL_MULTIPLIER])
+            })
+            .filter(|peak| *peak > 0.0);
+        let combined_rating = strain::weighted_sum(combined_peaks, DECAY_WEIGHT) * DIFFICULTY_MULTIPLIER;
+
+        let mut stars = rescale(combined_rating * 1.4);
+
+        //converts can be played with more keys than the map was made for, which the skills can't tell yet
+        if is_convert {
+            stars *= 0.925;
+
+            //especially when there are few colour changes but a lot of stamina
+            if colour_rating < 2.0 && stamina_rating > 8.0 {
+                stars *= 0.8;
+            }
+        }
+
+        let great_hit_window = map_attributes.hit_windows()
+            .map_or(0.0, |windows| windows.great / clock_rate);
+
+        TaikoDifficultyAttributes {
+            stars,
+            colour: colour_rating,
+            rhythm: rhythm_rating,
+            stamina: stamina_rating,
+            great_hit_window,
+            max_combo: objects.iter().filter(|o| o.is_hit()).count(),
+            is_convert,
+        }
+    }
+}
+
+fn norm(p: f64, values: &[f64]) -> f64 {
+    values.iter().map(|v| v.powf(p)).sum::<f64>().powf(1.0 / p)
+}
+
+fn rescale(stars: f64) -> f64 {
+    if stars < 0.0 {
+        return stars;
+    }
+    10.43 * (stars / 8.0 + 1.0).ln()
+}
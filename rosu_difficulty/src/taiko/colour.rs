@@ -0,0 +1,66 @@
+use std::f64::consts::E;
+
+use crate::strain::{self, SectionPeaks};
+
+use super::{
+    colour_encoding::{AlternatingMonoPattern, MonoStreak, RepeatingHitPatterns},
+    object::TaikoDifficultyObject,
+};
+
+const SKILL_MULTIPLIER: f64 = 0.12;
+const STRAIN_DECAY_BASE: f64 = 0.8;
+
+/// how hard the changes between dons and kats are
+#[derive(Debug, Default)]
+pub struct Colour {
+    current_strain: f64,
+    sections: SectionPeaks,
+}
+
+impl Colour {
+
+    pub fn process(&mut self, current: &TaikoDifficultyObject, objects: &[TaikoDifficultyObject]) {
+        while let Some(section_start) = self.sections.passed_section_end(current.start_time) {
+            let previous_start = current.previous(0, objects).map_or(0.0, |o| o.start_time);
+            self.sections.start_section(self.current_strain * strain::strain_decay(STRAIN_DECAY_BASE, section_start - previous_start));
+        }
+
+        self.current_strain *= strain::strain_decay(STRAIN_DECAY_BASE, current.delta_time);
+        self.current_strain += strain_value_of(current) * SKILL_MULTIPLIER;
+        self.sections.record(self.current_strain);
+    }
+
+    pub fn peaks(&self) -> impl Iterator<Item = f64> + '_ {
+        self.sections.peaks()
+    }
+
+    pub fn difficulty_value(&self) -> f64 {
+        strain::weighted_sum(self.peaks(), strain::DECAY_WEIGHT)
+    }
+}
+
+/// only the first object of each colour pattern counts, the more often a pattern came up the easier it gets
+fn strain_value_of(current: &TaikoDifficultyObject) -> f64 {
+    let colour = &current.colour;
+
+    colour.mono_streak.map_or(0.0, mono_streak_difficulty)
+        + colour.alternating_mono_pattern.map_or(0.0, alternating_mono_pattern_difficulty)
+        + colour.repeating_hit_patterns.map_or(0.0, repeating_hit_patterns_difficulty)
+}
+
+fn mono_streak_difficulty(streak: MonoStreak) -> f64 {
+    sigmoid(streak.index as f64, 2.0, 2.0, 0.5, 1.0) * alternating_mono_pattern_difficulty(streak.parent) * 0.5
+}
+
+fn alternating_mono_pattern_difficulty(pattern: AlternatingMonoPattern) -> f64 {
+    sigmoid(pattern.index as f64, 2.0, 2.0, 0.5, 1.0) * repeating_hit_patterns_difficulty(pattern.parent)
+}
+
+fn repeating_hit_patterns_difficulty(patterns: RepeatingHitPatterns) -> f64 {
+    2.0 * (1.0 - sigmoid(patterns.repetition_interval as f64, 2.0, 2.0, 0.5, 1.0))
+}
+
+/// falls from `middle + height / 2` to `middle - height / 2` around `center`
+fn sigmoid(value: f64, center: f64, width: f64, middle: f64, height: f64) -> f64 {
+    (E * -(value - center) / width).tanh() * (height / 2.0) + middle
+}
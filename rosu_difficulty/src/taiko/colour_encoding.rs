@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+
+use super::object::TaikoDifficultyObject;
+
+/// repeating patterns further back than this count as new
+const MAX_REPETITION_INTERVAL: usize = 16;
+
+/// where an object sits in the colour patterns of the map. each part is only set on the first object of its run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColourEncoding {
+    pub mono_streak: Option<MonoStreak>,
+    pub alternating_mono_pattern: Option<AlternatingMonoPattern>,
+    pub repeating_hit_patterns: Option<RepeatingHitPatterns>,
+}
+
+/// a run of notes of one colour
+#[derive(Debug, Clone, Copy)]
+pub struct MonoStreak {
+    pub index: usize, //in its alternating mono pattern
+    pub parent: AlternatingMonoPattern,
+}
+
+/// mono streaks of the same length one after the other, like don kat don kat or dd kk dd
+#[derive(Debug, Clone, Copy)]
+pub struct AlternatingMonoPattern {
+    pub index: usize, //in its repeating hit patterns
+    pub parent: RepeatingHitPatterns,
+}
+
+/// alternating mono patterns that repeat
+#[derive(Debug, Clone, Copy)]
+pub struct RepeatingHitPatterns {
+    pub repetition_interval: usize, //how many of these back the same patterns came up, above the max if never
+}
+
+/// groups the objects into the three kinds of colour pattern and marks the first object of each
+pub fn assign(objects: &mut [TaikoDifficultyObject]) {
+    let streaks = mono_streaks(objects);
+    let patterns = alternating_mono_patterns(&streaks);
+    let repeating = repeating_hit_patterns(&patterns, &streaks, objects);
+
+    for (repeating, repetition_interval) in repeating {
+        let hit_patterns = RepeatingHitPatterns { repetition_interval };
+        objects[streaks[patterns[repeating[0]][0]][0]].colour.repeating_hit_patterns = Some(hit_patterns);
+
+        for (i, &pattern) in repeating.iter().enumerate() {
+            let alternating = AlternatingMonoPattern { index: i, parent: hit_patterns };
+            objects[streaks[patterns[pattern][0]][0]].colour.alternating_mono_pattern = Some(alternating);
+
+            for (j, &streak) in patterns[pattern].iter().enumerate() {
+                objects[streaks[streak][0]].colour.mono_streak = Some(MonoStreak { index: j, parent: alternating });
+            }
+        }
+    }
+}
+
+/// runs of one colour as object indices. a drum roll or swell starts a new run, the note after it
+/// joins that run if it has the colour of the last note before it
+fn mono_streaks(objects: &[TaikoDifficultyObject]) -> Vec<Vec<usize>> {
+    let mut streaks: Vec<Vec<usize>> = Vec::new();
+    let mut previous_note = None;
+
+    for (i, object) in objects.iter().enumerate() {
+        let same_colour = object.hit_type.is_some() && previous_note.is_some() && object.hit_type == previous_note;
+
+        match streaks.last_mut() {
+            Some(streak) if same_colour => streak.push(i),
+            _ => streaks.push(vec![i]),
+        }
+
+        if object.hit_type.is_some() {
+            previous_note = object.hit_type;
+        }
+    }
+    streaks
+}
+
+/// consecutive mono streaks of the same length, as streak indices
+fn alternating_mono_patterns(streaks: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut patterns: Vec<Vec<usize>> = Vec::new();
+
+    for (i, streak) in streaks.iter().enumerate() {
+        match patterns.last_mut() {
+            Some(pattern) if streaks[pattern[pattern.len() - 1]].len() == streak.len() => pattern.push(i),
+            _ => patterns.push(vec![i]),
+        }
+    }
+    patterns
+}
+
+/// alternating mono patterns grouped with the ones repeating them two later, as pattern indices,
+/// each with its repetition interval
+fn repeating_hit_patterns(patterns: &[Vec<usize>], streaks: &[Vec<usize>], objects: &[TaikoDifficultyObject]) -> Vec<(Vec<usize>, usize)> {
+    //the same number of streaks, all of the same length, starting with the same colour
+    let is_repetition = |a: usize, b: usize| {
+        let (a, b) = (&patterns[a], &patterns[b]);
+        a.len() == b.len()
+            && streaks[a[0]].len() == streaks[b[0]].len()
+            && objects[streaks[a[0]][0]].hit_type == objects[streaks[b[0]][0]].hit_type
+    };
+
+    let mut remaining: VecDeque<usize> = (0..patterns.len()).collect();
+    let mut grouped: Vec<Vec<usize>> = Vec::new();
+
+    while let Some(&first) = remaining.front() {
+        let coupled = |remaining: &VecDeque<usize>| remaining.get(2).is_some_and(|&other| is_repetition(remaining[0], other));
+
+        if coupled(&remaining) {
+            let mut group = Vec::new();
+            while coupled(&remaining) {
+                group.extend(remaining.pop_front());
+            }
+            group.extend(remaining.drain(..2));
+            grouped.push(group);
+        } else {
+            remaining.pop_front();
+            grouped.push(vec![first]);
+        }
+    }
+
+    //only the lengths of the first streaks of the first two patterns have to match
+    let same_lengths = |a: &[usize], b: &[usize]| {
+        a.len() == b.len()
+            && a.iter().zip(b).take(2).all(|(&a, &b)| streaks[patterns[a][0]].len() == streaks[patterns[b][0]].len())
+    };
+
+    (0..grouped.len())
+        .map(|i| {
+            let interval = (1..MAX_REPETITION_INTERVAL)
+                .take_while(|interval| *interval <= i)
+                .find(|interval| same_lengths(&grouped[i], &grouped[i - interval]))
+                .unwrap_or(MAX_REPETITION_INTERVAL + 1);
+            (grouped[i].clone(), interval)
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+use rosu_parser::beatmap::{
+    Beatmap,
+    sections::{ObjectParams, HIT_SOUND_CLAP, HIT_SOUND_WHISTLE},
+};
+
+use super::colour_encoding::{self, ColourEncoding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitType {
+    Centre, //don
+    Rim, //kat
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaikoObjectKind {
+    Hit(HitType),
+    DrumRoll,
+    Swell,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TaikoObject {
+    pub kind: TaikoObjectKind,
+    pub start_time: f64,
+}
+
+impl TaikoObject {
+
+    pub fn is_hit(&self) -> bool {
+        matches!(self.kind, TaikoObjectKind::Hit(_))
+    }
+
+    pub fn hit_type(&self) -> Option<HitType> {
+        match self.kind {
+            TaikoObjectKind::Hit(hit_type) => Some(hit_type),
+            _ => None,
+        }
+    }
+}
+
+/// whistles and claps are kats, everything else is a don
+pub fn hit_type(hit_sound: usize) -> HitType {
+    if hit_sound & (HIT_SOUND_WHISTLE | HIT_SOUND_CLAP) != 0 {
+        HitType::Rim
+    } else {
+        HitType::Centre
+    }
+}
+
+/// hits, drum rolls and swells in time order, converts have to be converted first
+pub fn taiko_objects(beatmap: &Beatmap) -> Vec<TaikoObject> {
+    beatmap.hit_objects.iter()
+        .flatten()
+        .map(|hit_object| TaikoObject {
+            kind: match hit_object.object_params {
+                ObjectParams::Slider(_) => TaikoObjectKind::DrumRoll,
+                ObjectParams::EndTime(_) => TaikoObjectKind::Swell,
+                ObjectParams::None => TaikoObjectKind::Hit(hit_type(hit_object.hit_sound)),
+            },
+            start_time: hit_object.time as f64,
+        })
+        .collect()
+}
+
+/// an object and how its timing relates to the two before it, at the clock rate
+#[derive(Debug, Clone)]
+pub struct TaikoDifficultyObject {
+    pub hit_type: Option<HitType>, //drum rolls and swells have none
+    pub index: usize, //of the object in the map, the first two have no difficulty object
+    pub start_time: f64,
+    pub delta_time: f64,
+    pub rhythm: &'static TaikoRhythm,
+    pub colour: ColourEncoding,
+    pub key_previous: Option<usize>, //the difficulty object two notes of the same colour back, hit with the same key
+}
+
+/// ratio of this object's delta time to the previous one's, and how hard that change is
+#[derive(Debug, PartialEq)]
+pub struct TaikoRhythm {
+    pub ratio: f64,
+    pub difficulty: f64,
+}
+
+const COMMON_RHYTHMS: [TaikoRhythm; 9] = [
+    rhythm(1, 1, 0.0),
+    rhythm(2, 1, 0.3),
+    rhythm(1, 2, 0.5),
+    rhythm(3, 1, 0.3),
+    rhythm(1, 3, 0.35),
+    rhythm(3, 2, 0.6),
+    rhythm(2, 3, 0.4),
+    rhythm(5, 4, 0.5),
+    rhythm(4, 5, 0.7),
+];
+
+const fn rhythm(numerator: usize, denominator: usize, difficulty: f64) -> TaikoRhythm {
+    TaikoRhythm {
+        ratio: numerator as f64 / denominator as f64,
+        difficulty,
+    }
+}
+
+impl TaikoDifficultyObject {
+
+    pub fn create_all(objects: &[TaikoObject], clock_rate: f64) -> Vec<TaikoDifficultyObject> {
+        let mut centres = Vec::new();
+        let mut rims = Vec::new();
+
+        let mut difficulty_objects: Vec<_> = objects.windows(3)
+            .enumerate()
+            .map(|(i, window)| {
+                let (last_last, last, current) = (&window[0], &window[1], &window[2]);
+                let delta_time = (current.start_time - last.start_time) / clock_rate;
+                let previous_length = (last.start_time - last_last.start_time) / clock_rate;
+
+                let mono = match current.hit_type() {
+                    Some(HitType::Centre) => Some(&mut centres),
+                    Some(HitType::Rim) => Some(&mut rims),
+                    None => None,
+                };
+                let key_previous = mono.and_then(|mono| {
+                    mono.push(i);
+                    mono.len().checked_sub(3).map(|j| mono[j])
+                });
+
+                TaikoDifficultyObject {
+                    hit_type: current.hit_type(),
+                    index: i + 2,
+                    start_time: current.start_time / clock_rate,
+                    delta_time,
+                    rhythm: closest_rhythm(delta_time / previous_length),
+                    colour: ColourEncoding::default(),
+                    key_previous,
+                }
+            })
+            .collect();
+
+        colour_encoding::assign(&mut difficulty_objects);
+        difficulty_objects
+    }
+
+    /// the difficulty object `n` before this one
+    pub fn previous<'a>(&self, n: usize, objects: &'a [TaikoDifficultyObject]) -> Option<&'a TaikoDifficultyObject> {
+        let i = (self.index - 2).checked_sub(n + 1)?;
+        objects.get(i)
+    }
+}
+
+fn closest_rhythm(ratio: f64) -> &'static TaikoRhythm {
+    COMMON_RHYTHMS.iter()
+        .min_by(|a, b| (a.ratio - ratio).abs().total_cmp(&(b.ratio - ratio).abs()))
+        .unwrap()
+}
@@ -0,0 +1,129 @@
+use crate::strain::{self, SectionPeaks};
+
+use super::object::TaikoDifficultyObject;
+
+const SKILL_MULTIPLIER: f64 = 10.0;
+const STRAIN_DECAY_BASE: f64 = 0.0;
+
+/// how many rhythm changes are remembered when looking for repeated patterns
+const RHYTHM_HISTORY_MAX_LENGTH: usize = 8;
+const RHYTHM_STRAIN_DECAY: f64 = 0.96;
+
+/// how hard the changes in timing between notes are
+#[derive(Debug, Default)]
+pub struct Rhythm {
+    current_strain: f64,
+    sections: SectionPeaks,
+    rhythm_strain: f64,
+    notes_since_rhythm_change: usize,
+    rhythm_history: Vec<TaikoDifficultyObject>,
+}
+
+impl Rhythm {
+
+    pub fn process(&mut self, current: &TaikoDifficultyObject, objects: &[TaikoDifficultyObject]) {
+        while let Some(section_start) = self.sections.passed_section_end(current.start_time) {
+            let previous_start = current.previous(0, objects).map_or(0.0, |o| o.start_time);
+            self.sections.start_section(self.current_strain * strain::strain_decay(STRAIN_DECAY_BASE, section_start - previous_start));
+        }
+
+        self.current_strain *= strain::strain_decay(STRAIN_DECAY_BASE, current.delta_time);
+        self.current_strain += self.strain_value_of(current) * SKILL_MULTIPLIER;
+        self.sections.record(self.current_strain);
+    }
+
+    pub fn peaks(&self) -> impl Iterator<Item = f64> + '_ {
+        self.sections.peaks()
+    }
+
+    pub fn difficulty_value(&self) -> f64 {
+        strain::weighted_sum(self.peaks(), strain::DECAY_WEIGHT)
+    }
+
+    fn strain_value_of(&mut self, current: &TaikoDifficultyObject) -> f64 {
+        //drum rolls and swells have no rhythm
+        if current.hit_type.is_none() {
+            self.reset();
+            return 0.0;
+        }
+
+        self.rhythm_strain *= RHYTHM_STRAIN_DECAY;
+        self.notes_since_rhythm_change += 1;
+
+        if current.rhythm.difficulty == 0.0 {
+            return 0.0;
+        }
+
+        let mut object_strain = current.rhythm.difficulty;
+        object_strain *= self.repetition_penalties(current);
+        object_strain *= pattern_length_penalty(self.notes_since_rhythm_change);
+        object_strain *= self.speed_penalty(current.delta_time);
+
+        self.notes_since_rhythm_change = 0;
+
+        self.rhythm_strain += object_strain;
+        self.rhythm_strain
+    }
+
+    /// rhythm patterns that came up recently are easier the second time
+    fn repetition_penalties(&mut self, current: &TaikoDifficultyObject) -> f64 {
+        let mut penalty = 1.0;
+
+        self.rhythm_history.push(current.clone());
+        if self.rhythm_history.len() > RHYTHM_HISTORY_MAX_LENGTH {
+            self.rhythm_history.remove(0);
+        }
+
+        let history = &self.rhythm_history;
+
+        for patterns_to_compare in 2..=RHYTHM_HISTORY_MAX_LENGTH / 2 {
+            let recent = &history[history.len().saturating_sub(patterns_to_compare)..];
+
+            for start in (0..history.len().saturating_sub(patterns_to_compare)).rev() {
+                let same_pattern = history[start..].iter()
+                    .take(patterns_to_compare)
+                    .map(|o| o.rhythm)
+                    .eq(recent.iter().map(|o| o.rhythm));
+
+                if !same_pattern {
+                    continue;
+                }
+
+                let notes_since = current.index - history[start].index;
+                penalty *= repetition_penalty(notes_since);
+                break;
+            }
+        }
+
+        penalty
+    }
+
+    /// only the fast rhythm changes count, slow ones start over
+    fn speed_penalty(&mut self, delta_time: f64) -> f64 {
+        if delta_time < 80.0 {
+            return 1.0;
+        }
+        if delta_time < 210.0 {
+            return (1.4 - 0.005 * delta_time).max(0.0);
+        }
+
+        self.reset();
+        0.0
+    }
+
+    fn reset(&mut self) {
+        self.rhythm_strain = 0.0;
+        self.notes_since_rhythm_change = 0;
+    }
+}
+
+fn repetition_penalty(notes_since: usize) -> f64 {
+    (0.032 * notes_since as f64).min(1.0)
+}
+
+/// changes right after another one and changes after long steady parts are both easier
+fn pattern_length_penalty(pattern_length: usize) -> f64 {
+    let short_pattern_penalty = (0.15 * pattern_length as f64).min(1.0);
+    let long_pattern_penalty = (2.5 - 0.15 * pattern_length as f64).clamp(0.0, 1.0);
+    short_pattern_penalty.min(long_pattern_penalty)
+}
@@ -0,0 +1,47 @@
+use crate::strain::{self, SectionPeaks};
+
+use super::object::TaikoDifficultyObject;
+
+const SKILL_MULTIPLIER: f64 = 1.1;
+const STRAIN_DECAY_BASE: f64 = 0.4;
+
+/// how fast each key has to be hit, assuming every colour is played with two keys
+#[derive(Debug, Default)]
+pub struct Stamina {
+    current_strain: f64,
+    sections: SectionPeaks,
+}
+
+impl Stamina {
+
+    pub fn process(&mut self, current: &TaikoDifficultyObject, objects: &[TaikoDifficultyObject]) {
+        while let Some(section_start) = self.sections.passed_section_end(current.start_time) {
+            let previous_start = current.previous(0, objects).map_or(0.0, |o| o.start_time);
+            self.sections.start_section(self.current_strain * strain::strain_decay(STRAIN_DECAY_BASE, section_start - previous_start));
+        }
+
+        self.current_strain *= strain::strain_decay(STRAIN_DECAY_BASE, current.delta_time);
+        self.current_strain += strain_value_of(current, objects) * SKILL_MULTIPLIER;
+        self.sections.record(self.current_strain);
+    }
+
+    pub fn peaks(&self) -> impl Iterator<Item = f64> + '_ {
+        self.sections.peaks()
+    }
+
+    pub fn difficulty_value(&self) -> f64 {
+        strain::weighted_sum(self.peaks(), strain::DECAY_WEIGHT)
+    }
+}
+
+fn strain_value_of(current: &TaikoDifficultyObject, objects: &[TaikoDifficultyObject]) -> f64 {
+    match current.key_previous {
+        Some(key_previous) => 0.5 + speed_bonus(current.start_time - objects[key_previous].start_time),
+        None => 0.0,
+    }
+}
+
+/// capped at 50 ms between hits of one key, 600 bpm 1/4
+fn speed_bonus(interval: f64) -> f64 {
+    30.0 / interval.max(50.0)
+}
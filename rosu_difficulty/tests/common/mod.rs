@@ -0,0 +1,15 @@
+//shared by the integration tests
+use std::path::PathBuf;
+
+use rosu_parser::{beatmap::Beatmap, mods::Mods};
+
+/// a map from tests/fixtures
+pub fn fixture(name: &str) -> Beatmap {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    Beatmap::load_from_file(&path).unwrap()
+}
+
+/// mods written like the game shows them, e.g. HDDT
+pub fn mods(mods: &str) -> Mods {
+    mods.parse().unwrap()
+}
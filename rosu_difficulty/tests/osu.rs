@@ -0,0 +1,72 @@
+mod common;
+
+use common::{fixture, mods};
+use rosu_difficulty::osu::{OsuDifficultyAttributes, OsuStars};
+
+fn calculate(name: &str, mod_names: &str) -> OsuDifficultyAttributes {
+    OsuStars::new(&fixture(name))
+        .mods(mods(mod_names))
+        .calculate()
+}
+
+/// stars, aim, speed and slider factor.
+///
+/// the expected values are rosu-pp 0.10.0's, which ports osu!lazer's calculators as of 2023
+fn assert_rating(name: &str, mods: &str, expected: [f64; 4]) {
+    let attributes = calculate(name, mods);
+    let actual = [attributes.stars, attributes.aim, attributes.speed, attributes.slider_factor];
+
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!((actual - expected).abs() < 1e-5, "{} +{}: expected {:?}, got {:?}", name, mods, expected, actual);
+    }
+}
+
+#[test]
+fn streams() {
+    assert_rating("streams.osu", "", [4.968899, 1.822062, 2.731247, 1.0]);
+    assert_rating("streams.osu", "DT", [7.542502, 2.558675, 4.219572, 1.0]);
+    assert_rating("streams.osu", "EZHT", [3.646069, 1.275559, 2.025189, 1.0]);
+}
+
+#[test]
+fn jumps() {
+    assert_rating("jumps.osu", "", [6.807368, 3.813699, 2.290322, 1.0]);
+    assert_rating("jumps.osu", "HR", [7.284034, 4.125468, 2.290322, 1.0]);
+}
+
+#[test]
+fn sliders() {
+    assert_rating("sliders.osu", "", [5.407172, 3.173258, 0.762358, 0.354823]);
+    assert_rating("sliders.osu", "HR", [7.027102, 4.133084, 0.762483, 0.318203]);
+    assert_rating("sliders.osu", "DT", [6.144837, 3.599842, 1.048190, 0.401864]);
+}
+
+#[test]
+fn old_map_without_approach_rate() {
+    assert_rating("old_v5.osu", "", [2.457985, 1.258355, 1.077925, 0.870304]);
+
+    let attributes = calculate("old_v5.osu", "");
+    assert_eq!(attributes.approach_rate, attributes.overall_difficulty);
+}
+
+#[test]
+fn flashlight_only_counts_with_the_mod() {
+    //rosu-pp rates flashlight either way, the game only with the mod
+    assert_eq!(calculate("jumps.osu", "").flashlight, 0.0);
+    assert_rating("jumps.osu", "HDFL", [7.292852, 3.813699, 2.290322, 1.0]);
+    assert!((calculate("jumps.osu", "HDFL").flashlight - 1.717711).abs() < 1e-5);
+}
+
+#[test]
+fn object_counts_and_combo() {
+    let attributes = calculate("sliders.osu", "");
+    assert_eq!(attributes.max_combo, 232);
+    assert_eq!((attributes.n_circles, attributes.n_sliders, attributes.n_spinners), (60, 40, 1));
+}
+
+#[test]
+fn rate_adjusted_approach_rate_and_od() {
+    let attributes = calculate("streams.osu", "DT");
+    assert!((attributes.approach_rate - 31.0 / 3.0).abs() < 1e-9);
+    assert!((attributes.overall_difficulty - 88.0 / 9.0).abs() < 1e-9);
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{fixture, mods};
+use rosu_difficulty::osu::performance::{OsuPerformance, OsuScore, PROJECTED_ACCURACIES};
+
+/// pp, aim, speed and accuracy of an SS.
+///
+/// the expected values are rosu-pp 0.10.0's, like the ratings in the osu tests
+fn assert_ss(name: &str, mod_names: &str, expected: [f64; 4]) {
+    let map = fixture(name);
+    let performance = OsuPerformance::new(&map).mods(mods(mod_names)).calculate();
+    let actual = [performance.pp, performance.aim, performance.speed, performance.accuracy];
+
+    for (actual, expected) in actual.iter().zip(expected) {
+        assert!((actual - expected).abs() < 1e-4, "{} +{}: expected {:?}, got {:?}", name, mod_names, expected, actual);
+    }
+}
+
+#[test]
+fn ss_values() {
+    assert_ss("streams.osu", "", [158.273319, 22.327966, 79.813624, 49.573468]);
+    assert_ss("jumps.osu", "HDDT", [1125.008240, 742.441762, 176.695905, 141.437007]);
+    assert_ss("sliders.osu", "", [157.366441, 119.630050, 1.423074, 22.982393]);
+}
+
+#[test]
+fn score_with_misses_and_if_fc() {
+    let map = fixture("sliders.osu");
+    let score = OsuScore { n300: 90, n100: 8, n50: 1, n_misses: 2, max_combo: 120 };
+    let performance = OsuPerformance::new(&map).score(score);
+
+    let actual = performance.calculate();
+    assert!((actual.pp - 23.966027).abs() < 1e-4, "got {}", actual.pp);
+    assert_eq!(actual.effective_miss_count, 2.0);
+
+    let fc = performance.if_fc();
+    assert!(fc.pp > actual.pp);
+    assert_eq!(fc.effective_miss_count, 0.0);
+}
+
+#[test]
+fn accuracy_projections_increase() {
+    let map = fixture("streams.osu");
+    let projections = OsuPerformance::new(&map).accuracy_projections(&PROJECTED_ACCURACIES);
+
+    assert_eq!(projections.len(), PROJECTED_ACCURACIES.len());
+    for pair in projections.windows(2) {
+        assert!(pair[0].1.pp < pair[1].1.pp);
+    }
+
+    let ss = OsuPerformance::new(&map).calculate();
+    assert!((projections.last().unwrap().1.pp - ss.pp).abs() < 1e-9);
+}
+
+#[test]
+fn score_from_accuracy() {
+    for accuracy in [0.9, 0.95, 0.98, 0.99, 1.0] {
+        let score = OsuScore::with_accuracy(accuracy, 500, 0, 600);
+        assert_eq!(score.total_hits(), 500);
+        assert!((score.accuracy() - accuracy).abs() < 0.002, "{} got {}", accuracy, score.accuracy());
+    }
+
+    let score = OsuScore::with_accuracy(0.95, 500, 3, 600);
+    assert_eq!((score.n_misses, score.total_hits()), (3, 500));
+    assert!((score.accuracy() - 0.95).abs() < 0.002);
+}
@@ -1,10 +1,21 @@
-use std::{str::{Bytes}, path::PathBuf, fs};
+use std::{str::{Bytes, FromStr}, path::PathBuf, fs};
 
 pub mod events;
 pub mod sections;
+pub mod timing;
+pub mod attributes;
+pub mod pos;
+pub mod curve;
+pub mod slider;
+pub mod stacking;
+pub mod hitsounds;
+pub mod random;
+pub mod catch;
+pub mod combo;
+pub mod convert;
 use sections::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct Beatmap {
     pub version: Option<usize>,
 
@@ -35,22 +46,6 @@ impl Beatmap {
 
 }
 
-impl Default for Beatmap {
-    fn default() -> Self {
-        Self {
-            version: None,
-            general: Default::default(),
-            editor: Default::default(),
-            metadata: Default::default(),
-            difficulty: Default::default(),
-            events: Default::default(),
-            timing_points: Default::default(),
-            colours: Default::default(),
-            hit_objects: Default::default()
-        }
-    }
-}
-
 enum Section {
     General(General),
     Editor(Editor),
@@ -167,7 +162,7 @@ impl<'a> Parser<'a> {
                 }
                 b'[' => self.parse_section()?,
                 b'=' | b':' => {
-                    if let None = self.key {
+                    if self.key.is_none() {
                         return self.error("missing key");
                     }
 
@@ -230,8 +225,7 @@ impl<'a> Parser<'a> {
         let section_str = section_str.trim();
         self.bump();
         
-        let next_section;
-        next_section = match section_str {
+        let next_section = match section_str {
         
             "General" => Section::General(General::default()),
             "Editor" => Section::Editor(Editor::default()),
@@ -293,7 +287,7 @@ impl<'a> Parser<'a> {
 
                 }
 
-                return Ok(());
+                Ok(())
             }
 
         }
@@ -338,34 +332,62 @@ impl<'a> Parser<'a> {
                     effects: params.next().unwrap().parse().unwrap(),
                 });
             }
-            Some(Section::Colours(s)) => {
-                s.push(Colour { 
-                    combo: params.next().unwrap().trim_start_matches("Combo").parse().unwrap(),
-                    slider_track_override: params.next().unwrap().parse().unwrap(),
-                    slider_border: params.next().unwrap().parse().unwrap(),
-                });
-            }
             Some(Section::HitObjects(s)) => {
+                //older maps and some converts have fractional positions and times, the game truncates them
+                let x = parse_field::<f32>(params.next(), "x")? as isize;
+                let y = parse_field::<f32>(params.next(), "y")? as isize;
+                let time = parse_field::<f64>(params.next(), "time")? as isize;
+
+                //the type is a bit field, the other bits are combo information
+                let type_bits: usize = parse_field(params.next(), "type")?;
+                let kind = if type_bits & 1 != 0 {
+                    HitObjectKind::HitCircle
+                } else if type_bits & 2 != 0 {
+                    HitObjectKind::Slider
+                } else if type_bits & 8 != 0 {
+                    HitObjectKind::Spinner
+                } else if type_bits & 128 != 0 {
+                    HitObjectKind::ManiaHold
+                } else {
+                    return Ok(());//{return self.error("invalid hitcircle type")},
+                };
+
+                let hit_sound = parse_field(params.next(), "hit sound")?;
+
+                let (object_params, hit_sample) = match kind {
+                    HitObjectKind::HitCircle => (
+                        ObjectParams::None, 
+                        params.next(),
+                    ),
+                    HitObjectKind::Slider => (
+                        ObjectParams::Slider(parse_slider_params(&mut params)?),
+                        params.next(),
+                    ),
+                    HitObjectKind::Spinner => (
+                        ObjectParams::EndTime(parse_field(params.next(), "spinner end time")?),
+                        params.next(),
+                    ),
+                    //mania holds separate the end time from the hit sample with a colon, if they have one
+                    HitObjectKind::ManiaHold => {
+                        let field = params.next();
+                        let (end_time, hit_sample) = match field.and_then(|f| f.split_once(':')) {
+                            Some((end_time, hit_sample)) => (Some(end_time), Some(hit_sample)),
+                            None => (field, None),
+                        };
+                        (ObjectParams::EndTime(parse_field(end_time, "hold end time")?), hit_sample)
+                    }
+                };
+
                 s.push(HitObject { 
-                    x: params.next().unwrap().parse().unwrap(),
-                    y: params.next().unwrap().parse().unwrap(),
-                    time: params.next().unwrap().parse().unwrap(),
-                    kind: match params.next() {
-                        Some("0") => HitObjectKind::HitCircle,
-                        Some("1") => HitObjectKind::Slider,
-                        Some("2") => HitObjectKind::Spinner,
-                        Some("3") => HitObjectKind::ManiaHold,
-                        _ => {return  Ok(());}//{return self.error("invalid hitcircle type")},
-                    },
-                    hit_sound: params.next().unwrap().parse().unwrap(),
-                    object_params: match params.next() {
-                        Some(string) => Some(String::from(string)),
-                        None => None,
-                    },
-                    hit_sample: match params.next() {
-                        Some(string) => Some(String::from(string)),
-                        None => None,
-                    },
+                    x,
+                    y,
+                    time,
+                    kind,
+                    hit_sound,
+                    object_params,
+                    hit_sample: hit_sample.map(parse_hit_sample).unwrap_or_default(),
+                    new_combo: type_bits & 4 != 0,
+                    combo_skip: (type_bits >> 4) & 7,
                 });
             }
             _ => {}
@@ -391,11 +413,11 @@ impl<'a> Parser<'a> {
         self.bump();
         self.parse_whitespace_except_line_break();
 
-        let val;
-        match self.chr {
-            None => val = Ok(String::new()),
-            _ => val = self.parse_str_until_eol()
-        }
+        let val = if self.eof() {
+            Ok(String::new())
+        } else {
+            self.parse_str_until_eol()
+        };
 
         let mval = val?;
         let mval = mval.trim();
@@ -441,8 +463,8 @@ impl<'a> Parser<'a> {
                     }
                     None => return self.error("Key not defined"),
                 }
-                Section::Metadata(s) => match self.key.as_mut() {
-                    Some(k) => match k.as_str() {
+                Section::Metadata(s) => if let Some(k) = self.key.as_mut() {
+                    match k.as_str() {
                         "Title" => s.title = Some(String::from(mval)),
                         "TitleUnicode" => s.title_unicode = Some(String::from(mval)),
                         "Artist" => s.artist = Some(String::from(mval)),
@@ -455,10 +477,9 @@ impl<'a> Parser<'a> {
                         "BeatmapSetID" => s.beatmap_set_id = Some(mval.parse().unwrap()),
                         _ => {}
                     }
-                    _ => {}
                 }
-                Section::Difficulty(s) => match self.key.as_mut() {
-                    Some(k) => match k.as_str() {
+                Section::Difficulty(s) => if let Some(k) = self.key.as_mut() {
+                    match k.as_str() {
                         "HPDrainRate" => s.hp_drain_rate = Some(mval.parse().unwrap()),
                         "CircleSize" => s.circle_size = Some(mval.parse().unwrap()),
                         "OverallDifficulty" => s.overall_difficulty = Some(mval.parse().unwrap()),
@@ -467,13 +488,25 @@ impl<'a> Parser<'a> {
                         "SliderTickRate" => s.slider_tick_rate = Some(mval.parse().unwrap()),
                         _ => {}
                     }
-                    _ => {}
+                }
+                Section::Colours(s) => if let Some(k) = self.key.as_mut() {
+                    match k.as_str() {
+                        "SliderTrackOverride" => s.slider_track_override = mval.parse().ok(),
+                        "SliderBorder" => s.slider_border = mval.parse().ok(),
+                        k => {
+                            //Combo1 to Combo8, anything past those is ignored like the game does
+                            let index = k.strip_prefix("Combo").and_then(|i| i.parse::<usize>().ok());
+                            if let Some(colour) = index.and_then(|i| i.checked_sub(1)).and_then(|i| s.combo.get_mut(i)) {
+                                *colour = mval.parse().ok();
+                            }
+                        }
+                    }
                 }
                 _ => {}
             }
         }
         self.key = None;
-        return Ok(())
+        Ok(())
     }
 
     fn parse_str_until_eol(&mut self) -> Result<String, Error> {
@@ -505,7 +538,151 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// `curveType|curvePoints,slides,length,edgeSounds,edgeSets`, the last two are optional
+fn parse_slider_params<'a>(params: &mut impl Iterator<Item = &'a str>) -> Result<SliderParams, Error> {
+    let mut curve = params.next().unwrap_or_default().split('|');
+
+    let curve_type = match curve.next() {
+        Some("B") => CurveType::Bezier,
+        Some("C") => CurveType::Catmull,
+        Some("L") => CurveType::Linear,
+        Some("P") => CurveType::PerfectCircle,
+        other => return Err(Error::Parse(format!("invalid curve type \"{:?}\"", other))),
+    };
+
+    //`C|` has an empty point after the type
+    let curve_points = curve
+        .filter(|point| !point.is_empty())
+        .map(|point| parse_pair(point, "curve point"))
+        .collect::<Result<_, _>>()?;
+
+    let slides = parse_field(params.next(), "slide count")?;
+    let length = parse_field(params.next(), "slider length")?;
+
+    let edge_sounds = match params.next() {
+        Some(list) if !list.is_empty() => list.split('|')
+            .map(|e| parse_field(Some(e), "edge sound"))
+            .collect::<Result<_, _>>()?,
+        _ => Vec::new(),
+    };
+
+    let edge_sets = match params.next() {
+        Some(list) if !list.is_empty() => list.split('|')
+            .map(|e| parse_pair(e, "edge set"))
+            .collect::<Result<_, _>>()?,
+        _ => Vec::new(),
+    };
+
+    Ok(SliderParams {
+        curve_type,
+        curve_points,
+        slides,
+        length,
+        edge_sounds,
+        edge_sets,
+    })
+}
+
+/// a field that has to be there and parse, maps with a broken one don't load
+fn parse_field<T: FromStr>(field: Option<&str>, name: &str) -> Result<T, Error> {
+    field.and_then(|f| f.trim().parse().ok())
+        .ok_or_else(|| Error::Parse(format!("invalid {} \"{}\"", name, field.unwrap_or_default())))
+}
+
+/// two fields separated by a colon, like `x:y`
+fn parse_pair<T: FromStr, U: FromStr>(string: &str, name: &str) -> Result<(T, U), Error> {
+    match string.split_once(':') {
+        Some((a, b)) => Ok((parse_field(Some(a), name)?, parse_field(Some(b), name)?)),
+        None => Err(Error::Parse(format!("invalid {} \"{}\"", name, string))),
+    }
+}
+
+/// `normalSet:additionSet:index:volume:filename`, older maps leave off the last fields
+fn parse_hit_sample(string: &str) -> HitSample {
+    let mut fields = string.split(':');
+    let mut next_num = || fields.next()
+        .and_then(|f| f.parse().ok())
+        .unwrap_or(0);
+
+    let normal_set = next_num();
+    let addition_set = next_num();
+    let index = next_num();
+    let volume = next_num();
+    let filename = string.splitn(5, ':')
+        .nth(4)
+        .filter(|f| !f.is_empty())
+        .map(String::from);
+
+    HitSample {
+        normal_set,
+        addition_set,
+        index,
+        volume,
+        filename,
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Parse(String)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_objects(objects: &str) -> Result<Beatmap, Error> {
+        Beatmap::load_from_string(format!("osu file format v14\n\n[HitObjects]\n{}\n", objects))
+    }
+
+    #[test]
+    fn hold_without_hit_sample() {
+        let map = parse_objects("64,192,1000,128,0,1500").unwrap();
+        let hold = &map.hit_objects.unwrap()[0];
+        assert!(matches!(hold.object_params, ObjectParams::EndTime(1500)));
+    }
+
+    #[test]
+    fn hold_with_hit_sample() {
+        let map = parse_objects("64,192,1000,128,0,1500:1:2:0:0:").unwrap();
+        let hold = &map.hit_objects.unwrap()[0];
+        assert!(matches!(hold.object_params, ObjectParams::EndTime(1500)));
+        assert_eq!((hold.hit_sample.normal_set, hold.hit_sample.addition_set), (1, 2));
+    }
+
+    #[test]
+    fn catmull_slider_without_curve_points() {
+        let map = parse_objects("64,192,1000,2,0,C|,1,100").unwrap();
+        match &map.hit_objects.unwrap()[0].object_params {
+            ObjectParams::Slider(params) => assert!(params.curve_points.is_empty()),
+            _ => panic!("expected a slider"),
+        }
+    }
+
+    #[test]
+    fn broken_object_params_are_errors() {
+        assert!(parse_objects("64,192,1000,2,0,B|100,1,100").is_err());
+        assert!(parse_objects("64,192,1000,2,0,B|100:x,1,100").is_err());
+        assert!(parse_objects("64,192,1000,2,0,B|100:100").is_err());
+        assert!(parse_objects("64,192,1000,128,0,").is_err());
+        assert!(parse_objects("256,192,1000,8,0").is_err());
+    }
+
+    #[test]
+    fn fractional_positions_and_negative_times() {
+        let map = parse_objects("64.75,-20.5,-150.9,1,0\n100,192,250.5,1,0").unwrap();
+        let objects = map.hit_objects.unwrap();
+        assert_eq!((objects[0].x, objects[0].y, objects[0].time), (64, -20, -150));
+        assert_eq!(objects[1].time, 250);
+    }
+
+    #[test]
+    fn broken_object_fields_are_errors() {
+        assert!(parse_objects("64,192,1000,1").is_err());
+        assert!(parse_objects("64,192").is_err());
+        assert!(parse_objects("x,192,1000,1,0").is_err());
+        assert!(parse_objects("64,192,soon,1,0").is_err());
+        assert!(parse_objects("64,192,1000,circle,0").is_err());
+        assert!(parse_objects("64,192,1000,1,0").is_ok());
+    }
+}
@@ -0,0 +1,273 @@
+use crate::mods::Mods;
+use super::{Beatmap, sections::GameMode};
+
+/// circles are 64 osu!pixels in radius at a scale of 1
+pub const OBJECT_RADIUS: f64 = 64.0;
+
+/// the fade-in is shortened for approach times below this
+const PREEMPT_MIN: f64 = 450.0;
+
+/// difficulty values with the game's defaults filled in, and what they turn into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyAttributes {
+    pub mode: GameMode,
+    pub circle_size: f64,
+    pub approach_rate: f64,
+    pub overall_difficulty: f64,
+    pub hp_drain_rate: f64,
+    pub clock_rate: f64, //1.5 with DT, 0.75 with HT
+}
+
+/// how far from an object's time a hit still counts, in ms either side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitWindows {
+    pub great: f64, //300
+    pub ok: f64, //100
+    pub meh: Option<f64>, //50, taiko has none
+}
+
+impl Default for DifficultyAttributes {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::Osu,
+            circle_size: 5.0,
+            approach_rate: 5.0,
+            overall_difficulty: 5.0,
+            hp_drain_rate: 5.0,
+            clock_rate: 1.0,
+        }
+    }
+}
+
+impl DifficultyAttributes {
+
+    /// HR and EZ scale the values, HR capping them at 10. DT and HT only change the clock rate.
+    ///
+    /// mania's circle size is its key count, so that stays the same
+    pub fn with_mods(mut self, mods: Mods) -> Self {
+        let circle_size = self.circle_size;
+
+        if mods.contains(Mods::HARD_ROCK) {
+            self.circle_size = (self.circle_size * 1.3).min(10.0);
+            self.approach_rate = (self.approach_rate * 1.4).min(10.0);
+            self.overall_difficulty = (self.overall_difficulty * 1.4).min(10.0);
+            self.hp_drain_rate = (self.hp_drain_rate * 1.4).min(10.0);
+        } else if mods.contains(Mods::EASY) {
+            self.circle_size *= 0.5;
+            self.approach_rate *= 0.5;
+            self.overall_difficulty *= 0.5;
+            self.hp_drain_rate *= 0.5;
+        }
+
+        if self.mode == GameMode::Mania {
+            self.circle_size = circle_size;
+        }
+
+        self.clock_rate = mods.clock_rate();
+        self
+    }
+
+    /// the AR the approach time feels like at the clock rate, e.g. AR 9 becomes 10.33 with DT
+    pub fn effective_approach_rate(&self) -> f64 {
+        let preempt = self.preempt() / self.clock_rate;
+        if preempt > 1200.0 {
+            (1800.0 - preempt) / 120.0
+        } else {
+            (1200.0 - preempt) / 150.0 + 5.0
+        }
+    }
+
+    /// the OD the 300 window feels like at the clock rate, catch has no windows and keeps its OD
+    pub fn effective_overall_difficulty(&self) -> f64 {
+        //the 300 window is linear in OD for every mode
+        let (base, per_od) = match self.mode {
+            GameMode::Osu => (80.0, 6.0),
+            GameMode::Taiko => (50.0, 3.0),
+            GameMode::Mania => (64.0, 3.0),
+            GameMode::Catch => return self.overall_difficulty,
+        };
+
+        let great = (base - per_od * self.overall_difficulty) / self.clock_rate;
+        (base - great) / per_od
+    }
+
+    /// how long before its time an object starts appearing, in ms of map time
+    pub fn preempt(&self) -> f64 {
+        difficulty_range(self.approach_rate, 1800.0, 1200.0, 450.0)
+    }
+
+    /// how long an object takes to become fully visible, in ms
+    pub fn fade_in(&self) -> f64 {
+        400.0 * (self.preempt() / PREEMPT_MIN).min(1.0)
+    }
+
+    /// `None` for catch, where fruits are either caught or missed
+    pub fn hit_windows(&self) -> Option<HitWindows> {
+        let od = self.overall_difficulty;
+
+        match self.mode {
+            GameMode::Osu => Some(HitWindows {
+                great: difficulty_range(od, 80.0, 50.0, 20.0),
+                ok: difficulty_range(od, 140.0, 100.0, 60.0),
+                meh: Some(difficulty_range(od, 200.0, 150.0, 100.0)),
+            }),
+            GameMode::Taiko => Some(HitWindows {
+                great: difficulty_range(od, 50.0, 35.0, 20.0),
+                ok: difficulty_range(od, 120.0, 80.0, 50.0),
+                meh: None,
+            }),
+            GameMode::Catch => None,
+            GameMode::Mania => Some(HitWindows {
+                great: 64.0 - 3.0 * od,
+                ok: 127.0 - 3.0 * od,
+                meh: Some(151.0 - 3.0 * od),
+            }),
+        }
+    }
+
+    /// size of objects relative to a 64 osu!pixel radius circle
+    pub fn scale(&self) -> f64 {
+        (1.0 - 0.7 * (self.circle_size - 5.0) / 5.0) / 2.0
+    }
+
+    /// circle radius in osu!pixels
+    pub fn radius(&self) -> f64 {
+        OBJECT_RADIUS * self.scale()
+    }
+
+    /// full spins needed to clear a spinner lasting `duration` ms, stable counts these as half spins
+    pub fn spins_required(&self, duration: f64) -> usize {
+        let spins_per_second = difficulty_range(self.overall_difficulty, 1.5, 2.5, 3.75);
+        (duration / 1000.0 * spins_per_second).max(0.0) as usize
+    }
+}
+
+impl Beatmap {
+
+    pub fn mode(&self) -> GameMode {
+        self.general.as_ref()
+            .and_then(|g| g.mode)
+            .and_then(GameMode::from_id)
+            .unwrap_or_default()
+    }
+
+    /// difficulty values as the game reads them.
+    ///
+    /// maps from before v8 have no AR and use their OD instead
+    pub fn difficulty_attributes(&self) -> DifficultyAttributes {
+        let defaults = DifficultyAttributes::default();
+        let difficulty = match &self.difficulty {
+            Some(difficulty) => difficulty,
+            None => return DifficultyAttributes { mode: self.mode(), ..defaults },
+        };
+
+        let overall_difficulty = difficulty.overall_difficulty
+            .map_or(defaults.overall_difficulty, f64::from);
+        let approach_rate = difficulty.approach_rate
+            .map_or(overall_difficulty, f64::from);
+
+        DifficultyAttributes {
+            mode: self.mode(),
+            circle_size: difficulty.circle_size.map_or(defaults.circle_size, f64::from),
+            approach_rate,
+            overall_difficulty,
+            hp_drain_rate: difficulty.hp_drain_rate.map_or(defaults.hp_drain_rate, f64::from),
+            ..defaults
+        }
+    }
+}
+
+/// maps a 0-10 difficulty value onto the value at 0, 5 and 10, linearly in between
+pub fn difficulty_range(difficulty: f64, min: f64, mid: f64, max: f64) -> f64 {
+    if difficulty > 5.0 {
+        mid + (max - mid) * (difficulty - 5.0) / 5.0
+    } else if difficulty < 5.0 {
+        mid - (mid - min) * (5.0 - difficulty) / 5.0
+    } else {
+        mid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spins_required_by_od() {
+        let spins = |overall_difficulty| DifficultyAttributes { overall_difficulty, ..Default::default() }
+            .spins_required(4000.0);
+
+        assert_eq!(spins(0.0), 6);
+        assert_eq!(spins(5.0), 10);
+        assert_eq!(spins(10.0), 15);
+    }
+
+    fn at(mode: GameMode, circle_size: f64, approach_rate: f64, overall_difficulty: f64) -> DifficultyAttributes {
+        DifficultyAttributes { mode, circle_size, approach_rate, overall_difficulty, ..Default::default() }
+    }
+
+    #[test]
+    fn preempt_and_fade_in_by_ar() {
+        let preempt = |approach_rate| at(GameMode::Osu, 5.0, approach_rate, 5.0).preempt();
+        assert_eq!(preempt(0.0), 1800.0);
+        assert_eq!(preempt(5.0), 1200.0);
+        assert_eq!(preempt(10.0), 450.0);
+
+        //approach times never get below the minimum without a clock rate, so the fade-in stays whole
+        for approach_rate in [0.0, 5.0, 10.0] {
+            assert_eq!(at(GameMode::Osu, 5.0, approach_rate, 5.0).fade_in(), 400.0);
+        }
+    }
+
+    #[test]
+    fn hard_rock_caps_and_easy_halves() {
+        let hard_rock = at(GameMode::Osu, 4.0, 8.0, 9.0).with_mods(Mods::HARD_ROCK);
+        assert!((hard_rock.circle_size - 5.2).abs() < 1e-9);
+        assert_eq!(hard_rock.approach_rate, 10.0);
+        assert_eq!(hard_rock.overall_difficulty, 10.0);
+
+        let easy = at(GameMode::Osu, 4.0, 8.0, 9.0).with_mods(Mods::EASY);
+        assert_eq!((easy.circle_size, easy.approach_rate, easy.overall_difficulty), (2.0, 4.0, 4.5));
+
+        //the key count doesn't change
+        let mania = at(GameMode::Mania, 7.0, 5.0, 5.0).with_mods(Mods::HARD_ROCK);
+        assert_eq!(mania.circle_size, 7.0);
+
+        let double_time = at(GameMode::Osu, 5.0, 9.0, 5.0).with_mods(Mods::DOUBLE_TIME);
+        assert_eq!(double_time.approach_rate, 9.0);
+        assert!((double_time.effective_approach_rate() - 31.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hit_windows_by_mode() {
+        let windows = |mode, overall_difficulty| at(mode, 5.0, 5.0, overall_difficulty).hit_windows();
+
+        assert_eq!(windows(GameMode::Osu, 5.0), Some(HitWindows { great: 50.0, ok: 100.0, meh: Some(150.0) }));
+        assert_eq!(windows(GameMode::Osu, 10.0), Some(HitWindows { great: 20.0, ok: 60.0, meh: Some(100.0) }));
+        assert_eq!(windows(GameMode::Taiko, 5.0), Some(HitWindows { great: 35.0, ok: 80.0, meh: None }));
+        assert_eq!(windows(GameMode::Taiko, 0.0), Some(HitWindows { great: 50.0, ok: 120.0, meh: None }));
+        assert_eq!(windows(GameMode::Catch, 5.0), None);
+        assert_eq!(windows(GameMode::Mania, 5.0), Some(HitWindows { great: 49.0, ok: 112.0, meh: Some(136.0) }));
+    }
+
+    #[test]
+    fn scale_and_radius_by_cs() {
+        let circle = at(GameMode::Osu, 5.0, 5.0, 5.0);
+        assert_eq!((circle.scale(), circle.radius()), (0.5, 32.0));
+
+        let circle = at(GameMode::Osu, 4.0, 5.0, 5.0);
+        assert!((circle.scale() - 0.57).abs() < 1e-9);
+        assert!((circle.radius() - 36.48).abs() < 1e-9);
+    }
+
+    #[test]
+    fn approach_rate_falls_back_to_od() {
+        let map = Beatmap::load_from_string(String::from(
+            "osu file format v7\n\n[Difficulty]\nHPDrainRate:4\nCircleSize:3\nOverallDifficulty:7\n"
+        )).unwrap();
+
+        let attributes = map.difficulty_attributes();
+        assert_eq!(attributes.approach_rate, 7.0);
+        assert_eq!((attributes.circle_size, attributes.hp_drain_rate), (3.0, 4.0));
+    }
+}
@@ -0,0 +1,378 @@
+use crate::sort;
+
+use super::{
+    Beatmap,
+    pos::PLAYFIELD_WIDTH,
+    random::LegacyRandom,
+    sections::{HitObject, ObjectParams},
+    slider::Slider,
+    timing::TimingMap,
+};
+
+const BASE_CATCHER_SIZE: f64 = 106.75;
+/// the part of the catcher that actually catches
+pub const ALLOWED_CATCH_RANGE: f64 = 0.8;
+
+/// seed of the generator for HR offsets and banana positions
+const RNG_SEED: i32 = 1337;
+
+/// catcher width in osu!pixels at `circle_size`, only the part that catches
+pub fn catcher_width(circle_size: f64) -> f64 {
+    let scale = 1.0 - 0.7 * (circle_size - 5.0) / 5.0;
+    scale * BASE_CATCHER_SIZE * ALLOWED_CATCH_RANGE
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchObjectKind {
+    Fruit,
+    Droplet,
+    TinyDroplet,
+    Banana,
+}
+
+/// something that falls in a catch map
+#[derive(Debug, Clone, Copy)]
+pub struct CatchObject {
+    pub kind: CatchObjectKind,
+    pub start_time: f64,
+    pub x: f32, //with HR and random offsets
+    pub hyper_dash: bool, //the next fruit or droplet can only be reached with a hyperdash
+    pub distance_to_hyper_dash: f32, //how much room there was to spare otherwise
+    pub combo_colour: usize, //of the object it came from, see `Beatmap::combo_colour_indices`
+}
+
+impl CatchObject {
+
+    fn new(kind: CatchObjectKind, start_time: f64, x: f32) -> Self {
+        Self {
+            kind,
+            start_time,
+            x: x.clamp(0.0, PLAYFIELD_WIDTH),
+            hyper_dash: false,
+            distance_to_hyper_dash: 0.0,
+            combo_colour: 0,
+        }
+    }
+
+    /// fruits and droplets, the objects that give combo
+    pub fn is_palpable(&self) -> bool {
+        matches!(self.kind, CatchObjectKind::Fruit | CatchObjectKind::Droplet)
+    }
+}
+
+impl Beatmap {
+
+    /// fruits, droplets and banana showers the way the game generates them, in time order with hyperdashes marked.
+    ///
+    /// `circle_size` decides the catcher width for hyperdashes, pass the one with mods applied
+    pub fn catch_objects(&self, circle_size: f64, hard_rock: bool) -> Vec<CatchObject> {
+        let timing = self.timing_map();
+        let mut rng = LegacyRandom::new(RNG_SEED);
+        let mut objects = Vec::new();
+
+        //HR moves fruits based on the fruit before them
+        let mut last_position: Option<f32> = None;
+        let mut last_start_time = 0.0;
+
+        for (hit_object, combo_colour) in self.hit_objects.iter().flatten().zip(self.combo_colour_indices()) {
+            let start_time = hit_object.time as f64;
+            let first_new = objects.len();
+
+            match &hit_object.object_params {
+                ObjectParams::None => {
+                    let mut x = hit_object.x as f32;
+                    if hard_rock {
+                        apply_hard_rock_offset(&mut x, start_time, &mut last_position, &mut last_start_time, &mut rng);
+                    }
+                    objects.push(CatchObject::new(CatchObjectKind::Fruit, start_time, x));
+                }
+                ObjectParams::Slider(params) => {
+                    //the game used the last control point and the start time here, not the real end
+                    let last_point = params.curve_points.last().map_or(hit_object.x as f32, |(x, _)| *x as f32);
+                    last_position = Some(last_point);
+                    last_start_time = start_time;
+
+                    juice_stream(self, &timing, hit_object, &mut objects, &mut rng);
+                }
+                ObjectParams::EndTime(end_time) => {
+                    //every banana takes a position, then a type, a rotation and a colour
+                    for time in banana_times(start_time, *end_time as f64) {
+                        let x = (rng.next_double() * PLAYFIELD_WIDTH as f64) as f32;
+                        rng.next_int();
+                        rng.next_int();
+                        rng.next_int();
+                        objects.push(CatchObject::new(CatchObjectKind::Banana, time, x));
+                    }
+                }
+            }
+
+            for object in &mut objects[first_new..] {
+                object.combo_colour = combo_colour;
+            }
+        }
+
+        //hyper dashes are marked in the order of the game's unstable sort, what's caught in its stable one
+        mark_hyper_dashes(&mut objects, circle_size);
+        objects.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        objects
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum JuiceEvent {
+    Fruit,
+    Droplet,
+    LegacyLastTick, //only matters for where tiny droplets go
+}
+
+/// head, repeats and tail become fruits, ticks become droplets and tiny droplets fill the gaps
+fn juice_stream(beatmap: &Beatmap, timing: &TimingMap, hit_object: &HitObject, objects: &mut Vec<CatchObject>, rng: &mut LegacyRandom) {
+    let slider = match Slider::new(beatmap, timing, hit_object) {
+        Some(slider) => slider,
+        None => return,
+    };
+    let slider_timing = &slider.timing;
+
+    let mut events = vec![(slider_timing.start_time, 0.0, JuiceEvent::Fruit)];
+    for span in 0..slider_timing.spans {
+        events.extend(slider_timing.ticks.iter()
+            .filter(|tick| tick.span == span)
+            .map(|tick| (tick.time, tick.progress, JuiceEvent::Droplet)));
+
+        if span + 1 < slider_timing.spans {
+            let progress = if span % 2 == 0 { 1.0 } else { 0.0 };
+            events.push((slider_timing.start_time + (span + 1) as f64 * slider_timing.span_duration, progress, JuiceEvent::Fruit));
+        }
+    }
+    let end_progress = if slider_timing.spans % 2 == 1 { 1.0 } else { 0.0 };
+    events.push((slider_timing.legacy_last_tick_time(), end_progress, JuiceEvent::LegacyLastTick));
+    events.push((slider_timing.end_time(), end_progress, JuiceEvent::Fruit));
+
+    let mut last_event: Option<(f64, f64)> = None;
+    for (time, progress, event) in events {
+        if let Some((last_time, last_progress)) = last_event {
+            //the game worked with whole milliseconds here
+            let since_last = (time as i64 - last_time as i64) as f64;
+
+            //each tiny droplet sits on the path between the two events, with a random offset
+            for t in tiny_droplet_offsets(since_last) {
+                let x = slider.path.position_at(last_progress + t / since_last * (progress - last_progress)).x;
+                let offset = (rng.next_range(-20, 20) as f32).clamp(-x, PLAYFIELD_WIDTH - x);
+                objects.push(CatchObject::new(CatchObjectKind::TinyDroplet, last_time + t, x + offset));
+            }
+        }
+        last_event = Some((time, progress));
+
+        let x = slider.path.position_at(progress).x;
+        match event {
+            JuiceEvent::Fruit => objects.push(CatchObject::new(CatchObjectKind::Fruit, time, x)),
+            JuiceEvent::Droplet => {
+                //and each droplet a random rotation
+                rng.next_int();
+                objects.push(CatchObject::new(CatchObjectKind::Droplet, time, x));
+            }
+            JuiceEvent::LegacyLastTick => {}
+        }
+    }
+}
+
+/// times of the tiny droplets after an event, relative to it. they're at most 100 ms apart
+fn tiny_droplet_offsets(since_last: f64) -> Vec<f64> {
+    if since_last <= 80.0 {
+        return Vec::new();
+    }
+
+    let mut time_between = since_last;
+    while time_between > 100.0 {
+        time_between /= 2.0;
+    }
+
+    let mut offsets = Vec::new();
+    let mut t = time_between;
+    while t < since_last {
+        offsets.push(t);
+        t += time_between;
+    }
+    offsets
+}
+
+/// bananas fall at most 100 ms apart for the whole spinner
+fn banana_times(start_time: f64, end_time: f64) -> Vec<f64> {
+    let mut spacing = end_time - start_time;
+    while spacing > 100.0 {
+        spacing /= 2.0;
+    }
+    if spacing <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut times = Vec::new();
+    let mut time = start_time;
+    while time <= end_time {
+        times.push(time);
+        time += spacing;
+    }
+    times
+}
+
+/// HR pushes fruits further in the direction they were already going, and nudges stacked ones randomly
+fn apply_hard_rock_offset(x: &mut f32, start_time: f64, last_position: &mut Option<f32>, last_start_time: &mut f64, rng: &mut LegacyRandom) {
+    let position = *x;
+
+    //the game treats a fruit at x 0 as if there was no fruit before
+    let last = match *last_position {
+        Some(last) if last != 0.0 => last,
+        _ => {
+            *last_position = Some(position);
+            *last_start_time = start_time;
+            return;
+        }
+    };
+
+    let position_diff = position - last;
+    //the game worked with whole milliseconds here
+    let time_diff = (start_time - *last_start_time) as i32;
+
+    if time_diff > 1000 {
+        *last_position = Some(position);
+        *last_start_time = start_time;
+        return;
+    }
+
+    if position_diff == 0.0 {
+        let right = rng.next_bool();
+        let offset = (rng.next_double_range(0.0, (time_diff as f64 / 4.0).max(0.0)) as f32).min(20.0);
+
+        if right {
+            *x += if *x + offset <= PLAYFIELD_WIDTH { offset } else { -offset };
+        } else {
+            *x -= if *x - offset >= 0.0 { offset } else { -offset };
+        }
+        return;
+    }
+
+    if position_diff.abs() < (time_diff / 3) as f32 {
+        if position_diff > 0.0 {
+            if *x + position_diff < PLAYFIELD_WIDTH {
+                *x += position_diff;
+            }
+        } else if *x + position_diff > 0.0 {
+            *x += position_diff;
+        }
+    }
+
+    *last_position = Some(*x);
+    *last_start_time = start_time;
+}
+
+/// marks the fruits and droplets whose next one is too far to reach by dashing
+fn mark_hyper_dashes(objects: &mut [CatchObject], circle_size: f64) {
+    //the game uses the whole catcher here, not only the part that catches
+    let half_catcher_width = (catcher_width(circle_size) / 2.0 / ALLOWED_CATCH_RANGE) as f32;
+
+    let mut palpable: Vec<usize> = (0..objects.len())
+        .filter(|&i| objects[i].is_palpable())
+        .collect();
+    sort::list_sort(&mut palpable, |&a, &b| objects[a].start_time.total_cmp(&objects[b].start_time));
+
+    let mut last_direction = 0;
+    let mut last_excess = half_catcher_width;
+
+    for pair in palpable.windows(2) {
+        let next = objects[pair[1]];
+        let current = &mut objects[pair[0]];
+
+        let direction = if next.x > current.x { 1 } else { -1 };
+        //on whole milliseconds like the game, with a quarter of a frame of leniency
+        let time_to_next = (next.start_time as i32 - current.start_time as i32) as f32 - 1000.0 / 60.0 / 4.0;
+        let distance_to_next = (next.x - current.x).abs()
+            - if last_direction == direction { last_excess } else { half_catcher_width };
+        let distance_to_hyper = time_to_next - distance_to_next;
+
+        if distance_to_hyper < 0.0 {
+            current.hyper_dash = true;
+            current.distance_to_hyper_dash = 0.0;
+            last_excess = half_catcher_width;
+        } else {
+            current.hyper_dash = false;
+            current.distance_to_hyper_dash = distance_to_hyper;
+            last_excess = distance_to_hyper.clamp(0.0, half_catcher_width);
+        }
+
+        last_direction = direction;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1x slider multiplier, one tick a beat and 500 ms beats
+    fn catch_objects(objects: &str, hard_rock: bool) -> Vec<CatchObject> {
+        Beatmap::load_from_string(format!(
+            "osu file format v14\n\n[Difficulty]\nSliderMultiplier:1\nSliderTickRate:1\n\n\
+             [TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n{}\n", objects
+        )).unwrap()
+            .catch_objects(5.0, hard_rock)
+    }
+
+    fn of_kind(objects: &[CatchObject], kind: CatchObjectKind) -> Vec<(f64, f32)> {
+        objects.iter()
+            .filter(|o| o.kind == kind)
+            .map(|o| (o.start_time, o.x))
+            .collect()
+    }
+
+    #[test]
+    fn juice_stream() {
+        //a second long, with a tick half way and the legacy last tick 36 ms before the end
+        let objects = catch_objects("0,192,1000,2,0,L|200:192,1,200", false);
+
+        assert_eq!(of_kind(&objects, CatchObjectKind::Fruit), [(1000.0, 0.0), (2000.0, 200.0)]);
+        assert_eq!(of_kind(&objects, CatchObjectKind::Droplet), [(1500.0, 100.0)]);
+        //62.5 ms apart up to the tick and 58 ms apart up to the last tick, with random offsets
+        assert_eq!(of_kind(&objects, CatchObjectKind::TinyDroplet), [
+            (1062.5, 0.0), (1125.0, 15.0), (1187.5, 35.5), (1250.0, 65.0), (1312.5, 79.5), (1375.0, 76.0), (1437.5, 87.5),
+            (1558.0, 105.5), (1616.0, 114.0), (1674.0, 134.5), (1732.0, 164.0), (1790.0, 166.5), (1848.0, 167.0), (1906.0, 192.5),
+        ]);
+    }
+
+    #[test]
+    fn hard_rock_offsets() {
+        let objects = catch_objects(
+            "100,192,0,1,0\n\
+             150,192,300,1,0\n\
+             200,192,600,1,0\n\
+             400,192,700,1,0\n\
+             100,192,2000,1,0\n\
+             50,192,2100,1,0\n\
+             60,192,2400,1,0",
+            true,
+        );
+        let x: Vec<f32> = objects.iter().map(|o| o.x).collect();
+        //pushed on by the distance from the last fruit when that's under a third of the time since it,
+        //moved 17 to the left at random when in the same place, and left alone after a second
+        assert_eq!(x, [100.0, 200.0, 183.0, 400.0, 100.0, 50.0, 70.0]);
+
+        let objects = catch_objects("100,192,0,1,0\n150,192,300,1,0", false);
+        let x: Vec<f32> = objects.iter().map(|o| o.x).collect();
+        assert_eq!(x, [100.0, 150.0]);
+    }
+
+    #[test]
+    fn hyper_dashes_on_whole_milliseconds() {
+        let hyper_dash = |times: [f64; 2], x: f32| {
+            let mut objects = [
+                CatchObject::new(CatchObjectKind::Fruit, times[0], 0.0),
+                CatchObject::new(CatchObjectKind::Droplet, times[1], x),
+            ];
+            mark_hyper_dashes(&mut objects, 5.0);
+            objects[0].hyper_dash
+        };
+
+        //both pairs are 100 ms apart once truncated, 95.83 with the leniency. half the catcher is 53.375 wide,
+        //so they have 96.3 and 95.5 to cover, where the exact times would have given 96.73 and 95.03
+        assert!(hyper_dash([0.0, 100.9], 149.675));
+        assert!(!hyper_dash([0.9, 100.1], 148.875));
+    }
+}
@@ -0,0 +1,81 @@
+use super::{Beatmap, sections::HitObjectKind};
+
+impl Beatmap {
+
+    /// which combo colour each hit object takes, to be wrapped around the skin's or map's colours.
+    ///
+    /// the first object and the one after a spinner always start a new combo.
+    /// like the game, the first combo already counts as one, so it takes the second colour
+    pub fn combo_colour_indices(&self) -> Vec<usize> {
+        let mut indices = Vec::new();
+        let mut colour_index = 0;
+        let mut after_spinner = false;
+
+        for (i, hit_object) in self.hit_objects.iter().flatten().enumerate() {
+            if i == 0 || after_spinner || hit_object.new_combo {
+                colour_index += 1 + hit_object.combo_skip;
+            }
+            after_spinner = matches!(hit_object.kind, HitObjectKind::Spinner);
+            indices.push(colour_index);
+        }
+        indices
+    }
+
+    /// the number each hit object shows, counting up from 1 in every combo
+    pub fn combo_numbers(&self) -> Vec<usize> {
+        let mut numbers = Vec::new();
+        let mut number = 0;
+        let mut after_spinner = false;
+
+        for (i, hit_object) in self.hit_objects.iter().flatten().enumerate() {
+            if i == 0 || after_spinner || hit_object.new_combo {
+                number = 0;
+            }
+            number += 1;
+            after_spinner = matches!(hit_object.kind, HitObjectKind::Spinner);
+            numbers.push(number);
+        }
+        numbers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_objects(objects: &str) -> Beatmap {
+        Beatmap::load_from_string(format!("osu file format v14\n\n[HitObjects]\n{}\n", objects)).unwrap()
+    }
+
+    #[test]
+    fn first_object_starts_a_combo() {
+        //no new combo bit, and one with it skipping a colour
+        let map = parse_objects("64,64,0,1,0\n128,64,100,1,0");
+        assert_eq!(map.combo_colour_indices(), [1, 1]);
+        assert_eq!(map.combo_numbers(), [1, 2]);
+
+        let map = parse_objects("64,64,0,21,0\n128,64,100,1,0");
+        assert_eq!(map.combo_colour_indices(), [2, 2]);
+        assert_eq!(map.combo_numbers(), [1, 2]);
+    }
+
+    #[test]
+    fn new_combos_skip_colours() {
+        //new combo bit with 0, 2 and 7 colours skipped
+        let map = parse_objects("64,64,0,1,0\n128,64,100,5,0\n192,64,200,37,0\n256,64,300,1,0\n320,64,400,117,0");
+        assert_eq!(map.combo_colour_indices(), [1, 2, 5, 5, 13]);
+        assert_eq!(map.combo_numbers(), [1, 1, 1, 2, 1]);
+    }
+
+    #[test]
+    fn object_after_a_spinner_starts_a_combo() {
+        let map = parse_objects("64,64,0,1,0\n256,192,100,12,0,1000\n64,64,1200,1,0\n128,64,1300,1,0");
+        assert_eq!(map.combo_colour_indices(), [1, 2, 3, 3]);
+        assert_eq!(map.combo_numbers(), [1, 1, 1, 2]);
+
+        //even when the spinner doesn't start one itself
+        let map = parse_objects("64,64,0,1,0\n256,192,100,8,0,1000\n64,64,1200,1,0");
+        assert_eq!(map.combo_colour_indices(), [1, 1, 2]);
+        assert_eq!(map.combo_numbers(), [1, 2, 1]);
+    }
+}
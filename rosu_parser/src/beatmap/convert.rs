@@ -0,0 +1,187 @@
+use crate::mods::Mods;
+
+use super::{
+    Beatmap,
+    sections::{GameMode, HitObject, HitObjectKind, ObjectParams},
+    slider::Slider,
+    timing::TimingMap,
+};
+
+mod mania;
+
+/// converts scale slider lengths by this when working out drum roll speed
+const TAIKO_VELOCITY_MULTIPLIER: f64 = 1.4;
+const BASE_SCORING_DISTANCE: f64 = 100.0;
+
+impl Beatmap {
+
+    /// the map the way the game plays it in `mode`.
+    ///
+    /// only osu!standard maps convert, other maps give `None` for any mode but their own.
+    /// key mods pick the key count of mania converts, the other mods don't change a convert
+    pub fn convert(&self, mode: GameMode, mods: Mods) -> Option<Beatmap> {
+        let map_mode = self.mode();
+        if mode == map_mode {
+            return Some(self.clone());
+        }
+        if map_mode != GameMode::Osu {
+            return None;
+        }
+
+        let mut converted = match mode {
+            GameMode::Taiko => self.convert_taiko(),
+            GameMode::Mania => mania::convert(self, mods),
+            //catch stores its objects like osu!standard, the fruits themselves come from `catch_objects`
+            GameMode::Catch | GameMode::Osu => self.clone(),
+        };
+        converted.general.get_or_insert_with(Default::default).mode = Some(mode.id());
+        Some(converted)
+    }
+
+    /// circles stay hits, spinners become swells and sliders become drum rolls, unless they're short and fast
+    /// enough to be split into hits
+    fn convert_taiko(&self) -> Beatmap {
+        let timing = self.timing_map();
+        let mut hit_objects = Vec::new();
+
+        for hit_object in self.hit_objects.iter().flatten() {
+            let split = match &hit_object.object_params {
+                ObjectParams::Slider(params) => taiko_hits(self, &timing, hit_object).map(|hits| (params, hits)),
+                _ => None,
+            };
+
+            let (params, hits) = match split {
+                Some(split) => split,
+                None => {
+                    hit_objects.push(hit_object.clone());
+                    continue;
+                }
+            };
+
+            //each hit takes the sound of the next slider edge, wrapping around
+            for (i, time) in hits.into_iter().enumerate() {
+                let hit_sound = match params.edge_sounds.len() {
+                    0 => hit_object.hit_sound,
+                    n => params.edge_sounds[i % n],
+                };
+                hit_objects.push(HitObject {
+                    x: hit_object.x,
+                    y: hit_object.y,
+                    time: time.round() as isize,
+                    kind: HitObjectKind::HitCircle,
+                    hit_sound,
+                    object_params: ObjectParams::None,
+                    hit_sample: hit_object.hit_sample.clone(),
+                    //the combo carries on from the first hit
+                    new_combo: hit_object.new_combo && i == 0,
+                    combo_skip: hit_object.combo_skip,
+                });
+            }
+        }
+
+        //split sliders can overlap the objects after them
+        hit_objects.sort_by_key(|o| o.time);
+
+        Beatmap {
+            hit_objects: Some(hit_objects),
+            ..self.clone()
+        }
+    }
+}
+
+/// hit times for a slider that's too short and fast to be a drum roll, the way the game decides it
+fn taiko_hits(beatmap: &Beatmap, timing: &TimingMap, hit_object: &HitObject) -> Option<Vec<f64>> {
+    let slider = Slider::new(beatmap, timing, hit_object)?;
+    let start_time = hit_object.time as f64;
+    let spans = slider.timing.spans as f64;
+
+    let difficulty = beatmap.difficulty.as_ref();
+    let slider_multiplier = difficulty.and_then(|d| d.slider_multiplier).unwrap_or(1.4);
+    let tick_rate = difficulty.and_then(|d| d.slider_tick_rate).unwrap_or(1.0);
+
+    let distance = slider.path.length() * spans * TAIKO_VELOCITY_MULTIPLIER;
+
+    let timing_beat_length = timing.beat_length_at(start_time);
+    let mut beat_length = timing_beat_length / timing.slider_velocity_at(start_time);
+
+    let scoring_point_distance = BASE_SCORING_DISTANCE * slider_multiplier * TAIKO_VELOCITY_MULTIPLIER / tick_rate;
+    let taiko_velocity = scoring_point_distance * tick_rate;
+    let taiko_duration = (distance / taiko_velocity * beat_length).trunc();
+
+    let osu_velocity = taiko_velocity * 1000.0 / beat_length;
+
+    //old maps used the slider velocity adjusted beat length here too
+    if beatmap.version.unwrap_or(14) >= 8 {
+        beat_length = timing_beat_length;
+    }
+
+    let tick_spacing = (beat_length / tick_rate).min(taiko_duration / spans);
+    if tick_spacing <= 0.0 || distance / osu_velocity * 1000.0 >= 2.0 * beat_length {
+        return None;
+    }
+
+    let mut hits = Vec::new();
+    let mut time = start_time;
+    while time <= start_time + taiko_duration + tick_spacing / 8.0 {
+        hits.push(time);
+        time += tick_spacing;
+    }
+    Some(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //the expected hits are what rosu-pp 0.10.0's port of lazer's taiko converter gives for the same maps
+
+    /// 1x slider multiplier, one tick a beat and 500 ms beats, with 2x slider velocity from 10 seconds in
+    fn convert_to_taiko(objects: &str) -> Vec<HitObject> {
+        Beatmap::load_from_string(format!(
+            "osu file format v14\n\n[Difficulty]\nSliderMultiplier:1\nSliderTickRate:1\n\n\
+             [TimingPoints]\n0,500,4,1,0,100,1,0\n10000,-50,4,1,0,100,0,0\n\n[HitObjects]\n{}\n", objects
+        )).unwrap()
+            .convert(GameMode::Taiko, Mods::empty())
+            .unwrap()
+            .hit_objects
+            .unwrap()
+    }
+
+    fn times(hit_objects: &[HitObject]) -> Vec<isize> {
+        hit_objects.iter().map(|o| o.time).collect()
+    }
+
+    #[test]
+    fn short_sliders_split_into_hits() {
+        //a beat long, it would take a whole second to play at the osu!standard speed
+        let hits = convert_to_taiko("0,0,1000,2,0,L|100:0,1,100");
+        assert_eq!(times(&hits), [1000, 1500]);
+        assert!(hits.iter().all(|o| matches!(o.kind, HitObjectKind::HitCircle)));
+
+        //three spans of half a beat, a hit on every edge
+        let hits = convert_to_taiko("0,0,1000,2,0,L|50:0,3,50");
+        assert_eq!(times(&hits), [1000, 1250, 1500, 1750]);
+    }
+
+    #[test]
+    fn long_sliders_stay_drum_rolls() {
+        let rolls = convert_to_taiko("0,0,1000,2,0,L|400:0,1,400");
+        assert_eq!(rolls.len(), 1);
+        assert!(matches!(rolls[0].object_params, ObjectParams::Slider(_)));
+    }
+
+    #[test]
+    fn slider_velocity_shortens_the_hits() {
+        assert_eq!(times(&convert_to_taiko("0,0,10000,2,0,L|100:0,1,100")), [10000, 10250]);
+    }
+
+    #[test]
+    fn hits_take_the_edge_sounds() {
+        let hits = convert_to_taiko("0,0,1000,6,0,L|100:0,1,100,2|8");
+        let sounds: Vec<usize> = hits.iter().map(|o| o.hit_sound).collect();
+        assert_eq!(sounds, [2, 8]);
+
+        let new_combos: Vec<bool> = hits.iter().map(|o| o.new_combo).collect();
+        assert_eq!(new_combos, [true, false]);
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::VecDeque;
+
+use bitflags::bitflags;
+
+use crate::mods::Mods;
+
+use super::super::{
+    Beatmap,
+    attributes::DifficultyAttributes,
+    pos::{Pos2, PLAYFIELD_WIDTH},
+    random::LegacyRandom,
+    sections::{HitObject, HitObjectKind, ObjectParams},
+    timing::TimingMap,
+};
+
+use self::{
+    end_time::EndTimePatternGenerator,
+    hit_object::HitObjectPatternGenerator,
+    path::PathPatternGenerator,
+};
+
+mod end_time;
+mod hit_object;
+mod path;
+
+/// how many of the last notes the density is taken over
+const MAX_NOTES_FOR_DENSITY: usize = 7;
+
+bitflags! {
+    /// hints from the previous objects about what kind of pattern comes next
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct PatternType: u32 {
+        const REVERSE = 1 << 0; //mirror the previous pattern
+        const CYCLE = 1 << 1; //mirror a single previous note
+        const FORCE_STACK = 1 << 2; //same columns as the previous pattern
+        const STAIR = 1 << 3; //one column to the right
+        const REVERSE_STAIR = 1 << 4; //one column to the left
+        const KEEP_SINGLE = 1 << 5;
+        const LOW_PROBABILITY = 1 << 6; //less likely to make chords
+        const GATHERED = 1 << 7; //chords in neighbouring columns
+        const MIRROR = 1 << 8; //chords mirrored around the centre
+        const FORCE_NOT_STACK = 1 << 9; //avoid the columns of the previous pattern
+    }
+}
+
+/// a note, or a hold when it has an end time
+#[derive(Debug, Clone, Copy)]
+struct ManiaNote {
+    column: i32,
+    start_time: i32,
+    end_time: Option<i32>,
+    hit_sound: usize,
+}
+
+impl ManiaNote {
+
+    fn end_time(&self) -> i32 {
+        self.end_time.unwrap_or(self.start_time)
+    }
+}
+
+/// the notes generated for one object, or part of one
+#[derive(Debug, Clone, Default)]
+struct Pattern {
+    notes: Vec<ManiaNote>,
+}
+
+impl Pattern {
+
+    fn has_column(&self, column: i32) -> bool {
+        self.notes.iter().any(|n| n.column == column)
+    }
+
+    /// how many different columns have notes
+    fn column_count(&self) -> usize {
+        let mut columns: Vec<i32> = self.notes.iter().map(|n| n.column).collect();
+        columns.sort_unstable();
+        columns.dedup();
+        columns.len()
+    }
+
+    fn add(&mut self, note: ManiaNote) {
+        self.notes.push(note);
+    }
+
+    fn append(&mut self, other: &Pattern) {
+        self.notes.extend_from_slice(&other.notes);
+    }
+}
+
+/// what every pattern generator shares: the columns, the generator and the pattern before
+struct PatternContext<'a> {
+    rng: &'a mut LegacyRandom,
+    timing: &'a TimingMap,
+    total_columns: i32,
+    random_start: i32, //7K+1 keeps the special column out of random picks
+    conversion_difficulty: f64,
+    previous_pattern: &'a Pattern,
+}
+
+impl PatternContext<'_> {
+
+    /// the column under `x`, with `allow_special` 7K+1 only spreads over the normal columns
+    fn column_at(&self, x: f32, allow_special: bool) -> i32 {
+        if allow_special && self.total_columns == 8 {
+            let column_width = PLAYFIELD_WIDTH / 7.0;
+            return ((x / column_width).floor() as i32).clamp(0, 6) + 1;
+        }
+
+        let column_width = PLAYFIELD_WIDTH / self.total_columns as f32;
+        ((x / column_width).floor() as i32).clamp(0, self.total_columns - 1)
+    }
+
+    /// between 1 and 6 notes, `p2` is the chance for at least 2 and so on
+    fn random_note_count(&mut self, p2: f64, p3: f64, p4: f64, p5: f64, p6: f64) -> usize {
+        let value = self.rng.next_double();
+        if value >= 1.0 - p6 {
+            6
+        } else if value >= 1.0 - p5 {
+            5
+        } else if value >= 1.0 - p4 {
+            4
+        } else if value >= 1.0 - p3 {
+            3
+        } else if value >= 1.0 - p2 {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn random_column(&mut self, lower: Option<i32>, upper: Option<i32>) -> i32 {
+        let lower = lower.unwrap_or(self.random_start);
+        let upper = upper.unwrap_or(self.total_columns);
+        self.rng.next_range(lower, upper)
+    }
+
+    /// `initial` if it's free in `patterns`, otherwise random columns until one is
+    fn find_available_column(&mut self, initial: i32, patterns: &[&Pattern]) -> i32 {
+        self.find_available_column_with(initial, None, None, |ctx, _| ctx.random_column(None, None), |_| true, patterns)
+    }
+
+    /// `initial` if it's valid and free in `patterns`, otherwise the columns from `next_column` until one is
+    fn find_available_column_with(
+        &mut self,
+        initial: i32,
+        lower: Option<i32>,
+        upper: Option<i32>,
+        next_column: impl Fn(&mut Self, i32) -> i32,
+        validation: impl Fn(i32) -> bool,
+        patterns: &[&Pattern],
+    ) -> i32 {
+        let lower = lower.unwrap_or(self.random_start);
+        let upper = upper.unwrap_or(self.total_columns);
+        let is_valid = |column: i32| validation(column) && !patterns.iter().any(|p| p.has_column(column));
+
+        if is_valid(initial) {
+            return initial;
+        }
+
+        //the game gives up on the whole map here, keeping the column is kinder than looping forever
+        if !(lower..upper).any(is_valid) {
+            return initial;
+        }
+
+        let mut column = initial;
+        loop {
+            column = next_column(self, column);
+            if is_valid(column) {
+                return column;
+            }
+        }
+    }
+}
+
+/// the key count the game picks for a convert, from how many sliders and spinners it has
+fn key_count(beatmap: &Beatmap, attributes: &DifficultyAttributes, mods: Mods) -> i32 {
+    //key mods only change converts
+    let key_mods = [
+        Mods::KEY_1, Mods::KEY_2, Mods::KEY_3, Mods::KEY_4, Mods::KEY_5,
+        Mods::KEY_6, Mods::KEY_7, Mods::KEY_8, Mods::KEY_9,
+    ];
+    if let Some(i) = key_mods.iter().position(|key_mod| mods.contains(*key_mod)) {
+        return i as i32 + 1;
+    }
+
+    //the game rounds half to even
+    let circle_size = attributes.circle_size.round_ties_even();
+    let overall_difficulty = attributes.overall_difficulty.round_ties_even();
+
+    let hit_objects = beatmap.hit_objects.as_deref().unwrap_or_default();
+    if !hit_objects.is_empty() {
+        let with_duration = hit_objects.iter()
+            .filter(|o| !matches!(o.object_params, ObjectParams::None))
+            .count();
+        let percent_slider_or_spinner = with_duration as f64 / hit_objects.len() as f64;
+
+        if percent_slider_or_spinner < 0.2 {
+            return 7;
+        }
+        if percent_slider_or_spinner < 0.3 || circle_size >= 5.0 {
+            return if overall_difficulty > 5.0 { 7 } else { 6 };
+        }
+        if percent_slider_or_spinner > 0.6 {
+            return if overall_difficulty > 4.0 { 5 } else { 4 };
+        }
+    }
+
+    (overall_difficulty as i32 + 1).clamp(4, 7)
+}
+
+/// seed of the converter's generator, from the unrounded difficulty values
+fn seed(beatmap: &Beatmap, attributes: &DifficultyAttributes) -> i32 {
+    let difficulty = beatmap.difficulty.clone().unwrap_or_default();
+    let hp_drain_rate = difficulty.hp_drain_rate.unwrap_or(attributes.hp_drain_rate as f32);
+    let circle_size = difficulty.circle_size.unwrap_or(attributes.circle_size as f32);
+    let overall_difficulty = difficulty.overall_difficulty.unwrap_or(attributes.overall_difficulty as f32);
+    let approach_rate = attributes.approach_rate as f32;
+
+    (hp_drain_rate + circle_size).round_ties_even() as i32 * 20
+        + (overall_difficulty as f64 * 41.2) as i32
+        + approach_rate.round_ties_even() as i32
+}
+
+/// how dense and hard the original map is, which makes chords more likely
+fn conversion_difficulty(beatmap: &Beatmap, attributes: &DifficultyAttributes) -> f64 {
+    let hit_objects = beatmap.hit_objects.as_deref().unwrap_or_default();
+    let first = hit_objects.first().map_or(0.0, |o| o.time as f64);
+    let last = hit_objects.last().map_or(0.0, |o| o.time as f64);
+
+    let break_time: f64 = beatmap.events.iter()
+        .flat_map(|events| &events.breaks)
+        .map(|b| b.end() as f64 - b.start_time as f64)
+        .sum();
+
+    //drain time in whole seconds
+    let mut drain_time = ((last - first - break_time) / 1000.0) as i32;
+    if drain_time == 0 {
+        drain_time = 10000;
+    }
+
+    let difficulty = ((attributes.hp_drain_rate + attributes.approach_rate.clamp(4.0, 7.0)) / 1.5
+        + hit_objects.len() as f64 / drain_time as f64 * 9.0) / 38.0 * 5.0 / 1.15;
+    difficulty.min(12.0)
+}
+
+/// turns osu!standard objects into notes and holds one after another, each pattern depends on the ones before
+struct ManiaConverter {
+    timing: TimingMap,
+    rng: LegacyRandom,
+    total_columns: i32,
+    conversion_difficulty: f64,
+    last_pattern: Pattern,
+    last_time: f64,
+    last_position: Pos2,
+    last_stair: PatternType,
+    previous_note_times: VecDeque<f64>,
+    density: f64,
+}
+
+impl ManiaConverter {
+
+    fn new(beatmap: &Beatmap, total_columns: i32) -> Self {
+        let attributes = beatmap.difficulty_attributes();
+
+        Self {
+            timing: beatmap.timing_map(),
+            rng: LegacyRandom::new(seed(beatmap, &attributes)),
+            total_columns,
+            conversion_difficulty: conversion_difficulty(beatmap, &attributes),
+            last_pattern: Pattern::default(),
+            last_time: 0.0,
+            last_position: Pos2::ZERO,
+            last_stair: PatternType::STAIR,
+            previous_note_times: VecDeque::with_capacity(MAX_NOTES_FOR_DENSITY),
+            density: i32::MAX as f64,
+        }
+    }
+
+    /// average time between the last few notes
+    fn compute_density(&mut self, time: f64) {
+        if self.previous_note_times.len() == MAX_NOTES_FOR_DENSITY {
+            self.previous_note_times.pop_front();
+        }
+        self.previous_note_times.push_back(time);
+
+        if let (Some(first), Some(last)) = (self.previous_note_times.front(), self.previous_note_times.back()) {
+            if self.previous_note_times.len() >= 2 {
+                self.density = (last - first) / self.previous_note_times.len() as f64;
+            }
+        }
+    }
+
+    fn record_note(&mut self, time: f64, position: Pos2) {
+        self.last_time = time;
+        self.last_position = position;
+    }
+
+    fn convert_object(&mut self, beatmap: &Beatmap, hit_object: &HitObject) -> Vec<ManiaNote> {
+        let position = Pos2::new(hit_object.x as f32, hit_object.y as f32);
+        let start_time = hit_object.time as f64;
+
+        //circles count towards the density before their pattern is made, the rest after
+        if let ObjectParams::None = hit_object.object_params {
+            self.compute_density(start_time);
+        }
+
+        let previous_pattern = self.last_pattern.clone();
+        let mut ctx = PatternContext {
+            rng: &mut self.rng,
+            timing: &self.timing,
+            total_columns: self.total_columns,
+            random_start: if self.total_columns == 8 { 1 } else { 0 },
+            conversion_difficulty: self.conversion_difficulty,
+            previous_pattern: &previous_pattern,
+        };
+
+        let patterns = match &hit_object.object_params {
+            ObjectParams::Slider(_) => {
+                let generator = match PathPatternGenerator::new(beatmap, &ctx, hit_object) {
+                    Some(generator) => generator,
+                    None => return Vec::new(),
+                };
+                let patterns = generator.generate(&mut ctx);
+
+                for i in 0..=generator.span_count {
+                    let time = start_time + (generator.segment_duration * i) as f64;
+                    self.record_note(time, position);
+                    self.compute_density(time);
+                }
+                patterns
+            }
+            ObjectParams::EndTime(end_time) => {
+                let pattern = EndTimePatternGenerator::new(&ctx, hit_object, *end_time as i32).generate(&mut ctx);
+
+                //spinners sit in the middle, and don't count as a pattern for the next object
+                self.record_note(*end_time as f64, Pos2::new(256.0, 192.0));
+                self.compute_density(*end_time as f64);
+                return pattern.notes;
+            }
+            ObjectParams::None => {
+                let mut generator = HitObjectPatternGenerator::new(
+                    &ctx,
+                    hit_object,
+                    self.last_time,
+                    self.last_position,
+                    self.density,
+                    self.last_stair,
+                );
+                let pattern = generator.generate(&mut ctx);
+
+                self.last_stair = generator.stair_type;
+                self.record_note(start_time, position);
+                vec![pattern]
+            }
+        };
+
+        if let Some(last) = patterns.last() {
+            self.last_pattern = last.clone();
+        }
+        patterns.into_iter().flat_map(|p| p.notes).collect()
+    }
+}
+
+/// the whole map as notes and holds, with the key count as its circle size
+pub(super) fn convert(beatmap: &Beatmap, mods: Mods) -> Beatmap {
+    let total_columns = key_count(beatmap, &beatmap.difficulty_attributes(), mods);
+    let mut converter = ManiaConverter::new(beatmap, total_columns);
+
+    let mut notes: Vec<ManiaNote> = beatmap.hit_objects.iter()
+        .flatten()
+        .flat_map(|hit_object| converter.convert_object(beatmap, hit_object))
+        .collect();
+    notes.sort_by_key(|n| n.start_time);
+
+    //mania reads the column back from the middle of it
+    let column_width = PLAYFIELD_WIDTH / total_columns as f32;
+    let hit_objects = notes.into_iter()
+        .map(|note| HitObject {
+            x: ((note.column as f32 + 0.5) * column_width) as isize,
+            y: 192,
+            time: note.start_time as isize,
+            kind: if note.end_time.is_some() { HitObjectKind::ManiaHold } else { HitObjectKind::HitCircle },
+            hit_sound: note.hit_sound,
+            object_params: match note.end_time {
+                Some(end_time) => ObjectParams::EndTime(end_time.max(0) as usize),
+                None => ObjectParams::None,
+            },
+            hit_sample: Default::default(),
+            new_combo: false,
+            combo_skip: 0,
+        })
+        .collect();
+
+    let mut converted = Beatmap {
+        hit_objects: Some(hit_objects),
+        ..beatmap.clone()
+    };
+    converted.difficulty.get_or_insert_with(Default::default).circle_size = Some(total_columns as f32);
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::sections::GameMode;
+
+    //the expected key counts and columns are what rosu-pp 0.10.0's port of lazer's mania converter gives
+    //for the same maps
+
+    /// HP 5, CS 4, OD 5 and AR 5, with 1x slider multiplier and 500 ms beats
+    fn parse_map(objects: &str) -> Beatmap {
+        Beatmap::load_from_string(format!(
+            "osu file format v14\n\n[Difficulty]\nHPDrainRate:5\nCircleSize:4\nOverallDifficulty:5\nApproachRate:5\n\
+             SliderMultiplier:1\nSliderTickRate:1\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n{}\n", objects
+        )).unwrap()
+    }
+
+    /// (column, start time, end time of holds)
+    fn notes(map: &Beatmap) -> Vec<(i32, isize, Option<usize>)> {
+        let converted = map.convert(GameMode::Mania, Mods::empty()).unwrap();
+        let columns = converted.difficulty.unwrap().circle_size.unwrap();
+        let column_width = PLAYFIELD_WIDTH / columns;
+
+        converted.hit_objects.unwrap().iter()
+            .map(|o| {
+                let end_time = match o.object_params {
+                    ObjectParams::EndTime(end_time) => Some(end_time),
+                    _ => None,
+                };
+                ((o.x as f32 / column_width) as i32, o.time, end_time)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn key_count_from_sliders_and_spinners() {
+        let map = parse_map("100,100,0,1,0\n200,100,500,1,0");
+        let attributes = map.difficulty_attributes();
+        assert_eq!(key_count(&map, &attributes, Mods::empty()), 7);
+        assert_eq!(key_count(&map, &attributes, Mods::KEY_4), 4);
+
+        //more than 60% of them, with OD over 4
+        let map = parse_map("256,192,0,8,0,500\n256,192,1000,8,0,1500");
+        assert_eq!(key_count(&map, &map.difficulty_attributes(), Mods::empty()), 5);
+    }
+
+    #[test]
+    fn seed_from_the_difficulty() {
+        //(5 + 4) * 20 + 5 * 41.2 + 5
+        let map = parse_map("100,100,0,1,0");
+        assert_eq!(seed(&map, &map.difficulty_attributes()), 391);
+    }
+
+    #[test]
+    fn sliders_and_spinners() {
+        let map = parse_map(
+            "256,192,1000,8,0,2000\n\
+             100,100,3000,2,0,L|300:100,1,200\n\
+             256,192,5000,12,0,5050\n\
+             400,100,6000,2,0,L|200:100,1,200",
+        );
+        //5 keys. spinners under 100 ms become notes, and stay out of the columns of the slider before them
+        assert_eq!(notes(&map), [
+            (0, 1000, Some(2000)),
+            (2, 3000, Some(4000)),
+            (4, 5000, None),
+            (0, 6000, Some(7000)),
+        ]);
+    }
+}
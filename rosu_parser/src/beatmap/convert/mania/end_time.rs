@@ -0,0 +1,54 @@
+use super::{ManiaNote, Pattern, PatternContext};
+use super::super::super::sections::{HitObject, HIT_SOUND_FINISH};
+
+/// a single hold for a spinner, or a note for very short ones
+pub(super) struct EndTimePatternGenerator<'a> {
+    hit_object: &'a HitObject,
+    end_time: i32,
+    force_not_stack: bool,
+}
+
+impl<'a> EndTimePatternGenerator<'a> {
+
+    pub fn new(ctx: &PatternContext, hit_object: &'a HitObject, end_time: i32) -> Self {
+        Self {
+            hit_object,
+            end_time,
+            //stacking is only allowed when the previous pattern filled every column
+            force_not_stack: ctx.previous_pattern.column_count() as i32 != ctx.total_columns,
+        }
+    }
+
+    pub fn generate(&self, ctx: &mut PatternContext) -> Pattern {
+        let mut pattern = Pattern::default();
+        let start_time = self.hit_object.time as i32;
+        let hold = self.end_time - start_time >= 100;
+
+        let column = if ctx.total_columns == 8 {
+            //short spinners with a finish go in the special column of 7K+1
+            if self.hit_object.hit_sound & HIT_SOUND_FINISH != 0 && self.end_time - start_time < 1000 {
+                0
+            } else {
+                self.random_column(ctx, None)
+            }
+        } else {
+            self.random_column(ctx, Some(0))
+        };
+
+        pattern.add(ManiaNote {
+            column,
+            start_time,
+            end_time: if hold { Some(self.end_time) } else { None },
+            hit_sound: self.hit_object.hit_sound,
+        });
+        pattern
+    }
+
+    fn random_column(&self, ctx: &mut PatternContext, lower: Option<i32>) -> i32 {
+        let initial = ctx.random_column(lower, None);
+        let previous = ctx.previous_pattern;
+        let patterns: &[&Pattern] = if self.force_not_stack { &[previous] } else { &[] };
+
+        ctx.find_available_column_with(initial, lower, None, |ctx, _| ctx.random_column(lower, None), |_| true, patterns)
+    }
+}
@@ -0,0 +1,366 @@
+use super::{ManiaNote, Pattern, PatternContext, PatternType};
+use super::super::super::{
+    pos::Pos2,
+    sections::{HitObject, HIT_SOUND_CLAP, HIT_SOUND_FINISH},
+};
+
+/// notes for a circle, shaped by how far and how long ago the previous object was
+pub(super) struct HitObjectPatternGenerator<'a> {
+    hit_object: &'a HitObject,
+    convert_type: PatternType,
+    pub stair_type: PatternType, //which way the next stair goes
+}
+
+impl<'a> HitObjectPatternGenerator<'a> {
+
+    pub fn new(
+        ctx: &PatternContext,
+        hit_object: &'a HitObject,
+        previous_time: f64,
+        previous_position: Pos2,
+        density: f64,
+        last_stair: PatternType,
+    ) -> Self {
+        let start_time = hit_object.time as f64;
+        let beat_length = ctx.timing.beat_length_at(start_time);
+        let kiai = ctx.timing.kiai_at(start_time);
+
+        let position = Pos2::new(hit_object.x as f32, hit_object.y as f32);
+        let position_separation = position.distance(previous_position);
+        let time_separation = start_time - previous_time;
+
+        let mut convert_type = PatternType::empty();
+        if time_separation <= 80.0 {
+            //more than 187 bpm
+            convert_type |= PatternType::FORCE_NOT_STACK | PatternType::KEEP_SINGLE;
+        } else if time_separation <= 95.0 {
+            //more than 157 bpm
+            convert_type |= PatternType::FORCE_NOT_STACK | PatternType::KEEP_SINGLE | last_stair;
+        } else if time_separation <= 105.0 {
+            //more than 140 bpm
+            convert_type |= PatternType::FORCE_NOT_STACK | PatternType::LOW_PROBABILITY;
+        } else if time_separation <= 125.0 {
+            //more than 120 bpm
+            convert_type |= PatternType::FORCE_NOT_STACK;
+        } else if time_separation <= 135.0 && position_separation < 20.0 {
+            //more than 111 bpm stream
+            convert_type |= PatternType::CYCLE | PatternType::KEEP_SINGLE;
+        } else if time_separation <= 150.0 && position_separation < 20.0 {
+            //more than 100 bpm stream
+            convert_type |= PatternType::FORCE_STACK | PatternType::LOW_PROBABILITY;
+        } else if position_separation < 20.0 && density >= beat_length / 2.5 {
+            //low density stream
+            convert_type |= PatternType::REVERSE | PatternType::LOW_PROBABILITY;
+        } else if density < beat_length / 2.5 || kiai {
+            //high density, anything goes
+        } else {
+            convert_type |= PatternType::LOW_PROBABILITY;
+        }
+
+        if !convert_type.contains(PatternType::KEEP_SINGLE) {
+            if hit_object.hit_sound & HIT_SOUND_FINISH != 0 && ctx.total_columns != 8 {
+                convert_type |= PatternType::MIRROR;
+            } else if hit_object.hit_sound & HIT_SOUND_CLAP != 0 {
+                convert_type |= PatternType::GATHERED;
+            }
+        }
+
+        Self {
+            hit_object,
+            convert_type,
+            stair_type: last_stair,
+        }
+    }
+
+    pub fn generate(&mut self, ctx: &mut PatternContext) -> Pattern {
+        let pattern = self.generate_core(ctx);
+
+        //stairs turn around at the edges
+        for note in &pattern.notes {
+            if self.convert_type.contains(PatternType::STAIR) && note.column == ctx.total_columns - 1 {
+                self.stair_type = PatternType::REVERSE_STAIR;
+            }
+            if self.convert_type.contains(PatternType::REVERSE_STAIR) && note.column == ctx.random_start {
+                self.stair_type = PatternType::STAIR;
+            }
+        }
+
+        pattern
+    }
+
+    fn generate_core(&self, ctx: &mut PatternContext) -> Pattern {
+        let mut pattern = Pattern::default();
+        let previous = ctx.previous_pattern;
+        let (total_columns, random_start) = (ctx.total_columns, ctx.random_start);
+
+        if total_columns == 1 {
+            self.add_to_pattern(&mut pattern, 0);
+            return pattern;
+        }
+
+        let last_column = previous.notes.first().map_or(0, |n| n.column);
+
+        if self.convert_type.contains(PatternType::REVERSE) && !previous.notes.is_empty() {
+            //the previous notes in mirrored columns
+            for column in (random_start..total_columns).filter(|c| previous.has_column(*c)) {
+                self.add_to_pattern(&mut pattern, random_start + total_columns - column - 1);
+            }
+            return pattern;
+        }
+
+        if self.convert_type.contains(PatternType::CYCLE)
+            && previous.notes.len() == 1
+            //7K+1 shouldn't overload the special column
+            && (total_columns != 8 || last_column != 0)
+            //and a note in the centre column would only repeat
+            && (total_columns % 2 == 0 || last_column != total_columns / 2)
+        {
+            self.add_to_pattern(&mut pattern, random_start + total_columns - last_column - 1);
+            return pattern;
+        }
+
+        if self.convert_type.contains(PatternType::FORCE_STACK) && !previous.notes.is_empty() {
+            //the previous notes in the same columns
+            for column in (random_start..total_columns).filter(|c| previous.has_column(*c)) {
+                self.add_to_pattern(&mut pattern, column);
+            }
+            return pattern;
+        }
+
+        if previous.notes.len() == 1 {
+            if self.convert_type.contains(PatternType::STAIR) {
+                let mut column = last_column + 1;
+                if column == total_columns {
+                    column = random_start;
+                }
+                self.add_to_pattern(&mut pattern, column);
+                return pattern;
+            }
+
+            if self.convert_type.contains(PatternType::REVERSE_STAIR) {
+                let mut column = last_column - 1;
+                if column == random_start - 1 {
+                    column = total_columns - 1;
+                }
+                self.add_to_pattern(&mut pattern, column);
+                return pattern;
+            }
+        }
+
+        if self.convert_type.contains(PatternType::KEEP_SINGLE) {
+            return self.generate_random_notes(ctx, 1);
+        }
+
+        let low_probability = self.convert_type.contains(PatternType::LOW_PROBABILITY);
+
+        if self.convert_type.contains(PatternType::MIRROR) {
+            return if ctx.conversion_difficulty > 6.5 {
+                self.generate_random_pattern_with_mirrored(ctx, 0.12, 0.38, 0.12)
+            } else if ctx.conversion_difficulty > 4.0 {
+                self.generate_random_pattern_with_mirrored(ctx, 0.12, 0.17, 0.0)
+            } else {
+                self.generate_random_pattern_with_mirrored(ctx, 0.12, 0.0, 0.0)
+            };
+        }
+
+        if ctx.conversion_difficulty > 6.5 {
+            if low_probability {
+                self.generate_random_pattern(ctx, 0.78, 0.42, 0.0, 0.0)
+            } else {
+                self.generate_random_pattern(ctx, 1.0, 0.62, 0.0, 0.0)
+            }
+        } else if ctx.conversion_difficulty > 4.0 {
+            if low_probability {
+                self.generate_random_pattern(ctx, 0.35, 0.08, 0.0, 0.0)
+            } else {
+                self.generate_random_pattern(ctx, 0.52, 0.15, 0.0, 0.0)
+            }
+        } else if ctx.conversion_difficulty > 2.0 {
+            if low_probability {
+                self.generate_random_pattern(ctx, 0.18, 0.0, 0.0, 0.0)
+            } else {
+                self.generate_random_pattern(ctx, 0.45, 0.0, 0.0, 0.0)
+            }
+        } else {
+            self.generate_random_pattern(ctx, 0.0, 0.0, 0.0, 0.0)
+        }
+    }
+
+    /// up to `note_count` notes in random columns, fewer if they can't stack with the previous pattern
+    fn generate_random_notes(&self, ctx: &mut PatternContext, note_count: usize) -> Pattern {
+        let mut pattern = Pattern::default();
+        let previous = ctx.previous_pattern;
+
+        let allow_stacking = !self.convert_type.contains(PatternType::FORCE_NOT_STACK);
+        let mut note_count = note_count as i32;
+        if !allow_stacking {
+            note_count = note_count.min(ctx.total_columns - ctx.random_start - previous.column_count() as i32);
+        }
+
+        //clapped circles gather in neighbouring columns
+        let gathered = self.convert_type.contains(PatternType::GATHERED);
+        let next_column = |ctx: &mut PatternContext, last: i32| {
+            if gathered {
+                let column = last + 1;
+                if column == ctx.total_columns { ctx.random_start } else { column }
+            } else {
+                ctx.random_column(None, None)
+            }
+        };
+
+        let mut column = ctx.column_at(self.hit_object.x as f32, true);
+        for _ in 0..note_count {
+            column = if allow_stacking {
+                ctx.find_available_column_with(column, None, None, next_column, |_| true, &[&pattern])
+            } else {
+                ctx.find_available_column_with(column, None, None, next_column, |_| true, &[&pattern, previous])
+            };
+            self.add_to_pattern(&mut pattern, column);
+        }
+
+        pattern
+    }
+
+    /// circles with a clap and a finish can also use the special column of 7K+1
+    fn has_special_column(&self) -> bool {
+        self.hit_object.hit_sound & HIT_SOUND_CLAP != 0 && self.hit_object.hit_sound & HIT_SOUND_FINISH != 0
+    }
+
+    fn generate_random_pattern(&self, ctx: &mut PatternContext, p2: f64, p3: f64, p4: f64, p5: f64) -> Pattern {
+        let mut pattern = Pattern::default();
+
+        let note_count = self.random_note_count(ctx, p2, p3, p4, p5);
+        pattern.append(&self.generate_random_notes(ctx, note_count));
+
+        if ctx.random_start > 0 && self.has_special_column() {
+            self.add_to_pattern(&mut pattern, 0);
+        }
+
+        pattern
+    }
+
+    /// notes on one side and the same notes mirrored on the other, sometimes with one in the centre
+    fn generate_random_pattern_with_mirrored(&self, ctx: &mut PatternContext, centre_probability: f64, p2: f64, p3: f64) -> Pattern {
+        if self.convert_type.contains(PatternType::FORCE_NOT_STACK) {
+            return self.generate_random_pattern(ctx, 0.5 + p2 / 2.0, p2, (p2 + p3) / 2.0, p3);
+        }
+
+        let mut pattern = Pattern::default();
+        let (total_columns, random_start) = (ctx.total_columns, ctx.random_start);
+
+        let (note_count, add_to_centre) = self.random_note_count_mirrored(ctx, centre_probability, p2, p3);
+
+        let column_limit = (if total_columns % 2 == 0 { total_columns } else { total_columns - 1 }) / 2;
+        let mut column = ctx.random_column(None, Some(column_limit));
+
+        for _ in 0..note_count {
+            column = ctx.find_available_column_with(
+                column,
+                None,
+                Some(column_limit),
+                |ctx, _| ctx.random_column(None, Some(column_limit)),
+                |_| true,
+                &[&pattern],
+            );
+
+            self.add_to_pattern(&mut pattern, column);
+            self.add_to_pattern(&mut pattern, random_start + total_columns - column - 1);
+        }
+
+        if add_to_centre {
+            self.add_to_pattern(&mut pattern, total_columns / 2);
+        }
+
+        if random_start > 0 && self.has_special_column() {
+            self.add_to_pattern(&mut pattern, 0);
+        }
+
+        pattern
+    }
+
+    /// the note count with chances capped for low key counts, clapped circles always get a chord
+    fn random_note_count(&self, ctx: &mut PatternContext, mut p2: f64, mut p3: f64, mut p4: f64, mut p5: f64) -> usize {
+        match ctx.total_columns {
+            2 => {
+                p2 = 0.0;
+                p3 = 0.0;
+                p4 = 0.0;
+                p5 = 0.0;
+            }
+            3 => {
+                p2 = p2.min(0.1);
+                p3 = 0.0;
+                p4 = 0.0;
+                p5 = 0.0;
+            }
+            4 => {
+                p2 = p2.min(0.23);
+                p3 = p3.min(0.04);
+                p4 = 0.0;
+                p5 = 0.0;
+            }
+            5 => {
+                p3 = p3.min(0.15);
+                p4 = p4.min(0.03);
+                p5 = 0.0;
+            }
+            _ => {}
+        }
+
+        if self.hit_object.hit_sound & HIT_SOUND_CLAP != 0 {
+            p2 = 1.0;
+        }
+
+        ctx.random_note_count(p2, p3, p4, p5, 0.0)
+    }
+
+    /// how many mirrored pairs there are, and whether a note goes in the centre too
+    fn random_note_count_mirrored(&self, ctx: &mut PatternContext, mut centre_probability: f64, mut p2: f64, mut p3: f64) -> (usize, bool) {
+        //the game wanted the random value above these, which is one minus the probability
+        match ctx.total_columns {
+            2 => {
+                centre_probability = 0.0;
+                p2 = 0.0;
+                p3 = 0.0;
+            }
+            3 => {
+                centre_probability = centre_probability.min(0.03);
+                p2 = 0.0;
+                p3 = 0.0;
+            }
+            4 => {
+                centre_probability = 0.0;
+                p2 = 1.0 - ((1.0 - p2) * 2.0).max(0.8);
+                p3 = 0.0;
+            }
+            5 => {
+                centre_probability = centre_probability.min(0.03);
+                p3 = 0.0;
+            }
+            6 => {
+                centre_probability = 0.0;
+                p2 = 1.0 - ((1.0 - p2) * 2.0).max(0.5);
+                p3 = 1.0 - ((1.0 - p3) * 2.0).max(0.85);
+            }
+            _ => {}
+        }
+
+        let p2 = p2.clamp(0.0, 1.0);
+        let p3 = p3.clamp(0.0, 1.0);
+
+        let centre_value = ctx.rng.next_double();
+        let note_count = ctx.random_note_count(p2, p3, 0.0, 0.0, 0.0);
+
+        let add_to_centre = ctx.total_columns % 2 != 0 && note_count != 3 && centre_value > 1.0 - centre_probability;
+        (note_count, add_to_centre)
+    }
+
+    fn add_to_pattern(&self, pattern: &mut Pattern, column: i32) {
+        pattern.add(ManiaNote {
+            column,
+            start_time: self.hit_object.time as i32,
+            end_time: None,
+            hit_sound: self.hit_object.hit_sound,
+        });
+    }
+}
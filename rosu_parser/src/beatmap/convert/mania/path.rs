@@ -0,0 +1,369 @@
+use super::{ManiaNote, Pattern, PatternContext, PatternType};
+use super::super::super::{
+    Beatmap,
+    sections::{HitObject, SliderParams, ObjectParams, HIT_SOUND_CLAP, HIT_SOUND_FINISH, HIT_SOUND_WHISTLE},
+    slider::Slider,
+};
+
+/// notes and holds for a slider, shaped by how long each of its spans is
+pub(super) struct PathPatternGenerator<'a> {
+    hit_object: &'a HitObject,
+    params: &'a SliderParams,
+    convert_type: PatternType,
+    start_time: i32,
+    end_time: i32,
+    pub segment_duration: i32, //one span, in whole ms
+    pub span_count: i32,
+}
+
+impl<'a> PathPatternGenerator<'a> {
+
+    /// `None` for anything that isn't a slider
+    pub fn new(beatmap: &Beatmap, ctx: &PatternContext, hit_object: &'a HitObject) -> Option<Self> {
+        let params = match &hit_object.object_params {
+            ObjectParams::Slider(params) => params,
+            _ => return None,
+        };
+        let slider = Slider::new(beatmap, ctx.timing, hit_object)?;
+
+        let time = hit_object.time as f64;
+        let convert_type = if ctx.timing.kiai_at(time) { PatternType::empty() } else { PatternType::LOW_PROBABILITY };
+
+        let beat_length = ctx.timing.beat_length_at(time) / ctx.timing.slider_velocity_at(time);
+        let slider_multiplier = beatmap.difficulty.as_ref()
+            .and_then(|d| d.slider_multiplier)
+            .unwrap_or(1.4);
+
+        let span_count = slider.timing.spans as i32;
+        let start_time = hit_object.time as i32;
+        //the game's own way of getting the end, rounded down to whole ms
+        let end_time = (start_time as f64 + slider.path.length() * beat_length * span_count as f64 * 0.01 / slider_multiplier).floor() as i32;
+
+        Some(Self {
+            hit_object,
+            params,
+            convert_type,
+            start_time,
+            end_time,
+            segment_duration: (end_time - start_time) / span_count,
+            span_count,
+        })
+    }
+
+    /// the notes that end with the slider are their own pattern, the next object only looks at those
+    pub fn generate(&self, ctx: &mut PatternContext) -> Vec<Pattern> {
+        let mut convert_type = self.convert_type;
+        let pattern = self.generate_core(ctx, &mut convert_type);
+
+        if pattern.notes.len() == 1 {
+            return vec![pattern];
+        }
+
+        let (ending, intermediate): (Vec<ManiaNote>, Vec<ManiaNote>) = pattern.notes.into_iter()
+            .partition(|n| n.end_time() == self.end_time);
+
+        vec![Pattern { notes: intermediate }, Pattern { notes: ending }]
+    }
+
+    fn generate_core(&self, ctx: &mut PatternContext, convert_type: &mut PatternType) -> Pattern {
+        let start_time = self.start_time;
+
+        if ctx.total_columns == 1 {
+            let mut pattern = Pattern::default();
+            self.add_to_pattern(&mut pattern, 0, start_time, self.end_time);
+            return pattern;
+        }
+
+        if self.span_count > 1 {
+            if self.segment_duration <= 90 {
+                return self.generate_random_hold_notes(ctx, start_time, 1);
+            }
+
+            if self.segment_duration <= 120 {
+                *convert_type |= PatternType::FORCE_NOT_STACK;
+                return self.generate_random_notes(ctx, *convert_type, start_time, self.span_count + 1);
+            }
+
+            if self.segment_duration <= 160 {
+                return self.generate_stair(ctx, start_time);
+            }
+
+            if self.segment_duration <= 200 && ctx.conversion_difficulty > 3.0 {
+                return self.generate_random_multiple_notes(ctx, start_time);
+            }
+
+            if self.end_time - start_time >= 4000 {
+                return self.generate_n_random_notes(ctx, *convert_type, start_time, 0.23, 0.0, 0.0);
+            }
+
+            if self.segment_duration > 400 && self.span_count < ctx.total_columns - 1 - ctx.random_start {
+                return self.generate_tiled_hold_notes(ctx, *convert_type, start_time);
+            }
+
+            return self.generate_hold_and_normal_notes(ctx, *convert_type, start_time);
+        }
+
+        if self.segment_duration <= 110 {
+            if (ctx.previous_pattern.column_count() as i32) < ctx.total_columns {
+                *convert_type |= PatternType::FORCE_NOT_STACK;
+            } else {
+                convert_type.remove(PatternType::FORCE_NOT_STACK);
+            }
+            let note_count = if self.segment_duration < 80 { 1 } else { 2 };
+            return self.generate_random_notes(ctx, *convert_type, start_time, note_count);
+        }
+
+        let low_probability = convert_type.contains(PatternType::LOW_PROBABILITY);
+        let (p2, p3, p4) = if ctx.conversion_difficulty > 6.5 {
+            if low_probability { (0.78, 0.3, 0.0) } else { (0.85, 0.36, 0.03) }
+        } else if ctx.conversion_difficulty > 4.0 {
+            if low_probability { (0.43, 0.08, 0.0) } else { (0.56, 0.18, 0.0) }
+        } else if ctx.conversion_difficulty > 2.5 {
+            if low_probability { (0.3, 0.0, 0.0) } else { (0.37, 0.08, 0.0) }
+        } else if low_probability {
+            (0.17, 0.0, 0.0)
+        } else {
+            (0.27, 0.0, 0.0)
+        };
+        self.generate_n_random_notes(ctx, *convert_type, start_time, p2, p3, p4)
+    }
+
+    /// holds over the whole slider in random columns, away from the previous pattern if there's room
+    fn generate_random_hold_notes(&self, ctx: &mut PatternContext, start_time: i32, note_count: i32) -> Pattern {
+        let mut pattern = Pattern::default();
+        let previous = ctx.previous_pattern;
+
+        let usable_columns = ctx.total_columns - ctx.random_start - previous.column_count() as i32;
+        let mut column = ctx.random_column(None, None);
+
+        for _ in 0..usable_columns.min(note_count) {
+            column = ctx.find_available_column(column, &[&pattern, previous]);
+            self.add_to_pattern(&mut pattern, column, start_time, self.end_time);
+        }
+
+        //the rest may stack with the previous pattern
+        for _ in 0..note_count - usable_columns {
+            column = ctx.find_available_column(column, &[&pattern]);
+            self.add_to_pattern(&mut pattern, column, start_time, self.end_time);
+        }
+
+        pattern
+    }
+
+    /// one note on every edge, never twice in the same column in a row
+    fn generate_random_notes(&self, ctx: &mut PatternContext, convert_type: PatternType, mut start_time: i32, note_count: i32) -> Pattern {
+        let mut pattern = Pattern::default();
+        let previous = ctx.previous_pattern;
+
+        let mut column = ctx.column_at(self.hit_object.x as f32, true);
+        if convert_type.contains(PatternType::FORCE_NOT_STACK) && (previous.column_count() as i32) < ctx.total_columns {
+            column = ctx.find_available_column(column, &[previous]);
+        }
+
+        let mut last_column = column;
+        for _ in 0..note_count {
+            self.add_to_pattern(&mut pattern, column, start_time, start_time);
+            column = ctx.find_available_column_with(column, None, None, |ctx, _| ctx.random_column(None, None), |c| c != last_column, &[]);
+            last_column = column;
+            start_time += self.segment_duration;
+        }
+
+        pattern
+    }
+
+    /// one note on every edge, walking across the columns and turning around at the sides
+    fn generate_stair(&self, ctx: &mut PatternContext, mut start_time: i32) -> Pattern {
+        let mut pattern = Pattern::default();
+
+        let mut column = ctx.column_at(self.hit_object.x as f32, true);
+        let mut increasing = ctx.rng.next_double() > 0.5;
+
+        for _ in 0..=self.span_count {
+            self.add_to_pattern(&mut pattern, column, start_time, start_time);
+            start_time += self.segment_duration;
+
+            if increasing {
+                if column >= ctx.total_columns - 1 {
+                    increasing = false;
+                    column -= 1;
+                } else {
+                    column += 1;
+                }
+            } else if column <= ctx.random_start {
+                increasing = true;
+                column += 1;
+            } else {
+                column -= 1;
+            }
+        }
+
+        pattern
+    }
+
+    /// one or two notes on every edge
+    fn generate_random_multiple_notes(&self, ctx: &mut PatternContext, mut start_time: i32) -> Pattern {
+        let mut pattern = Pattern::default();
+        let (total_columns, random_start) = (ctx.total_columns, ctx.random_start);
+
+        let legacy = (4..=8).contains(&total_columns);
+        let interval = ctx.rng.next_range(1, total_columns - if legacy { 1 } else { 0 });
+
+        let mut column = ctx.column_at(self.hit_object.x as f32, true);
+        for _ in 0..=self.span_count {
+            self.add_to_pattern(&mut pattern, column, start_time, start_time);
+
+            column += interval;
+            if column >= total_columns - random_start {
+                column = column - total_columns - random_start + if legacy { 1 } else { 0 };
+            }
+            column += random_start;
+
+            //2K would get nothing but doubles
+            if total_columns > 2 {
+                self.add_to_pattern(&mut pattern, column, start_time, start_time);
+            }
+
+            column = ctx.random_column(None, None);
+            start_time += self.segment_duration;
+        }
+
+        pattern
+    }
+
+    /// a few holds over the whole slider, more likely with a clap or finish
+    fn generate_n_random_notes(&self, ctx: &mut PatternContext, convert_type: PatternType, start_time: i32, mut p2: f64, mut p3: f64, mut p4: f64) -> Pattern {
+        match ctx.total_columns {
+            2 => {
+                p2 = 0.0;
+                p3 = 0.0;
+                p4 = 0.0;
+            }
+            3 => {
+                p2 = p2.min(0.1);
+                p3 = 0.0;
+                p4 = 0.0;
+            }
+            4 => {
+                p2 = p2.min(0.3);
+                p3 = p3.min(0.04);
+                p4 = 0.0;
+            }
+            5 => {
+                p2 = p2.min(0.34);
+                p3 = p3.min(0.1);
+                p4 = p4.min(0.03);
+            }
+            _ => {}
+        }
+
+        let is_double = |hit_sound: usize| hit_sound & (HIT_SOUND_CLAP | HIT_SOUND_FINISH) != 0;
+        let can_generate_two_notes = !convert_type.contains(PatternType::LOW_PROBABILITY)
+            && (is_double(self.hit_object.hit_sound) || is_double(self.hit_sound_at(self.start_time)));
+        if can_generate_two_notes {
+            p2 = 1.0;
+        }
+
+        let note_count = ctx.random_note_count(p2, p3, p4, 0.0, 0.0);
+        self.generate_random_hold_notes(ctx, start_time, note_count as i32)
+    }
+
+    /// holds starting on each edge, all ending together
+    fn generate_tiled_hold_notes(&self, ctx: &mut PatternContext, convert_type: PatternType, mut start_time: i32) -> Pattern {
+        let mut pattern = Pattern::default();
+        let previous = ctx.previous_pattern;
+
+        let column_repeat = self.span_count.min(ctx.total_columns);
+        //rounding can make this differ from the slider's end
+        let end_time = start_time + self.segment_duration * self.span_count;
+
+        let mut column = ctx.column_at(self.hit_object.x as f32, true);
+        if convert_type.contains(PatternType::FORCE_NOT_STACK) && (previous.column_count() as i32) < ctx.total_columns {
+            column = ctx.find_available_column(column, &[previous]);
+        }
+
+        for _ in 0..column_repeat {
+            column = ctx.find_available_column(column, &[&pattern]);
+            self.add_to_pattern(&mut pattern, column, start_time, end_time);
+            start_time += self.segment_duration;
+        }
+
+        pattern
+    }
+
+    /// a hold over the whole slider with notes on the edges beside it
+    fn generate_hold_and_normal_notes(&self, ctx: &mut PatternContext, convert_type: PatternType, mut start_time: i32) -> Pattern {
+        let mut pattern = Pattern::default();
+        let previous = ctx.previous_pattern;
+        let total_columns = ctx.total_columns;
+
+        let mut hold_column = ctx.column_at(self.hit_object.x as f32, true);
+        if convert_type.contains(PatternType::FORCE_NOT_STACK) && (previous.column_count() as i32) < total_columns {
+            hold_column = ctx.find_available_column(hold_column, &[previous]);
+        }
+        self.add_to_pattern(&mut pattern, hold_column, start_time, self.end_time);
+
+        let mut column = ctx.random_column(None, None);
+        let note_count = if ctx.conversion_difficulty > 6.5 {
+            ctx.random_note_count(0.63, 0.0, 0.0, 0.0, 0.0)
+        } else if ctx.conversion_difficulty > 4.0 {
+            ctx.random_note_count(if total_columns < 6 { 0.12 } else { 0.45 }, 0.0, 0.0, 0.0, 0.0)
+        } else if ctx.conversion_difficulty > 2.5 {
+            ctx.random_note_count(if total_columns < 6 { 0.0 } else { 0.24 }, 0.0, 0.0, 0.0, 0.0)
+        } else {
+            0
+        };
+        let note_count = (note_count as i32).min(total_columns - 1);
+
+        //without an addition the head is left alone
+        let ignore_head = self.hit_sound_at(start_time) & (HIT_SOUND_WHISTLE | HIT_SOUND_FINISH | HIT_SOUND_CLAP) == 0;
+
+        for _ in 0..=self.span_count {
+            if !(ignore_head && start_time == self.start_time) {
+                let mut row = Pattern::default();
+                for _ in 0..note_count {
+                    column = ctx.find_available_column_with(
+                        column,
+                        None,
+                        None,
+                        |ctx, _| ctx.random_column(None, None),
+                        |c| c != hold_column,
+                        &[&row],
+                    );
+                    self.add_to_pattern(&mut row, column, start_time, start_time);
+                }
+                pattern.append(&row);
+            }
+
+            start_time += self.segment_duration;
+        }
+
+        pattern
+    }
+
+    /// the sound of the edge at `time`, or of the whole slider
+    fn hit_sound_at(&self, time: i32) -> usize {
+        let index = if self.segment_duration == 0 { 0 } else { (time - self.start_time) / self.segment_duration };
+        self.params.edge_sounds.get(index.max(0) as usize)
+            .copied()
+            .unwrap_or(self.hit_object.hit_sound)
+    }
+
+    fn add_to_pattern(&self, pattern: &mut Pattern, column: i32, start_time: i32, end_time: i32) {
+        let note = if start_time == end_time {
+            ManiaNote {
+                column,
+                start_time,
+                end_time: None,
+                hit_sound: self.hit_sound_at(start_time),
+            }
+        } else {
+            ManiaNote {
+                column,
+                start_time,
+                end_time: Some(end_time),
+                hit_sound: self.hit_object.hit_sound,
+            }
+        };
+        pattern.add(note);
+    }
+}
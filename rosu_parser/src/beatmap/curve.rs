@@ -0,0 +1,233 @@
+use std::f64::consts::PI;
+
+use super::{pos::Pos2, sections::CurveType};
+
+const BEZIER_TOLERANCE: f32 = 0.25;
+pub(crate) const CIRCULAR_ARC_TOLERANCE: f64 = 0.1;
+const CATMULL_DETAIL: usize = 50;
+
+/// arcs that need more points than this are drawn as beziers instead
+const MAX_ARC_POINTS: usize = 1000;
+
+/// turns control points into a polyline, the same way the game approximates curves.
+///
+/// bezier curves start a new segment at every doubled (red) control point,
+/// perfect circles need exactly 3 points and fall back to bezier otherwise
+pub fn approximate(curve_type: CurveType, control: &[Pos2]) -> Vec<Pos2> {
+    match curve_type {
+        CurveType::Linear => control.to_vec(),
+        CurveType::Catmull => approximate_catmull(control),
+        CurveType::PerfectCircle if control.len() == 3 => {
+            approximate_circular_arc(control)
+                .unwrap_or_else(|| approximate_bezier(control))
+        }
+        _ => approximate_bezier_segments(control),
+    }
+}
+
+fn approximate_bezier_segments(control: &[Pos2]) -> Vec<Pos2> {
+    let mut output: Vec<Pos2> = Vec::new();
+    let mut start = 0;
+
+    for i in 0..control.len() {
+        let segment_end = i == control.len() - 1 || control[i] == control[i + 1];
+        if !segment_end {
+            continue;
+        }
+
+        let segment = &control[start..=i];
+        start = i + 1;
+
+        if segment.len() < 2 {
+            continue;
+        }
+
+        let points = approximate_bezier(segment);
+        //segments share their joining point
+        let skip = match (output.last(), points.first()) {
+            (Some(last), Some(first)) if last == first => 1,
+            _ => 0,
+        };
+        output.extend(points.into_iter().skip(skip));
+    }
+
+    if output.is_empty() {
+        output.extend(control.first());
+    }
+    output
+}
+
+/// adaptive subdivision until every piece is flat enough to draw as lines
+pub fn approximate_bezier(control: &[Pos2]) -> Vec<Pos2> {
+    let count = control.len();
+    let mut output = Vec::new();
+
+    if count == 0 {
+        return output;
+    }
+
+    let mut to_flatten = vec![control.to_vec()];
+    let mut left_child = vec![Pos2::ZERO; count * 2 - 1];
+
+    while let Some(mut parent) = to_flatten.pop() {
+        if bezier_is_flat_enough(&parent) {
+            bezier_approximate(&parent, &mut output);
+            continue;
+        }
+
+        let mut right_child = vec![Pos2::ZERO; count];
+        bezier_subdivide(&parent, &mut left_child, &mut right_child);
+
+        //the left half reuses the parent's buffer
+        parent.copy_from_slice(&left_child[..count]);
+
+        to_flatten.push(right_child);
+        to_flatten.push(parent);
+    }
+
+    output.push(control[count - 1]);
+    output
+}
+
+fn bezier_is_flat_enough(control: &[Pos2]) -> bool {
+    control.windows(3).all(|w| {
+        (w[0] - w[1] * 2.0 + w[2]).length_squared() <= BEZIER_TOLERANCE * BEZIER_TOLERANCE * 4.0
+    })
+}
+
+/// de casteljau split at t = 0.5
+fn bezier_subdivide(control: &[Pos2], left: &mut [Pos2], right: &mut [Pos2]) {
+    let count = control.len();
+    let mut midpoints = control.to_vec();
+
+    for i in 0..count {
+        left[i] = midpoints[0];
+        right[count - i - 1] = midpoints[count - i - 1];
+
+        for j in 0..count - i - 1 {
+            midpoints[j] = (midpoints[j] + midpoints[j + 1]) / 2.0;
+        }
+    }
+}
+
+fn bezier_approximate(control: &[Pos2], output: &mut Vec<Pos2>) {
+    let count = control.len();
+    let mut left = vec![Pos2::ZERO; count * 2 - 1];
+    let mut right = vec![Pos2::ZERO; count];
+
+    bezier_subdivide(control, &mut left, &mut right);
+
+    left[count..].copy_from_slice(&right[1..]);
+
+    output.push(control[0]);
+
+    for i in 1..count - 1 {
+        let index = 2 * i;
+        output.push((left[index - 1] + left[index] * 2.0 + left[index + 1]) * 0.25);
+    }
+}
+
+pub fn approximate_catmull(control: &[Pos2]) -> Vec<Pos2> {
+    let count = control.len();
+    let mut output = Vec::with_capacity(count.saturating_sub(1) * CATMULL_DETAIL * 2);
+
+    for i in 0..count.saturating_sub(1) {
+        let v1 = if i > 0 { control[i - 1] } else { control[i] };
+        let v2 = control[i];
+        let v3 = if i < count - 1 { control[i + 1] } else { v2 * 2.0 - v1 };
+        let v4 = if i < count - 2 { control[i + 2] } else { v3 * 2.0 - v2 };
+
+        for c in 0..CATMULL_DETAIL {
+            output.push(catmull_point(v1, v2, v3, v4, c as f32 / CATMULL_DETAIL as f32));
+            output.push(catmull_point(v1, v2, v3, v4, (c + 1) as f32 / CATMULL_DETAIL as f32));
+        }
+    }
+
+    output
+}
+
+fn catmull_point(v1: Pos2, v2: Pos2, v3: Pos2, v4: Pos2, t: f32) -> Pos2 {
+    let t2 = t * t;
+    let t3 = t * t2;
+
+    Pos2::new(
+        0.5 * (2.0 * v2.x + (-v1.x + v3.x) * t
+            + (2.0 * v1.x - 5.0 * v2.x + 4.0 * v3.x - v4.x) * t2
+            + (-v1.x + 3.0 * v2.x - 3.0 * v3.x + v4.x) * t3),
+        0.5 * (2.0 * v2.y + (-v1.y + v3.y) * t
+            + (2.0 * v1.y - 5.0 * v2.y + 4.0 * v3.y - v4.y) * t2
+            + (-v1.y + 3.0 * v2.y - 3.0 * v3.y + v4.y) * t3),
+    )
+}
+
+/// the arc through three points, `None` when they are (nearly) on one line
+pub fn approximate_circular_arc(control: &[Pos2]) -> Option<Vec<Pos2>> {
+    let [a, b, c] = [control[0], control[1], control[2]]
+        .map(|p| (p.x as f64, p.y as f64));
+
+    let dist_sq = |p: (f64, f64), q: (f64, f64)| (p.0 - q.0).powi(2) + (p.1 - q.1).powi(2);
+    let a_sq = dist_sq(b, c);
+    let b_sq = dist_sq(a, c);
+    let c_sq = dist_sq(a, b);
+
+    if a_sq < 1e-3 || b_sq < 1e-3 || c_sq < 1e-3 {
+        return None;
+    }
+
+    let s = a_sq * (b_sq + c_sq - a_sq);
+    let t = b_sq * (a_sq + c_sq - b_sq);
+    let u = c_sq * (a_sq + b_sq - c_sq);
+    let sum = s + t + u;
+
+    if sum.abs() < 1e-3 {
+        return None;
+    }
+
+    let centre = (
+        (s * a.0 + t * b.0 + u * c.0) / sum,
+        (s * a.1 + t * b.1 + u * c.1) / sum,
+    );
+
+    let d_a = (a.0 - centre.0, a.1 - centre.1);
+    let d_c = (c.0 - centre.0, c.1 - centre.1);
+    let radius = (d_a.0 * d_a.0 + d_a.1 * d_a.1).sqrt();
+
+    let theta_start = d_a.1.atan2(d_a.0);
+    let mut theta_end = d_c.1.atan2(d_c.0);
+
+    while theta_end < theta_start {
+        theta_end += 2.0 * PI;
+    }
+
+    let mut direction = 1.0;
+    let mut theta_range = theta_end - theta_start;
+
+    //which side of a->c the middle point is on decides the direction
+    let ortho_a_to_c = (c.1 - a.1, -(c.0 - a.0));
+    if ortho_a_to_c.0 * (b.0 - a.0) + ortho_a_to_c.1 * (b.1 - a.1) < 0.0 {
+        direction = -1.0;
+        theta_range = 2.0 * PI - theta_range;
+    }
+
+    let amount_points = if 2.0 * radius <= CIRCULAR_ARC_TOLERANCE {
+        2
+    } else {
+        let step = 2.0 * (1.0 - CIRCULAR_ARC_TOLERANCE / radius).acos();
+        ((theta_range / step).ceil() as usize).max(2)
+    };
+
+    if amount_points >= MAX_ARC_POINTS {
+        return None;
+    }
+
+    Some((0..amount_points)
+        .map(|i| {
+            let fract = i as f64 / (amount_points - 1) as f64;
+            let theta = theta_start + direction * fract * theta_range;
+            Pos2::new(
+                (centre.0 + theta.cos() * radius) as f32,
+                (centre.1 + theta.sin() * radius) as f32,
+            )
+        })
+        .collect())
+}
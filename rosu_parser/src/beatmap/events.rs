@@ -1,21 +1,29 @@
 use std::path::PathBuf;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Background {
     pub start_time: usize,
     pub filename: PathBuf,
     pub x_offset: isize,
     pub y_offset: isize,
 }
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Video {
     pub start_time: usize,
     pub filename: PathBuf,
     pub x_offset: isize,
     pub y_offset: isize,
 }
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Break {
     pub start_time: usize,
     pub end_time: String,
-}
\ No newline at end of file
+}
+
+impl Break {
+
+    /// the end time as a number, breaks that can't be read end where they start
+    pub fn end(&self) -> usize {
+        self.end_time.trim().parse().unwrap_or(self.start_time)
+    }
+}
@@ -0,0 +1,323 @@
+use super::{Beatmap, slider::SliderTiming, sections::*};
+
+/// sample settings are read slightly after an object's time, like the game does
+const SAMPLE_POINT_LENIENCY: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleBank {
+    Normal,
+    Soft,
+    Drum,
+}
+
+impl SampleBank {
+
+    /// sample sets in timing points and hit samples, 0 means inherit
+    pub fn from_set(set: usize) -> Option<Self> {
+        match set {
+            1 => Some(SampleBank::Normal),
+            2 => Some(SampleBank::Soft),
+            3 => Some(SampleBank::Drum),
+            _ => None,
+        }
+    }
+
+    /// `SampleSet` in [General]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Normal" => Some(SampleBank::Normal),
+            "Soft" => Some(SampleBank::Soft),
+            "Drum" => Some(SampleBank::Drum),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SampleBank::Normal => "normal",
+            SampleBank::Soft => "soft",
+            SampleBank::Drum => "drum",
+        }
+    }
+}
+
+/// a sample to play during the map
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub time: f64,
+    pub bank: SampleBank,
+    pub name: &'static str, //hitnormal, hitclap, slidertick, ...
+    pub index: usize, //0 means only skin samples are used
+    pub volume: usize, //percent
+    pub filename: Option<String>, //replaces the bank samples entirely
+    pub loop_duration: Option<f64>, //slider slides loop while the slider is held
+}
+
+impl Sample {
+
+    /// `soft-hitclap2`, beatmap folders can provide samples under these names
+    pub fn beatmap_name(&self) -> Option<String> {
+        match self.index {
+            0 => None,
+            1 => Some(self.skin_name()),
+            i => Some(format!("{}{}", self.skin_name(), i)),
+        }
+    }
+
+    /// `soft-hitclap`, the name in skins and the default samples
+    pub fn skin_name(&self) -> String {
+        format!("{}-{}", self.bank.name(), self.name)
+    }
+}
+
+/// what the timing point at a time leaves for hit objects to override
+struct SampleDefaults {
+    bank: SampleBank,
+    index: usize,
+    volume: usize,
+}
+
+impl Beatmap {
+
+    /// every sample the map plays, in time order
+    pub fn hit_sounds(&self) -> Vec<Sample> {
+        let mut samples = Vec::new();
+        let timing = self.timing_map();
+
+        let map_bank = self.general.as_ref()
+            .and_then(|g| g.sample_set.as_deref())
+            .and_then(SampleBank::from_name)
+            .unwrap_or(SampleBank::Normal);
+
+        let defaults_at = |time: f64| {
+            match timing.point_at(time + SAMPLE_POINT_LENIENCY) {
+                Some(point) => SampleDefaults {
+                    bank: SampleBank::from_set(point.sample_set).unwrap_or(map_bank),
+                    index: point.sample_index,
+                    volume: point.volume,
+                },
+                None => SampleDefaults {
+                    bank: map_bank,
+                    index: 0,
+                    volume: 100,
+                },
+            }
+        };
+
+        for hit_object in self.hit_objects.iter().flatten() {
+            let sample = &hit_object.hit_sample;
+
+            match &hit_object.object_params {
+                ObjectParams::Slider(params) => {
+                    let slider = SliderTiming::new(self, &timing, hit_object, params);
+
+                    //head, repeats and tail each have their own sounds
+                    for (i, time) in slider.edge_times().enumerate() {
+                        let hit_sound = params.edge_sounds.get(i)
+                            .copied()
+                            .unwrap_or(hit_object.hit_sound);
+                        let (normal_set, addition_set) = params.edge_sets.get(i)
+                            .copied()
+                            .unwrap_or((sample.normal_set, sample.addition_set));
+
+                        let edge_sample = HitSample {
+                            normal_set: if normal_set != 0 { normal_set } else { sample.normal_set },
+                            addition_set: if addition_set != 0 { addition_set } else { sample.addition_set },
+                            ..sample.clone()
+                        };
+                        push_hit(&mut samples, time, hit_sound, &edge_sample, defaults_at(time));
+                    }
+
+                    for tick in &slider.ticks {
+                        let defaults = defaults_at(tick.time);
+                        samples.push(body_sample(tick.time, "slidertick", sample.normal_set, sample, &defaults, None));
+                    }
+
+                    let defaults = defaults_at(slider.start_time);
+                    let duration = Some(slider.duration());
+                    samples.push(body_sample(slider.start_time, "sliderslide", sample.normal_set, sample, &defaults, duration));
+
+                    if hit_object.hit_sound & HIT_SOUND_WHISTLE != 0 {
+                        let addition_set = if sample.addition_set != 0 { sample.addition_set } else { sample.normal_set };
+                        samples.push(body_sample(slider.start_time, "sliderwhistle", addition_set, sample, &defaults, duration));
+                    }
+                }
+                //spinners sound when they end
+                ObjectParams::EndTime(end_time) if matches!(hit_object.kind, HitObjectKind::Spinner) => {
+                    let time = *end_time as f64;
+                    push_hit(&mut samples, time, hit_object.hit_sound, sample, defaults_at(time));
+                }
+                _ => {
+                    let time = hit_object.time as f64;
+                    push_hit(&mut samples, time, hit_object.hit_sound, sample, defaults_at(time));
+                }
+            }
+        }
+
+        samples.sort_by(|a, b| a.time.total_cmp(&b.time));
+        samples
+    }
+}
+
+/// hitnormal plus the whistle, finish and clap additions
+fn push_hit(samples: &mut Vec<Sample>, time: f64, hit_sound: usize, sample: &HitSample, defaults: SampleDefaults) {
+    let normal_bank = SampleBank::from_set(sample.normal_set).unwrap_or(defaults.bank);
+    let addition_bank = SampleBank::from_set(sample.addition_set).unwrap_or(normal_bank);
+    let index = if sample.index != 0 { sample.index } else { defaults.index };
+    let volume = if sample.volume != 0 { sample.volume } else { defaults.volume };
+
+    let mut push = |bank: SampleBank, name: &'static str| samples.push(Sample {
+        time,
+        bank,
+        name,
+        index,
+        volume,
+        filename: sample.filename.clone(),
+        loop_duration: None,
+    });
+
+    //a custom file is played on its own
+    if sample.filename.is_some() {
+        push(normal_bank, "hitnormal");
+        return;
+    }
+
+    push(normal_bank, "hitnormal");
+
+    if hit_sound & HIT_SOUND_WHISTLE != 0 {
+        push(addition_bank, "hitwhistle");
+    }
+    if hit_sound & HIT_SOUND_FINISH != 0 {
+        push(addition_bank, "hitfinish");
+    }
+    if hit_sound & HIT_SOUND_CLAP != 0 {
+        push(addition_bank, "hitclap");
+    }
+}
+
+/// ticks and slides, which don't use custom files
+fn body_sample(
+    time: f64,
+    name: &'static str,
+    set: usize,
+    sample: &HitSample,
+    defaults: &SampleDefaults,
+    loop_duration: Option<f64>) -> Sample {
+
+    Sample {
+        time,
+        bank: SampleBank::from_set(set).unwrap_or(defaults.bank),
+        name,
+        index: if sample.index != 0 { sample.index } else { defaults.index },
+        volume: if sample.volume != 0 { sample.volume } else { defaults.volume },
+        filename: None,
+        loop_duration,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// soft map sample set, 1x slider multiplier, one tick a beat and 500 ms beats with normal samples.
+    /// from 2 seconds in it's drum samples with index 2 at 60% volume, and from 3 seconds back to the map's set
+    fn samples(objects: &str) -> Vec<Sample> {
+        Beatmap::load_from_string(format!(
+            "osu file format v14\n\n[General]\nSampleSet: Soft\n\n[Difficulty]\nSliderMultiplier:1\nSliderTickRate:1\n\n\
+             [TimingPoints]\n0,500,4,1,0,100,1,0\n2000,-100,4,3,2,60,0,0\n3000,-100,4,0,0,100,0,0\n\n[HitObjects]\n{}\n", objects
+        )).unwrap()
+            .hit_sounds()
+    }
+
+    /// (time, skin name, index, volume)
+    fn summary(samples: &[Sample]) -> Vec<(f64, String, usize, usize)> {
+        samples.iter()
+            .map(|s| (s.time, s.skin_name(), s.index, s.volume))
+            .collect()
+    }
+
+    #[test]
+    fn defaults_from_timing_points() {
+        let samples = samples("256,192,1000,1,0\n256,192,1997,1,0\n256,192,2500,1,2\n256,192,4000,1,0");
+        assert_eq!(summary(&samples), [
+            (1000.0, String::from("normal-hitnormal"), 0, 100),
+            //read a little after the object
+            (1997.0, String::from("drum-hitnormal"), 2, 60),
+            (2500.0, String::from("drum-hitnormal"), 2, 60),
+            (2500.0, String::from("drum-hitwhistle"), 2, 60),
+            //a timing point without a sample set uses the map's
+            (4000.0, String::from("soft-hitnormal"), 0, 100),
+        ]);
+    }
+
+    #[test]
+    fn hit_sample_overrides() {
+        let samples = samples("256,192,1000,1,8,0:2:3:70:\n256,192,1500,1,4,3:0:0:0:\n256,192,2500,1,0,1:0:0:0:");
+        assert_eq!(summary(&samples), [
+            (1000.0, String::from("normal-hitnormal"), 3, 70),
+            (1000.0, String::from("soft-hitclap"), 3, 70),
+            //additions use the normal set without their own
+            (1500.0, String::from("drum-hitnormal"), 0, 100),
+            (1500.0, String::from("drum-hitfinish"), 0, 100),
+            (2500.0, String::from("normal-hitnormal"), 2, 60),
+        ]);
+        assert_eq!(samples[0].beatmap_name().as_deref(), Some("normal-hitnormal3"));
+        assert_eq!(samples[2].beatmap_name(), None);
+    }
+
+    #[test]
+    fn custom_filename() {
+        let samples = samples("256,192,1000,1,10,0:0:0:0:boom.wav");
+        //played on its own, without the additions
+        assert_eq!(summary(&samples), [(1000.0, String::from("normal-hitnormal"), 0, 100)]);
+        assert_eq!(samples[0].filename.as_deref(), Some("boom.wav"));
+    }
+
+    #[test]
+    fn slider_edges_ticks_and_slides() {
+        //200 long, so a second each way with a tick half way
+        let samples = samples("0,192,0,2,2,L|200:192,2,200,2|0|8,1:2|0:0|3:0,0:0:0:0:");
+        assert_eq!(summary(&samples), [
+            (0.0, String::from("normal-hitnormal"), 0, 100),
+            (0.0, String::from("soft-hitwhistle"), 0, 100),
+            (0.0, String::from("normal-sliderslide"), 0, 100),
+            (0.0, String::from("normal-sliderwhistle"), 0, 100),
+            (500.0, String::from("normal-slidertick"), 0, 100),
+            (1000.0, String::from("normal-hitnormal"), 0, 100),
+            (1500.0, String::from("normal-slidertick"), 0, 100),
+            (2000.0, String::from("drum-hitnormal"), 2, 60),
+            (2000.0, String::from("drum-hitclap"), 2, 60),
+        ]);
+
+        //only the slides loop, for as long as the slider
+        let loops: Vec<Option<f64>> = samples.iter().map(|s| s.loop_duration).collect();
+        assert_eq!(loops, [None, None, Some(2000.0), Some(2000.0), None, None, None, None, None]);
+    }
+
+    #[test]
+    fn slider_without_edge_sounds() {
+        //every edge takes the object's sounds and sets
+        let samples = samples("0,192,0,2,8,L|200:192,1,200,,,2:3:0:0:");
+        let edges: Vec<(f64, String)> = samples.iter()
+            .filter(|s| s.name.starts_with("hit"))
+            .map(|s| (s.time, s.skin_name()))
+            .collect();
+        assert_eq!(edges, [
+            (0.0, String::from("soft-hitnormal")),
+            (0.0, String::from("drum-hitclap")),
+            (1000.0, String::from("soft-hitnormal")),
+            (1000.0, String::from("drum-hitclap")),
+        ]);
+        //no whistle, so no whistle slide
+        assert!(samples.iter().all(|s| s.name != "sliderwhistle"));
+    }
+
+    #[test]
+    fn spinner_sounds_at_its_end() {
+        let samples = samples("256,192,500,12,4,3500");
+        assert_eq!(summary(&samples), [
+            (3500.0, String::from("soft-hitnormal"), 0, 100),
+            (3500.0, String::from("soft-hitfinish"), 0, 100),
+        ]);
+    }
+}
@@ -0,0 +1,96 @@
+use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign};
+
+/// the area objects are placed in, in osu!pixels
+pub const PLAYFIELD_WIDTH: f32 = 512.0;
+pub const PLAYFIELD_HEIGHT: f32 = 384.0;
+
+/// a position in osu!pixels, the playfield is 512x384 with y pointing down
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pos2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Pos2 {
+
+    pub const ZERO: Pos2 = Pos2 { x: 0.0, y: 0.0 };
+
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn distance(self, other: Pos2) -> f32 {
+        (self - other).length()
+    }
+
+    pub fn dot(self, other: Pos2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// unit vector in the same direction, zero stays zero
+    pub fn normalize(self) -> Pos2 {
+        let length = self.length();
+        if length == 0.0 {
+            return self;
+        }
+        self / length
+    }
+
+    pub fn lerp(self, other: Pos2, t: f32) -> Pos2 {
+        self + (other - self) * t
+    }
+}
+
+impl Add for Pos2 {
+    type Output = Pos2;
+
+    fn add(self, rhs: Pos2) -> Pos2 {
+        Pos2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Pos2 {
+    fn add_assign(&mut self, rhs: Pos2) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Pos2 {
+    type Output = Pos2;
+
+    fn sub(self, rhs: Pos2) -> Pos2 {
+        Pos2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<f32> for Pos2 {
+    type Output = Pos2;
+
+    fn mul(self, rhs: f32) -> Pos2 {
+        Pos2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Div<f32> for Pos2 {
+    type Output = Pos2;
+
+    fn div(self, rhs: f32) -> Pos2 {
+        Pos2::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl Neg for Pos2 {
+    type Output = Pos2;
+
+    fn neg(self) -> Pos2 {
+        Pos2::new(-self.x, -self.y)
+    }
+}
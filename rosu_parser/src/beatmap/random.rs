@@ -0,0 +1,110 @@
+/// the generator the game used for anything random, converts only come out the same with it
+#[derive(Debug, Clone)]
+pub struct LegacyRandom {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32,
+    bit_buffer: u32,
+    bit_index: u32,
+}
+
+impl LegacyRandom {
+
+    pub fn new(seed: i32) -> Self {
+        Self {
+            x: seed as u32,
+            y: 842502087,
+            z: 3579807591,
+            w: 273326509,
+            bit_buffer: 0,
+            bit_index: 32,
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let t = self.x ^ (self.x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        self.w = self.w ^ (self.w >> 19) ^ t ^ (t >> 8);
+        self.w
+    }
+
+    /// from 0 to i32::MAX
+    pub fn next_int(&mut self) -> i32 {
+        (self.next_u32() & 0x7fffffff) as i32
+    }
+
+    /// from `lower` up to but excluding `upper`
+    pub fn next_range(&mut self, lower: i32, upper: i32) -> i32 {
+        (lower as f64 + self.next_double() * (upper - lower) as f64) as i32
+    }
+
+    /// truncated like the game did
+    pub fn next_double_range(&mut self, lower: f64, upper: f64) -> i32 {
+        (lower + self.next_double() * (upper - lower)) as i32
+    }
+
+    /// from 0 to just below 1
+    pub fn next_double(&mut self) -> f64 {
+        self.next_int() as f64 / (i32::MAX as f64 + 1.0)
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        if self.bit_index == 32 {
+            self.bit_buffer = self.next_u32();
+            self.bit_index = 1;
+            return self.bit_buffer & 1 == 1;
+        }
+
+        self.bit_index += 1;
+        self.bit_buffer >>= 1;
+        self.bit_buffer & 1 == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //the expected values are what rosu-pp 0.10.0's port of LegacyRandom gives for the same seeds
+
+    #[test]
+    fn sequence_for_the_catch_seed() {
+        let mut rng = LegacyRandom::new(1337);
+        let values: Vec<u32> = (0..5).map(|_| rng.next_u32()).collect();
+        assert_eq!(values, [274941776, 2661595948, 3085529888, 4075547577, 4172835699]);
+
+        //the top bit is masked off
+        let mut rng = LegacyRandom::new(1337);
+        let values: Vec<i32> = (0..3).map(|_| rng.next_int()).collect();
+        assert_eq!(values, [274941776, 514112300, 938046240]);
+    }
+
+    #[test]
+    fn negative_seed() {
+        let mut rng = LegacyRandom::new(-1);
+        assert_eq!((rng.next_u32(), rng.next_u32()), (273327196, 2660063269));
+    }
+
+    #[test]
+    fn ranges_truncate() {
+        let mut rng = LegacyRandom::new(1337);
+        let values: Vec<i32> = (0..5).map(|_| rng.next_range(0, 10)).collect();
+        assert_eq!(values, [1, 2, 4, 8, 9]);
+    }
+
+    #[test]
+    fn bools_come_from_the_bits_of_one_value() {
+        //the first value is 0b10000011000110100011101010000, read from the lowest bit up
+        let mut rng = LegacyRandom::new(1337);
+        let values: Vec<bool> = (0..8).map(|_| rng.next_bool()).collect();
+        assert_eq!(values, [false, false, false, false, true, false, true, false]);
+
+        //and only the next value after 32 of them
+        let mut rng = LegacyRandom::new(1337);
+        (0..32).for_each(|_| { rng.next_bool(); });
+        assert_eq!(rng.next_u32(), 2661595948);
+    }
+}
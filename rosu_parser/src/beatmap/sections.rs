@@ -1,10 +1,11 @@
 
 //TODO: fill rest of the fields for all sections
 
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 use super::events::{self};
+use crate::colour::Rgb;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct General {
     pub audio_filename: Option<PathBuf>,
     pub audio_lead_in: Option<isize>,
@@ -26,7 +27,48 @@ pub struct General {
     pub widescreen_storyboard: Option<bool>,
     pub samples_match_playback_rate: Option<bool>,
 }
-#[derive(Debug, Default)]
+
+/// `Mode` in [General]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Osu,
+    Taiko,
+    Catch,
+    Mania,
+}
+
+impl GameMode {
+    pub fn from_id(id: usize) -> Option<Self> {
+        match id {
+            0 => Some(GameMode::Osu),
+            1 => Some(GameMode::Taiko),
+            2 => Some(GameMode::Catch),
+            3 => Some(GameMode::Mania),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> usize {
+        self as usize
+    }
+}
+
+/// parses the mode's name or id, `fruits` and `ctb` are catch
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "osu" | "standard" | "0" => Ok(GameMode::Osu),
+            "taiko" | "1" => Ok(GameMode::Taiko),
+            "catch" | "fruits" | "ctb" | "2" => Ok(GameMode::Catch),
+            "mania" | "3" => Ok(GameMode::Mania),
+            _ => Err(format!("unknown mode \"{}\"", s)),
+        }
+    }
+}
+#[derive(Debug, Clone, Default)]
 pub struct Editor {
     pub bookmarks: Option<String>, //comma separated list of ints
     pub distance_spacing: Option<f32>,
@@ -34,7 +76,19 @@ pub struct Editor {
     pub grid_size: Option<usize>,
     pub timeline_zoom: Option<f32>,
 }
-#[derive(Debug, Default)]
+
+impl Editor {
+
+    /// bookmark times in ms, entries that aren't numbers are skipped
+    pub fn bookmark_times(&self) -> Vec<usize> {
+        self.bookmarks.iter()
+            .flat_map(|bookmarks| bookmarks.split(','))
+            .filter_map(|time| time.trim().parse().ok())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Metadata {
     pub title: Option<String>,
     pub title_unicode: Option<String>,
@@ -47,16 +101,16 @@ pub struct Metadata {
     pub beatmap_id: Option<usize>,
     pub beatmap_set_id: Option<usize>,
 }
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Difficulty {
     pub hp_drain_rate: Option<f32>,
     pub circle_size: Option<f32>,
     pub overall_difficulty: Option<f32>,
     pub approach_rate: Option<f32>,
-    pub slider_multiplier: Option<f32>,
-    pub slider_tick_rate: Option<f32>,
+    pub slider_multiplier: Option<f64>,
+    pub slider_tick_rate: Option<f64>,
 }
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Events {
     pub backgrounds: Vec<events::Background>,
     pub videos: Vec<events::Video>,
@@ -65,10 +119,10 @@ pub struct Events {
 
 pub type TimingPoints = Vec<TimingPoint>;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct TimingPoint {
-    pub time: usize,
-    pub beat_length: f32,
+    pub time: f64,
+    pub beat_length: f64,
     pub meter: usize,
     pub sample_set: usize,
     pub sample_index: usize,
@@ -77,42 +131,88 @@ pub struct TimingPoint {
     pub effects: usize,
 }
 
-pub type Colours = Vec<Colour>;
+#[derive(Debug, Clone, Default)]
+pub struct Colours {
+    pub combo: [Option<Rgb>; 8], //Combo1 to Combo8
+    pub slider_track_override: Option<Rgb>,
+    pub slider_border: Option<Rgb>,
+}
+
+impl Colours {
 
-#[derive(Debug, Default)]
-pub struct Colour {
-    pub combo: u8,
-    pub slider_track_override: u8,
-    pub slider_border: u8,
+    /// the defined combo colours in order, skipping gaps
+    pub fn combo_colours(&self) -> Vec<Rgb> {
+        self.combo.iter().flatten().copied().collect()
+    }
 }
 
 pub type HitObjects = Vec<HitObject>;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct HitObject {
-    pub x: usize,
-    pub y: usize,
-    pub time: usize,
+    pub x: isize,
+    pub y: isize,
+    pub time: isize,
     pub kind: HitObjectKind,
     pub hit_sound: usize,
-    pub object_params: Option<String>, //TODO: split comma list
-    pub hit_sample: Option<String>, // TODO: split colon list
+    pub object_params: ObjectParams,
+    pub hit_sample: HitSample,
+    pub new_combo: bool,
+    pub combo_skip: usize, //combo colours skipped on top of the next one when starting a new combo
+}
+
+//hit sound flags
+pub const HIT_SOUND_NORMAL: usize = 1;
+pub const HIT_SOUND_WHISTLE: usize = 2;
+pub const HIT_SOUND_FINISH: usize = 4;
+pub const HIT_SOUND_CLAP: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub enum ObjectParams {
+    #[default]
+    None,
+    Slider(SliderParams),
+    EndTime(usize), //spinners and mania holds
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SliderParams {
+    pub curve_type: CurveType,
+    pub curve_points: Vec<(isize, isize)>, //excludes the head at the object's x/y
+    pub slides: usize,
+    pub length: f64,
+    pub edge_sounds: Vec<usize>, //one per edge, head first
+    pub edge_sets: Vec<(usize, usize)>, //normal set, addition set
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CurveType {
+    #[default]
+    Bezier,
+    Catmull,
+    Linear,
+    PerfectCircle,
+}
+
+/// `normalSet:additionSet:index:volume:filename`, zeroes mean inherit from the timing point
+#[derive(Debug, Clone, Default)]
+pub struct HitSample {
+    pub normal_set: usize,
+    pub addition_set: usize,
+    pub index: usize,
+    pub volume: usize,
+    pub filename: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
 pub enum HitObjectKind {
+    #[default]
     HitCircle,
     Slider,
     Spinner,
     ManiaHold
 }
 
-impl Default for HitObjectKind {
-    fn default() -> Self {
-        HitObjectKind::HitCircle
-    }
-}
-
 #[derive(Debug)]
 pub enum Value {
     Str(String),
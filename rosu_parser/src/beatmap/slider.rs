@@ -0,0 +1,421 @@
+use super::{Beatmap, curve, pos::Pos2, timing::TimingMap, sections::{CurveType, HitObject, ObjectParams, SliderParams}};
+
+/// sliders longer than this are cut off, same as the game
+const MAX_LENGTH: f64 = 100000.0;
+
+/// the game counts the tail this long before the slider actually ends
+const LEGACY_LAST_TICK_OFFSET: f64 = 36.0;
+
+/// a slider's geometry and timing together
+#[derive(Debug, Clone)]
+pub struct Slider {
+    pub path: SliderPath,
+    pub timing: SliderTiming,
+}
+
+impl Slider {
+
+    /// `None` for anything that isn't a slider
+    pub fn new(beatmap: &Beatmap, timing: &TimingMap, hit_object: &HitObject) -> Option<Self> {
+        match &hit_object.object_params {
+            ObjectParams::Slider(params) => Some(Self {
+                path: SliderPath::new(hit_object, params),
+                timing: SliderTiming::new(beatmap, timing, hit_object, params),
+            }),
+            _ => None,
+        }
+    }
+
+    /// position at `progress` through the whole slider, repeats included
+    pub fn position_at(&self, progress: f64) -> Pos2 {
+        let spans = self.timing.spans as f64;
+        let span_progress = (progress.clamp(0.0, 1.0) * spans).min(spans);
+        let span = span_progress.floor();
+        let mut path_progress = span_progress - span;
+
+        //the tail of a forward span and the last pass of a reversed one
+        if span_progress >= spans {
+            path_progress = 1.0;
+            if self.timing.spans.is_multiple_of(2) {
+                path_progress = 0.0;
+            }
+        } else if span as usize % 2 == 1 {
+            path_progress = 1.0 - path_progress;
+        }
+
+        self.path.position_at(path_progress)
+    }
+
+    /// where the slider ball is at `time`
+    pub fn position_at_time(&self, time: f64) -> Pos2 {
+        let duration = self.timing.duration();
+        if duration <= 0.0 {
+            return self.path.position_at(0.0);
+        }
+        self.position_at((time - self.timing.start_time) / duration)
+    }
+
+    /// where the slider ends, which is the head again after an even number of slides
+    pub fn end_position(&self) -> Pos2 {
+        self.position_at(1.0)
+    }
+
+    pub fn tick_positions(&self) -> impl Iterator<Item = (f64, Pos2)> + '_ {
+        self.timing.ticks.iter().map(|tick| (tick.time, self.path.position_at(tick.progress)))
+    }
+}
+
+/// a slider curve approximated as a polyline and fitted to its declared length
+#[derive(Debug, Clone, Default)]
+pub struct SliderPath {
+    points: Vec<Pos2>,
+    cumulative_length: Vec<f64>,
+}
+
+impl SliderPath {
+
+    pub fn new(hit_object: &HitObject, params: &SliderParams) -> Self {
+        let head = Pos2::new(hit_object.x as f32, hit_object.y as f32);
+
+        let control: Vec<Pos2> = std::iter::once(head)
+            .chain(params.curve_points.iter().map(|(x, y)| Pos2::new(*x as f32, *y as f32)))
+            .collect();
+
+        SliderPath::from_control_points(params.curve_type, &control, params.length)
+    }
+
+    /// `expected_length` is the pixel length from the .osu, the path is cut or extended to it
+    pub fn from_control_points(curve_type: CurveType, control: &[Pos2], expected_length: f64) -> Self {
+        let mut points = curve::approximate(curve_type, control);
+        //catmull sliders with only their head have no curve, they stay on the head
+        if points.is_empty() {
+            points.extend(control.first());
+        }
+
+        let mut path = Self {
+            points,
+            cumulative_length: Vec::new(),
+        };
+        path.fit_length(control, expected_length);
+        path
+    }
+
+    fn fit_length(&mut self, control: &[Pos2], expected_length: f64) {
+        let points = &mut self.points;
+        let cumulative = &mut self.cumulative_length;
+
+        let mut calculated = 0.0;
+        cumulative.push(0.0);
+        for pair in points.windows(2) {
+            calculated += pair[0].distance(pair[1]) as f64;
+            cumulative.push(calculated);
+        }
+
+        if expected_length <= 0.0 || calculated == expected_length {
+            return;
+        }
+
+        //stable doesn't extend sliders whose last two control points are the same
+        let n = control.len();
+        if n >= 2 && control[n - 1] == control[n - 2] && expected_length > calculated {
+            return;
+        }
+
+        cumulative.pop();
+        let mut end = points.len().saturating_sub(1);
+
+        if calculated > expected_length {
+            while end > 0 && cumulative.last().is_some_and(|l| *l >= expected_length) {
+                cumulative.pop();
+                points.pop();
+                end -= 1;
+            }
+        }
+
+        if end == 0 {
+            cumulative.push(0.0);
+            return;
+        }
+
+        //move the last point so the path ends exactly at the expected length
+        let direction = (points[end] - points[end - 1]).normalize();
+        let remaining = expected_length - cumulative.last().copied().unwrap_or(0.0);
+        points[end] = points[end - 1] + direction * remaining as f32;
+        cumulative.push(expected_length);
+    }
+
+    /// the approximated polyline
+    pub fn points(&self) -> &[Pos2] {
+        &self.points
+    }
+
+    pub fn length(&self) -> f64 {
+        self.cumulative_length.last().copied().unwrap_or(0.0)
+    }
+
+    /// position at `progress` along the path, 0 is the head and 1 the end
+    pub fn position_at(&self, progress: f64) -> Pos2 {
+        let distance = progress.clamp(0.0, 1.0) * self.length();
+        let i = self.cumulative_length.partition_point(|l| *l < distance);
+
+        if i == 0 {
+            return self.points.first().copied().unwrap_or_default();
+        }
+        if i >= self.points.len() {
+            return self.points.last().copied().unwrap_or_default();
+        }
+
+        let (d0, d1) = (self.cumulative_length[i - 1], self.cumulative_length[i]);
+        if d1 - d0 <= f64::EPSILON {
+            return self.points[i - 1];
+        }
+
+        let t = (distance - d0) / (d1 - d0);
+        self.points[i - 1].lerp(self.points[i], t as f32)
+    }
+}
+
+/// a tick on the slider body
+#[derive(Debug, Clone, Copy)]
+pub struct SliderTick {
+    pub time: f64,
+    pub progress: f64, //0 at the head, 1 at the end of the path
+    pub span: usize,
+}
+
+/// when the parts of a slider happen
+#[derive(Debug, Clone)]
+pub struct SliderTiming {
+    pub start_time: f64,
+    pub span_duration: f64, //one pass over the path
+    pub spans: usize, //slides, 1 for sliders without repeats
+    pub velocity: f64, //osu!pixels per ms
+    pub ticks: Vec<SliderTick>,
+}
+
+impl SliderTiming {
+
+    pub fn new(beatmap: &Beatmap, timing: &TimingMap, hit_object: &HitObject, params: &SliderParams) -> Self {
+        let start_time = hit_object.time as f64;
+        let difficulty = beatmap.difficulty.as_ref();
+
+        let slider_multiplier = difficulty
+            .and_then(|d| d.slider_multiplier)
+            .unwrap_or(1.4);
+        let tick_rate = difficulty
+            .and_then(|d| d.slider_tick_rate)
+            .unwrap_or(1.0);
+
+        let beat_length = timing.beat_length_at(start_time);
+        let slider_velocity = timing.slider_velocity_at(start_time);
+
+        let scoring_distance = 100.0 * slider_multiplier * slider_velocity;
+        let velocity = scoring_distance / beat_length;
+
+        //old maps spaced ticks without slider velocity
+        let mut tick_distance = scoring_distance / tick_rate;
+        if beatmap.version.unwrap_or(14) < 8 {
+            tick_distance /= slider_velocity;
+        }
+
+        let length = params.length.min(MAX_LENGTH);
+        let spans = params.slides.max(1);
+        let span_duration = length / velocity;
+
+        let mut ticks = Vec::new();
+        let tick_distance = tick_distance.clamp(0.0, length);
+        let min_distance_from_end = velocity * 10.0;
+
+        if tick_distance > 0.0 {
+            let span_ticks: Vec<f64> = (1..)
+                .map(|i| i as f64 * tick_distance)
+                .take_while(|d| *d < length - min_distance_from_end)
+                .map(|d| d / length)
+                .collect();
+
+            for span in 0..spans {
+                let span_start = start_time + span as f64 * span_duration;
+                let reversed = span % 2 == 1;
+
+                let mut push = |progress: f64| {
+                    let time_progress = if reversed { 1.0 - progress } else { progress };
+                    ticks.push(SliderTick {
+                        time: span_start + time_progress * span_duration,
+                        progress,
+                        span,
+                    });
+                };
+
+                //ticks are always in time order
+                if reversed {
+                    span_ticks.iter().rev().for_each(|p| push(*p));
+                } else {
+                    span_ticks.iter().for_each(|p| push(*p));
+                }
+            }
+        }
+
+        Self {
+            start_time,
+            span_duration,
+            spans,
+            velocity,
+            ticks,
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.span_duration * self.spans as f64
+    }
+
+    pub fn end_time(&self) -> f64 {
+        self.start_time + self.duration()
+    }
+
+    /// times of the head, every repeat and the tail
+    pub fn edge_times(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..=self.spans).map(|i| self.start_time + i as f64 * self.span_duration)
+    }
+
+    /// times of the repeat arrows only
+    pub fn repeat_times(&self) -> impl Iterator<Item = f64> + '_ {
+        self.edge_times().skip(1).take(self.spans - 1)
+    }
+
+    /// where the game judges the tail, slightly before the real end
+    pub fn legacy_last_tick_time(&self) -> f64 {
+        (self.end_time() - LEGACY_LAST_TICK_OFFSET)
+            .max(self.start_time + self.duration() / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beatmap::curve::CIRCULAR_ARC_TOLERANCE;
+
+    /// the first slider in a map with 1x slider multiplier, one tick a beat and 500 ms beats
+    fn parse_slider(object: &str) -> Slider {
+        let map = Beatmap::load_from_string(format!(
+            "osu file format v14\n\n[Difficulty]\nSliderMultiplier:1\nSliderTickRate:1\n\n\
+             [TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n{}\n", object
+        )).unwrap();
+        let hit_object = &map.hit_objects.as_ref().unwrap()[0];
+        Slider::new(&map, &map.timing_map(), hit_object).unwrap()
+    }
+
+    fn assert_near(a: Pos2, b: Pos2) {
+        assert!(a.distance(b) < 0.01, "{:?} is not {:?}", a, b);
+    }
+
+    #[test]
+    fn perfect_circle_arc() {
+        let control = [Pos2::new(0.0, 0.0), Pos2::new(50.0, 50.0), Pos2::new(100.0, 0.0)];
+        let path = SliderPath::from_control_points(CurveType::PerfectCircle, &control, 50.0 * std::f64::consts::PI);
+        //the arc is drawn as lines within its tolerance, which come out about 0.1 short of the length
+        //and get extended by that at the end
+        assert!(path.position_at(0.5).distance(Pos2::new(50.0, 50.0)) < CIRCULAR_ARC_TOLERANCE as f32);
+        assert!(path.position_at(1.0).distance(Pos2::new(100.0, 0.0)) < 2.0 * CIRCULAR_ARC_TOLERANCE as f32);
+    }
+
+    #[test]
+    fn collinear_perfect_circle_is_a_bezier() {
+        let control = [Pos2::new(0.0, 0.0), Pos2::new(50.0, 0.0), Pos2::new(100.0, 0.0)];
+        let path = SliderPath::from_control_points(CurveType::PerfectCircle, &control, 100.0);
+        assert!(path.points().iter().all(|point| point.y == 0.0));
+        assert_near(path.position_at(0.5), Pos2::new(50.0, 0.0));
+        assert_near(path.position_at(1.0), Pos2::new(100.0, 0.0));
+    }
+
+    #[test]
+    fn bezier_splits_at_red_anchors() {
+        //two straight segments with a sharp corner, not one curve through (100, 0)
+        let control = [Pos2::new(0.0, 0.0), Pos2::new(100.0, 0.0), Pos2::new(100.0, 0.0), Pos2::new(100.0, 100.0)];
+        let path = SliderPath::from_control_points(CurveType::Bezier, &control, 200.0);
+        assert!(path.points().contains(&Pos2::new(100.0, 0.0)));
+        assert_near(path.position_at(0.25), Pos2::new(50.0, 0.0));
+        assert_near(path.position_at(0.5), Pos2::new(100.0, 0.0));
+        assert_near(path.position_at(0.75), Pos2::new(100.0, 50.0));
+    }
+
+    #[test]
+    fn fitted_to_the_declared_length() {
+        let control = [Pos2::new(0.0, 0.0), Pos2::new(100.0, 0.0), Pos2::new(100.0, 100.0)];
+
+        let cut = SliderPath::from_control_points(CurveType::Linear, &control, 150.0);
+        assert_eq!(cut.length(), 150.0);
+        assert_near(cut.position_at(1.0), Pos2::new(100.0, 50.0));
+
+        let cut_in_first_segment = SliderPath::from_control_points(CurveType::Linear, &control, 50.0);
+        assert_eq!(cut_in_first_segment.points().len(), 2);
+        assert_near(cut_in_first_segment.position_at(1.0), Pos2::new(50.0, 0.0));
+
+        //extended along the last segment
+        let extended = SliderPath::from_control_points(CurveType::Linear, &control, 250.0);
+        assert_eq!(extended.length(), 250.0);
+        assert_near(extended.position_at(1.0), Pos2::new(100.0, 150.0));
+
+        //except when the last two control points are the same
+        let doubled = [control[0], control[1], control[1]];
+        let not_extended = SliderPath::from_control_points(CurveType::Bezier, &doubled, 150.0);
+        assert_eq!(not_extended.length(), 100.0);
+    }
+
+    #[test]
+    fn ticks_across_reverse_spans() {
+        //0.2 osu!pixels per ms and a tick every 100, so three 1500 ms slides with ticks a third of the way apart
+        let slider = parse_slider("0,0,1000,2,0,L|300:0,3,300");
+        assert_eq!(slider.timing.span_duration, 1500.0);
+        assert_eq!(slider.timing.end_time(), 5500.0);
+        assert_eq!(slider.timing.repeat_times().collect::<Vec<_>>(), [2500.0, 4000.0]);
+
+        let ticks: Vec<(f64, f64, usize)> = slider.timing.ticks.iter()
+            .map(|tick| (tick.time.round(), (tick.progress * 3.0).round(), tick.span))
+            .collect();
+        //the reversed slide meets the ticks in the opposite order
+        assert_eq!(ticks, [
+            (1500.0, 1.0, 0), (2000.0, 2.0, 0),
+            (3000.0, 2.0, 1), (3500.0, 1.0, 1),
+            (4500.0, 1.0, 2), (5000.0, 2.0, 2),
+        ]);
+        assert_near(slider.tick_positions().nth(2).unwrap().1, Pos2::new(200.0, 0.0));
+    }
+
+    #[test]
+    fn positions_on_repeats() {
+        let slider = parse_slider("0,0,1000,2,0,L|300:0,2,300");
+        assert_near(slider.position_at_time(1750.0), Pos2::new(150.0, 0.0));
+        assert_near(slider.position_at_time(2500.0), Pos2::new(300.0, 0.0));
+        //on the way back
+        assert_near(slider.position_at_time(2875.0), Pos2::new(225.0, 0.0));
+        assert_near(slider.position_at(0.75), Pos2::new(150.0, 0.0));
+        assert_near(slider.end_position(), Pos2::new(0.0, 0.0));
+        //clamped outside of the slider
+        assert_near(slider.position_at_time(0.0), Pos2::new(0.0, 0.0));
+        assert_near(slider.position_at_time(9000.0), Pos2::new(0.0, 0.0));
+
+        let slider = parse_slider("0,0,1000,2,0,L|300:0,3,300");
+        assert_near(slider.end_position(), Pos2::new(300.0, 0.0));
+    }
+
+    #[test]
+    fn catmull_slider_without_curve_points_stays_on_its_head() {
+        let slider = parse_slider("64,192,1000,2,0,C|,2,100");
+        let head = Pos2::new(64.0, 192.0);
+        for time in [1000.0, 1200.0, 1500.0, 2000.0] {
+            assert_eq!(slider.position_at_time(time), head);
+        }
+        assert_eq!(slider.end_position(), head);
+        assert!(slider.tick_positions().all(|(_, position)| position == head));
+    }
+
+    #[test]
+    fn catmull_path_with_one_control_point() {
+        let head = Pos2::new(64.0, 192.0);
+        let path = SliderPath::from_control_points(CurveType::Catmull, &[head], 100.0);
+
+        assert_eq!(path.points(), &[head]);
+        assert_eq!(path.length(), 0.0);
+        assert_eq!(path.position_at(1.0), head);
+    }
+}
@@ -0,0 +1,250 @@
+use super::{Beatmap, pos::Pos2, slider::Slider, timing::TimingMap, sections::{GameMode, HitObject, HitObjectKind, ObjectParams}};
+
+/// objects closer than this (in osu!pixels) stack on each other
+const STACK_DISTANCE: f32 = 3.0;
+
+/// where an object is drawn after stacking
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stack {
+    pub height: isize, //negative below slider ends
+    pub position: Pos2,
+}
+
+/// what the stacking pass needs to know about an object
+struct StackObject {
+    kind: HitObjectKind,
+    start_time: f64,
+    end_time: f64,
+    position: Pos2,
+    end_position: Pos2, //where the slider ends, repeats included
+    path_end: Pos2, //the end of the slider path, which old maps stack on
+    height: isize,
+}
+
+impl StackObject {
+    fn is_spinner(&self) -> bool {
+        matches!(self.kind, HitObjectKind::Spinner)
+    }
+
+    fn is_slider(&self) -> bool {
+        matches!(self.kind, HitObjectKind::Slider)
+    }
+}
+
+impl Beatmap {
+
+    /// stack height and stacked position of every hit object, in the same order.
+    ///
+    /// only osu!standard stacks, objects of other modes keep their position
+    pub fn stacking(&self) -> Vec<Stack> {
+        let hit_objects = match &self.hit_objects {
+            Some(hit_objects) => hit_objects,
+            None => return Vec::new(),
+        };
+
+        let timing = self.timing_map();
+        let mut objects: Vec<StackObject> = hit_objects.iter()
+            .map(|hit_object| stack_object(self, &timing, hit_object))
+            .collect();
+
+        let attributes = self.difficulty_attributes();
+        if attributes.mode == GameMode::Osu {
+            let leniency = self.general.as_ref()
+                .and_then(|g| g.stack_leniency)
+                .unwrap_or(0.7) as f64;
+            let threshold = attributes.preempt() * leniency;
+
+            if self.version.unwrap_or(14) >= 6 {
+                apply_stacking(&mut objects, threshold);
+            } else {
+                apply_stacking_old(&mut objects, threshold);
+            }
+        }
+
+        let offset = self.stack_offset();
+        objects.iter()
+            .map(|object| {
+                let shift = object.height as f32 * offset;
+                Stack {
+                    height: object.height,
+                    position: object.position + Pos2::new(shift, shift),
+                }
+            })
+            .collect()
+    }
+
+    /// how far each stack level moves an object, up and to the left
+    pub fn stack_offset(&self) -> f32 {
+        (self.difficulty_attributes().scale() * -6.4) as f32
+    }
+}
+
+fn stack_object(beatmap: &Beatmap, timing: &TimingMap, hit_object: &HitObject) -> StackObject {
+    let position = Pos2::new(hit_object.x as f32, hit_object.y as f32);
+    let start_time = hit_object.time as f64;
+
+    let mut object = StackObject {
+        kind: hit_object.kind,
+        start_time,
+        end_time: start_time,
+        position,
+        end_position: position,
+        path_end: position,
+        height: 0,
+    };
+
+    match &hit_object.object_params {
+        ObjectParams::Slider(_) => {
+            if let Some(slider) = Slider::new(beatmap, timing, hit_object) {
+                object.end_time = slider.timing.end_time();
+                object.end_position = slider.end_position();
+                object.path_end = slider.path.position_at(1.0);
+            }
+        }
+        ObjectParams::EndTime(end_time) => object.end_time = *end_time as f64,
+        ObjectParams::None => {}
+    }
+
+    object
+}
+
+/// the stacking pass of maps from v6 on, working backwards from the last object
+fn apply_stacking(objects: &mut [StackObject], threshold: f64) {
+    for i in (1..objects.len()).rev() {
+        if objects[i].height != 0 || objects[i].is_spinner() {
+            continue;
+        }
+
+        //the object the next one stacks on
+        let mut current = i;
+
+        if matches!(objects[i].kind, HitObjectKind::HitCircle) {
+            for n in (0..i).rev() {
+                if objects[n].is_spinner() {
+                    continue;
+                }
+                if objects[current].start_time - objects[n].end_time > threshold {
+                    break;
+                }
+
+                //circles right after a slider's end move away from it instead
+                if objects[n].is_slider() && objects[n].end_position.distance(objects[current].position) < STACK_DISTANCE {
+                    let offset = objects[current].height - objects[n].height + 1;
+                    let end_position = objects[n].end_position;
+
+                    for object in &mut objects[n + 1..=i] {
+                        if end_position.distance(object.position) < STACK_DISTANCE {
+                            object.height -= offset;
+                        }
+                    }
+                    break;
+                }
+
+                if objects[n].position.distance(objects[current].position) < STACK_DISTANCE {
+                    objects[n].height = objects[current].height + 1;
+                    current = n;
+                }
+            }
+        } else if objects[i].is_slider() {
+            for n in (0..i).rev() {
+                if objects[n].is_spinner() {
+                    continue;
+                }
+                if objects[current].start_time - objects[n].start_time > threshold {
+                    break;
+                }
+
+                if objects[n].end_position.distance(objects[current].position) < STACK_DISTANCE {
+                    objects[n].height = objects[current].height + 1;
+                    current = n;
+                }
+            }
+        }
+    }
+}
+
+/// maps before v6 stacked forwards, and onto the end of the slider path
+fn apply_stacking_old(objects: &mut [StackObject], threshold: f64) {
+    for i in 0..objects.len() {
+        if objects[i].height != 0 && !objects[i].is_slider() {
+            continue;
+        }
+
+        let mut start_time = objects[i].end_time;
+        let mut slider_stack = 0;
+
+        for j in i + 1..objects.len() {
+            if objects[j].start_time - threshold > start_time {
+                break;
+            }
+
+            let path_end = if objects[i].is_slider() { objects[i].path_end } else { objects[i].position };
+
+            //the start time, stable never worked out the end time of the later object here
+            if objects[j].position.distance(objects[i].position) < STACK_DISTANCE {
+                objects[i].height += 1;
+                start_time = objects[j].start_time;
+            } else if objects[j].position.distance(path_end) < STACK_DISTANCE {
+                slider_stack += 1;
+                objects[j].height -= slider_stack;
+                start_time = objects[j].start_time;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// AR 5 and CS 4 for a 840 ms stack threshold and 3.2 osu!pixel stack offset,
+    /// sliders move 0.2 osu!pixels per ms
+    fn stack_heights(version: usize, objects: &str) -> Vec<isize> {
+        Beatmap::load_from_string(format!(
+            "osu file format v{}\n\n[Difficulty]\nCircleSize:4\nApproachRate:5\nSliderMultiplier:1\n\n\
+             [TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n{}\n", version, objects
+        )).unwrap()
+            .stacking()
+            .iter()
+            .map(|stack| stack.height)
+            .collect()
+    }
+
+    #[test]
+    fn stream_stacks_up_to_the_first_object() {
+        let objects = "100,100,0,1,0\n100,100,100,1,0\n101,101,200,1,0\n300,300,300,1,0";
+        assert_eq!(stack_heights(14, objects), [2, 1, 0, 0]);
+
+        //too far apart in time to stack
+        assert_eq!(stack_heights(14, "100,100,0,1,0\n100,100,1000,1,0"), [0, 0]);
+    }
+
+    #[test]
+    fn stacked_positions() {
+        let map = Beatmap::load_from_string(String::from(
+            "osu file format v14\n\n[Difficulty]\nCircleSize:5\n\n[HitObjects]\n100,100,0,1,0\n100,100,100,1,0\n"
+        )).unwrap();
+        let stacking = map.stacking();
+        assert_eq!(map.stack_offset(), -3.2);
+        assert_eq!(stacking[0].position, Pos2::new(96.8, 96.8));
+        assert_eq!(stacking[1].position, Pos2::new(100.0, 100.0));
+    }
+
+    #[test]
+    fn circles_on_a_slider_end_stack_below_it() {
+        let slider = "0,0,0,2,0,L|100:0,1,100";
+        assert_eq!(stack_heights(14, &format!("{}\n100,0,600,1,0", slider)), [0, -1]);
+        assert_eq!(stack_heights(14, &format!("{}\n100,0,600,1,0\n100,0,700,1,0", slider)), [0, -1, -2]);
+    }
+
+    #[test]
+    fn old_maps_stack_forwards() {
+        //circles on the end of the path go below the slider
+        assert_eq!(stack_heights(5, "0,0,0,2,0,L|100:0,1,100\n100,0,600,1,0\n100,0,700,1,0"), [0, -1, -2]);
+
+        //the slider at 500 ends at 2000, but the circle at 2500 is measured from its start
+        //so it doesn't reach back to the first one
+        let objects = "200,200,0,2,0,L|500:200,1,300\n200,200,500,2,0,L|500:200,1,300\n200,200,2500,1,0";
+        assert_eq!(stack_heights(5, objects), [1, 1, 0]);
+    }
+}
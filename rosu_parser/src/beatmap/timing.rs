@@ -0,0 +1,239 @@
+use super::{Beatmap, sections::TimingPoint};
+
+//effect flags
+pub const EFFECT_KIAI: usize = 1;
+pub const EFFECT_OMIT_FIRST_BARLINE: usize = 8;
+
+//used before any timing point and for maps without one
+const DEFAULT_BEAT_LENGTH: f64 = 1000.0;
+const DEFAULT_METER: usize = 4;
+const DEFAULT_VOLUME: usize = 100;
+
+/// answers what the timing points say at any time.
+///
+/// lookups are binary searches, so it's fine to call them for every object
+/// on maps with thousands of points
+#[derive(Debug, Clone, Default)]
+pub struct TimingMap {
+    uninherited: Vec<TimingPoint>, //red lines
+    points: Vec<TimingPoint>, //every point, in file order at equal times
+}
+
+impl TimingMap {
+
+    pub fn new(points: &[TimingPoint]) -> Self {
+        let mut points = points.to_vec();
+        //red lines go before green ones at the same time so the green one's sv and samples win, like the game.
+        //stable sort, so the rest keep their file order
+        points.sort_by(|a, b| a.time.total_cmp(&b.time).then(b.uninherited.cmp(&a.uninherited)));
+
+        Self {
+            uninherited: points.iter().filter(|p| p.uninherited).cloned().collect(),
+            points,
+        }
+    }
+
+    pub fn points(&self) -> &[TimingPoint] {
+        &self.points
+    }
+
+    pub fn uninherited_points(&self) -> &[TimingPoint] {
+        &self.uninherited
+    }
+
+    /// the red line active at `time`, objects before the first one use the first one
+    pub fn uninherited_at(&self, time: f64) -> Option<&TimingPoint> {
+        last_at(&self.uninherited, time).or(self.uninherited.first())
+    }
+
+    /// the last point of either kind at `time`, which decides sv, samples and kiai
+    pub fn point_at(&self, time: f64) -> Option<&TimingPoint> {
+        last_at(&self.points, time).or(self.points.first())
+    }
+
+    /// ms per beat, clamped the same way the game does
+    pub fn beat_length_at(&self, time: f64) -> f64 {
+        self.uninherited_at(time)
+            .map(|p| p.beat_length.clamp(6.0, 60000.0))
+            .unwrap_or(DEFAULT_BEAT_LENGTH)
+    }
+
+    pub fn bpm_at(&self, time: f64) -> f64 {
+        60000.0 / self.beat_length_at(time)
+    }
+
+    /// beats per measure
+    pub fn meter_at(&self, time: f64) -> usize {
+        self.uninherited_at(time)
+            .map(|p| p.meter)
+            .unwrap_or(DEFAULT_METER)
+    }
+
+    /// slider velocity multiplier at `time`.
+    ///
+    /// green lines store it as a negative beat length, -100 being 1x. red lines
+    /// have positive beat lengths and reset it to 1x
+    pub fn slider_velocity_at(&self, time: f64) -> f64 {
+        match last_at(&self.points, time) {
+            Some(point) if point.beat_length < 0.0 => {
+                (-100.0 / point.beat_length).clamp(0.1, 10.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// 1 normal, 2 soft, 3 drum, 0 for the map's default
+    pub fn sample_set_at(&self, time: f64) -> usize {
+        self.point_at(time).map(|p| p.sample_set).unwrap_or(0)
+    }
+
+    /// custom sample index, 0 for skin samples
+    pub fn sample_index_at(&self, time: f64) -> usize {
+        self.point_at(time).map(|p| p.sample_index).unwrap_or(0)
+    }
+
+    /// sample volume in percent
+    pub fn volume_at(&self, time: f64) -> usize {
+        self.point_at(time).map(|p| p.volume).unwrap_or(DEFAULT_VOLUME)
+    }
+
+    pub fn kiai_at(&self, time: f64) -> bool {
+        last_at(&self.points, time)
+            .map(|p| p.effects & EFFECT_KIAI != 0)
+            .unwrap_or(false)
+    }
+
+    /// start and end of every kiai section, one still going at the last point ends at infinity
+    pub fn kiai_ranges(&self) -> Vec<(f64, f64)> {
+        let mut ranges = Vec::new();
+        let mut start = None;
+
+        for point in &self.points {
+            let kiai = point.effects & EFFECT_KIAI != 0;
+            match start {
+                None if kiai => start = Some(point.time),
+                Some(start_time) if !kiai => {
+                    ranges.push((start_time, point.time));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(start_time) = start {
+            ranges.push((start_time, f64::INFINITY));
+        }
+        ranges
+    }
+
+    /// whether the first barline of the red line active at `time` is hidden
+    pub fn omit_first_barline_at(&self, time: f64) -> bool {
+        last_at(&self.uninherited, time)
+            .map(|p| p.effects & EFFECT_OMIT_FIRST_BARLINE != 0)
+            .unwrap_or(false)
+    }
+}
+
+impl Beatmap {
+
+    pub fn timing_map(&self) -> TimingMap {
+        TimingMap::new(self.timing_points.as_deref().unwrap_or_default())
+    }
+}
+
+/// the last point at or before `time`
+fn last_at(points: &[TimingPoint], time: f64) -> Option<&TimingPoint> {
+    match points.partition_point(|p| p.time <= time) {
+        0 => None,
+        i => Some(&points[i - 1]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `time,beatLength,meter,sampleSet,sampleIndex,volume,uninherited,effects` lines
+    fn timing_map(points: &str) -> TimingMap {
+        Beatmap::load_from_string(format!("osu file format v14\n\n[TimingPoints]\n{}\n", points))
+            .unwrap()
+            .timing_map()
+    }
+
+    #[test]
+    fn slider_velocity_from_green_lines() {
+        let map = timing_map("0,500,4,1,0,100,1,0\n1000,-50,4,1,0,100,0,0\n2000,-2000,4,1,0,100,0,0\n3000,-1,4,1,0,100,0,0\n4000,250,4,1,0,100,1,0");
+        assert_eq!(map.slider_velocity_at(500.0), 1.0);
+        assert_eq!(map.slider_velocity_at(1000.0), 2.0);
+        //clamped to 0.1x to 10x
+        assert_eq!(map.slider_velocity_at(2500.0), 0.1);
+        assert_eq!(map.slider_velocity_at(3500.0), 10.0);
+        //red lines reset it
+        assert_eq!(map.slider_velocity_at(4000.0), 1.0);
+        assert_eq!(map.beat_length_at(4000.0), 250.0);
+        assert_eq!(map.bpm_at(3999.0), 120.0);
+    }
+
+    #[test]
+    fn lookups_before_the_first_point() {
+        let map = timing_map("1000,500,3,2,0,60,1,1\n2000,-50,4,1,0,100,0,0");
+        //the first red line covers everything before it
+        assert_eq!(map.beat_length_at(0.0), 500.0);
+        assert_eq!(map.meter_at(0.0), 3);
+        assert_eq!(map.sample_set_at(0.0), 2);
+        assert_eq!(map.volume_at(0.0), 60);
+        //but sv and kiai only start at it
+        assert_eq!(map.slider_velocity_at(0.0), 1.0);
+        assert!(!map.kiai_at(0.0));
+
+        let empty = timing_map("");
+        assert_eq!(empty.beat_length_at(0.0), DEFAULT_BEAT_LENGTH);
+        assert_eq!(empty.meter_at(0.0), DEFAULT_METER);
+        assert_eq!(empty.volume_at(0.0), DEFAULT_VOLUME);
+    }
+
+    #[test]
+    fn green_line_wins_at_the_same_time() {
+        for points in ["0,500,4,1,0,100,1,0\n0,-50,4,2,0,70,0,0", "0,-50,4,2,0,70,0,0\n0,500,4,1,0,100,1,0"] {
+            let map = timing_map(points);
+            assert_eq!(map.slider_velocity_at(0.0), 2.0);
+            assert_eq!(map.sample_set_at(0.0), 2);
+            assert_eq!(map.volume_at(0.0), 70);
+            assert_eq!(map.beat_length_at(0.0), 500.0);
+        }
+    }
+
+    #[test]
+    fn kiai() {
+        let map = timing_map("0,500,4,1,0,100,1,0\n1000,-100,4,1,0,100,0,1\n1500,-50,4,1,0,100,0,1\n2000,-100,4,1,0,100,0,0\n3000,-100,4,1,0,100,0,1");
+        assert!(!map.kiai_at(999.0));
+        assert!(map.kiai_at(1000.0));
+        assert!(map.kiai_at(1999.0));
+        assert!(!map.kiai_at(2000.0));
+        assert!(map.kiai_at(10000.0));
+        //the last one never ends
+        assert_eq!(map.kiai_ranges(), [(1000.0, 2000.0), (3000.0, f64::INFINITY)]);
+    }
+
+    #[test]
+    fn omitted_first_barline() {
+        let map = timing_map("0,500,4,1,0,100,1,8\n1000,-100,4,1,0,100,0,0\n2000,500,4,1,0,100,1,0");
+        assert!(map.omit_first_barline_at(0.0));
+        //green lines don't change it
+        assert!(map.omit_first_barline_at(1500.0));
+        assert!(!map.omit_first_barline_at(2000.0));
+    }
+
+    #[test]
+    fn meter_from_red_lines_and_samples_from_any() {
+        //green lines have their own meter in the file, it's ignored
+        let map = timing_map("0,500,3,1,0,100,1,0\n1000,-100,7,2,1,50,0,0\n2000,400,5,3,0,80,1,0");
+        assert_eq!(map.meter_at(1500.0), 3);
+        assert_eq!(map.sample_set_at(1500.0), 2);
+        assert_eq!(map.sample_index_at(1500.0), 1);
+        assert_eq!(map.volume_at(1500.0), 50);
+        assert_eq!(map.meter_at(2000.0), 5);
+        assert_eq!(map.sample_set_at(2000.0), 3);
+        assert_eq!(map.sample_index_at(2000.0), 0);
+    }
+}
@@ -0,0 +1,57 @@
+use std::str::FromStr;
+
+/// a colour written as `r,g,b` or `r,g,b,a` in .osu and skin.ini files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgb {
+
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    /// components as floats in the 0..1 range
+    pub fn as_f32(&self) -> [f32; 4] {
+        [
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+            self.a as f32 / 255.0,
+        ]
+    }
+}
+
+impl Default for Rgb {
+    fn default() -> Self {
+        Self::new(255, 255, 255)
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(',').map(|p| p.trim().parse::<u8>());
+
+        let mut next = || match parts.next() {
+            Some(Ok(v)) => Ok(Some(v)),
+            Some(Err(_)) => Err(format!("invalid colour \"{}\"", s)),
+            None => Ok(None),
+        };
+
+        match (next()?, next()?, next()?, next()?) {
+            (Some(r), Some(g), Some(b), a) => Ok(Self {
+                r,
+                g,
+                b,
+                a: a.unwrap_or(255),
+            }),
+            _ => Err(format!("invalid colour \"{}\"", s)),
+        }
+    }
+}
@@ -1,2 +1,12 @@
 //module for the .osu format
-pub mod beatmap;
\ No newline at end of file
+pub mod beatmap;
+//module for skin.ini and skin element lookup
+pub mod skin;
+//colour values shared by beatmaps and skins
+pub mod colour;
+//mod bitflags shared by beatmaps and replays
+pub mod mods;
+//.osr replays
+pub mod replay;
+//the unstable sorts of .NET the game's results depend on
+pub mod sort;
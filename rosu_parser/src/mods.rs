@@ -0,0 +1,248 @@
+use std::{fmt, str::FromStr};
+
+use bitflags::bitflags;
+
+use crate::beatmap::{Beatmap, pos::PLAYFIELD_HEIGHT, sections::{GameMode, ObjectParams}};
+
+bitflags! {
+    /// mods as the game stores them, the same bits are used in replays and scores
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Mods: u32 {
+        const NO_FAIL = 1 << 0;
+        const EASY = 1 << 1;
+        const TOUCH_DEVICE = 1 << 2;
+        const HIDDEN = 1 << 3;
+        const HARD_ROCK = 1 << 4;
+        const SUDDEN_DEATH = 1 << 5;
+        const DOUBLE_TIME = 1 << 6;
+        const RELAX = 1 << 7;
+        const HALF_TIME = 1 << 8;
+        const NIGHTCORE = 1 << 9; //always set together with double time
+        const FLASHLIGHT = 1 << 10;
+        const AUTOPLAY = 1 << 11;
+        const SPUN_OUT = 1 << 12;
+        const AUTOPILOT = 1 << 13;
+        const PERFECT = 1 << 14; //always set together with sudden death
+        const KEY_4 = 1 << 15;
+        const KEY_5 = 1 << 16;
+        const KEY_6 = 1 << 17;
+        const KEY_7 = 1 << 18;
+        const KEY_8 = 1 << 19;
+        const FADE_IN = 1 << 20;
+        const RANDOM = 1 << 21;
+        const CINEMA = 1 << 22;
+        const TARGET = 1 << 23;
+        const KEY_9 = 1 << 24;
+        const KEY_COOP = 1 << 25;
+        const KEY_1 = 1 << 26;
+        const KEY_3 = 1 << 27;
+        const KEY_2 = 1 << 28;
+        const SCORE_V2 = 1 << 29;
+        const MIRROR = 1 << 30;
+    }
+}
+
+/// acronyms in the order the game lists them, implied mods come after what implies them
+const ACRONYMS: [(&str, Mods); 31] = [
+    ("NF", Mods::NO_FAIL),
+    ("EZ", Mods::EASY),
+    ("TD", Mods::TOUCH_DEVICE),
+    ("HD", Mods::HIDDEN),
+    ("HR", Mods::HARD_ROCK),
+    ("SD", Mods::SUDDEN_DEATH),
+    ("DT", Mods::DOUBLE_TIME),
+    ("RX", Mods::RELAX),
+    ("HT", Mods::HALF_TIME),
+    ("NC", Mods::NIGHTCORE),
+    ("FL", Mods::FLASHLIGHT),
+    ("AT", Mods::AUTOPLAY),
+    ("SO", Mods::SPUN_OUT),
+    ("AP", Mods::AUTOPILOT),
+    ("PF", Mods::PERFECT),
+    ("4K", Mods::KEY_4),
+    ("5K", Mods::KEY_5),
+    ("6K", Mods::KEY_6),
+    ("7K", Mods::KEY_7),
+    ("8K", Mods::KEY_8),
+    ("FI", Mods::FADE_IN),
+    ("RD", Mods::RANDOM),
+    ("CN", Mods::CINEMA),
+    ("TP", Mods::TARGET),
+    ("9K", Mods::KEY_9),
+    ("CP", Mods::KEY_COOP),
+    ("1K", Mods::KEY_1),
+    ("3K", Mods::KEY_3),
+    ("2K", Mods::KEY_2),
+    ("V2", Mods::SCORE_V2),
+    ("MR", Mods::MIRROR),
+];
+
+impl Mods {
+
+    /// how fast the map plays, 1.5 for DT/NC and 0.75 for HT
+    pub fn clock_rate(&self) -> f64 {
+        if self.intersects(Mods::DOUBLE_TIME | Mods::NIGHTCORE) {
+            1.5
+        } else if self.contains(Mods::HALF_TIME) {
+            0.75
+        } else {
+            1.0
+        }
+    }
+}
+
+/// parses acronyms like `HDHR` or `hd,dt`, NC and PF also set the mods they imply and `NM` sets none
+impl FromStr for Mods {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let acronyms: Vec<char> = s.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+
+        if !acronyms.len().is_multiple_of(2) {
+            return Err(format!("invalid mods \"{}\"", s));
+        }
+
+        let mut mods = Mods::empty();
+        for pair in acronyms.chunks(2) {
+            let acronym: String = pair.iter().collect();
+            if acronym == "NM" {
+                continue;
+            }
+            let (_, m) = ACRONYMS.iter()
+                .find(|(name, _)| *name == acronym)
+                .ok_or_else(|| format!("unknown mod \"{}\"", acronym))?;
+            mods |= *m;
+        }
+
+        if mods.contains(Mods::NIGHTCORE) {
+            mods |= Mods::DOUBLE_TIME;
+        }
+        if mods.contains(Mods::PERFECT) {
+            mods |= Mods::SUDDEN_DEATH;
+        }
+
+        Ok(mods)
+    }
+}
+
+/// acronyms without the mods implied by NC and PF, `NM` when empty
+impl fmt::Display for Mods {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "NM");
+        }
+
+        for (acronym, m) in ACRONYMS {
+            let implied = (m == Mods::DOUBLE_TIME && self.contains(Mods::NIGHTCORE))
+                || (m == Mods::SUDDEN_DEATH && self.contains(Mods::PERFECT));
+            if self.contains(m) && !implied {
+                write!(f, "{}", acronym)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Beatmap {
+
+    /// bakes HR/EZ into the `[Difficulty]` values and flips the map for HR.
+    ///
+    /// the clock rate can't be stored in a map, use `DifficultyAttributes::with_mods` for that
+    pub fn apply_mods(&mut self, mods: Mods) {
+        let attributes = self.difficulty_attributes().with_mods(mods);
+        let difficulty = self.difficulty.get_or_insert_with(Default::default);
+        difficulty.circle_size = Some(attributes.circle_size as f32);
+        difficulty.approach_rate = Some(attributes.approach_rate as f32);
+        difficulty.overall_difficulty = Some(attributes.overall_difficulty as f32);
+        difficulty.hp_drain_rate = Some(attributes.hp_drain_rate as f32);
+
+        if mods.contains(Mods::HARD_ROCK) && self.mode() == GameMode::Osu {
+            self.flip_vertically();
+        }
+    }
+
+    /// mirrors every object and slider point over the middle of the playfield
+    pub fn flip_vertically(&mut self) {
+        for hit_object in self.hit_objects.iter_mut().flatten() {
+            hit_object.y = PLAYFIELD_HEIGHT as isize - hit_object.y;
+
+            if let ObjectParams::Slider(params) = &mut hit_object.object_params {
+                for (_, y) in &mut params.curve_points {
+                    *y = PLAYFIELD_HEIGHT as isize - *y;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flip_keeps_sliders_below_the_playfield_attached() {
+        let mut map = Beatmap::load_from_string(
+            "osu file format v14\n\n[HitObjects]\n100,400,1000,2,0,L|200:450,1,100\n-20,-10,2000,1,0\n".to_string()
+        ).unwrap();
+        map.flip_vertically();
+
+        let hit_objects = map.hit_objects.unwrap();
+        assert_eq!(hit_objects[0].y, -16);
+        match &hit_objects[0].object_params {
+            ObjectParams::Slider(params) => assert_eq!(params.curve_points, vec![(200, -66)]),
+            _ => panic!("expected a slider"),
+        }
+        assert_eq!((hit_objects[1].x, hit_objects[1].y), (-20, 394));
+    }
+
+    #[test]
+    fn implied_mods() {
+        assert_eq!("NC".parse::<Mods>().unwrap(), Mods::NIGHTCORE | Mods::DOUBLE_TIME);
+        assert_eq!("hd,pf".parse::<Mods>().unwrap(), Mods::HIDDEN | Mods::PERFECT | Mods::SUDDEN_DEATH);
+        assert_eq!("NC".parse::<Mods>().unwrap().clock_rate(), 1.5);
+    }
+
+    #[test]
+    fn no_mods() {
+        assert_eq!("NM".parse::<Mods>().unwrap(), Mods::empty());
+        assert_eq!("".parse::<Mods>().unwrap(), Mods::empty());
+        assert_eq!(Mods::empty().to_string(), "NM");
+    }
+
+    #[test]
+    fn invalid_mods() {
+        assert!("HDH".parse::<Mods>().is_err());
+        assert!("HDXX".parse::<Mods>().is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for acronyms in ["NM", "HDHR", "EZHDNC", "HDPF", "NFHT", "4KMR"] {
+            let mods: Mods = acronyms.parse().unwrap();
+            assert_eq!(mods.to_string(), acronyms);
+            assert_eq!(mods.to_string().parse::<Mods>().unwrap(), mods);
+        }
+    }
+
+    #[test]
+    fn hard_rock_caps_and_easy_halves() {
+        let map = || Beatmap::load_from_string(String::from(
+            "osu file format v14\n\n[Difficulty]\nHPDrainRate:6\nCircleSize:4\nOverallDifficulty:8\nApproachRate:9\n"
+        )).unwrap();
+
+        let mut hard_rock = map();
+        hard_rock.apply_mods(Mods::HARD_ROCK);
+        let difficulty = hard_rock.difficulty.unwrap();
+        assert_eq!((difficulty.approach_rate, difficulty.overall_difficulty), (Some(10.0), Some(10.0)));
+        assert_eq!((difficulty.circle_size, difficulty.hp_drain_rate), (Some(5.2), Some(8.4)));
+
+        let mut easy = map();
+        easy.apply_mods(Mods::EASY);
+        let difficulty = easy.difficulty.unwrap();
+        assert_eq!((difficulty.approach_rate, difficulty.overall_difficulty), (Some(4.5), Some(4.0)));
+        assert_eq!((difficulty.circle_size, difficulty.hp_drain_rate), (Some(2.0), Some(3.0)));
+    }
+}
@@ -0,0 +1,297 @@
+use std::{fs, path::Path};
+
+use crate::{beatmap::{Error, sections::GameMode}, mods::Mods};
+
+/// the last frame of newer replays only stores the rng seed, with this as its time
+const SEED_FRAME_TIME: i64 = -12345;
+
+/// an .osr file
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    pub mode: GameMode,
+    pub version: u32, //game version the replay was made on, e.g. 20221219
+    pub beatmap_hash: String, //md5 of the .osu file
+    pub player_name: String,
+    pub replay_hash: String,
+    pub n300: usize,
+    pub n100: usize,
+    pub n50: usize,
+    pub n_geki: usize,
+    pub n_katu: usize,
+    pub n_misses: usize,
+    pub score: u32,
+    pub max_combo: usize,
+    pub perfect: bool,
+    pub mods: Mods,
+    pub life_bar: Vec<(f64, f64)>, //ms, health from 0 to 1
+    pub timestamp: i64, //.NET ticks
+    pub frames: Vec<ReplayFrame>,
+    pub online_id: i64,
+    pub rng_seed: Option<i64>,
+}
+
+/// cursor position and pressed keys at a point in time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplayFrame {
+    pub time: f64, //ms since the start of the map
+    pub x: f32,
+    pub y: f32,
+    pub keys: u32,
+}
+
+impl Replay {
+
+    pub fn load_from_file(path: &Path) -> Result<Replay, Error> {
+        match fs::read(path) {
+            Ok(bytes) => Replay::load_from_bytes(&bytes),
+            Err(_) => Err(Error::Parse(format!("couldn't read \"{}\"", path.display()))),
+        }
+    }
+
+    pub fn load_from_bytes(bytes: &[u8]) -> Result<Replay, Error> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        let mut replay = Replay {
+            mode: GameMode::from_id(reader.u8()? as usize).unwrap_or_default(),
+            version: reader.u32()?,
+            beatmap_hash: reader.string()?,
+            player_name: reader.string()?,
+            replay_hash: reader.string()?,
+            n300: reader.u16()? as usize,
+            n100: reader.u16()? as usize,
+            n50: reader.u16()? as usize,
+            n_geki: reader.u16()? as usize,
+            n_katu: reader.u16()? as usize,
+            n_misses: reader.u16()? as usize,
+            score: reader.u32()?,
+            max_combo: reader.u16()? as usize,
+            perfect: reader.u8()? != 0,
+            mods: Mods::from_bits_truncate(reader.u32()?),
+            ..Default::default()
+        };
+
+        replay.life_bar = parse_life_bar(&reader.string()?);
+        replay.timestamp = reader.i64()?;
+
+        let compressed_length = reader.u32()? as usize;
+        let compressed = reader.take(compressed_length)?;
+        (replay.frames, replay.rng_seed) = parse_frames(compressed)?;
+
+        //older replays end before the score id
+        replay.online_id = reader.i64().unwrap_or(0);
+
+        Ok(replay)
+    }
+
+    /// the accuracy the game shows for an osu!standard score, from 0 to 1
+    pub fn accuracy(&self) -> f64 {
+        let total = self.n300 + self.n100 + self.n50 + self.n_misses;
+        if total == 0 {
+            return 0.0;
+        }
+        (self.n300 * 6 + self.n100 * 2 + self.n50) as f64 / (total * 6) as f64
+    }
+}
+
+/// `ms|health,` pairs
+fn parse_life_bar(string: &str) -> Vec<(f64, f64)> {
+    string.split(',')
+        .filter_map(|point| {
+            let (time, health) = point.split_once('|')?;
+            Some((time.trim().parse().ok()?, health.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// lzma compressed `w|x|y|z,` frames where w is the time since the previous frame
+fn parse_frames(compressed: &[u8]) -> Result<(Vec<ReplayFrame>, Option<i64>), Error> {
+    if compressed.is_empty() {
+        return Ok((Vec::new(), None));
+    }
+
+    let mut decompressed = Vec::new();
+    lzma_rs::lzma_decompress(&mut &compressed[..], &mut decompressed)
+        .map_err(|e| Error::Parse(format!("couldn't decompress replay frames: {}", e)))?;
+    let string = String::from_utf8_lossy(&decompressed);
+
+    let mut frames = Vec::new();
+    let mut rng_seed = None;
+    let mut time = 0;
+
+    for frame in string.split(',').filter(|f| !f.trim().is_empty()) {
+        let mut parts = frame.split('|');
+        let mut next = || parts.next().map(str::trim).unwrap_or("0");
+
+        let delta: i64 = next().parse().unwrap_or(0);
+        let x: f32 = next().parse().unwrap_or(0.0);
+        let y: f32 = next().parse().unwrap_or(0.0);
+        let keys: i64 = next().parse().unwrap_or(0);
+
+        if delta == SEED_FRAME_TIME {
+            rng_seed = Some(keys);
+            continue;
+        }
+
+        time += delta;
+        frames.push(ReplayFrame {
+            time: time as f64,
+            x,
+            y,
+            keys: keys as u32,
+        });
+    }
+
+    Ok((frames, rng_seed))
+}
+
+/// little endian values and the game's length-prefixed strings
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let end = match self.pos.checked_add(length) {
+            Some(end) if end <= self.bytes.len() => end,
+            _ => return Err(Error::Parse("unexpected end of replay".to_owned())),
+        };
+
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64, Error> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+
+    /// 0x00 for no string, or 0x0b followed by a uleb128 length and utf-8
+    fn string(&mut self) -> Result<String, Error> {
+        match self.u8()? {
+            0x00 => Ok(String::new()),
+            0x0b => {
+                let mut length = 0;
+                let mut shift = 0;
+                loop {
+                    if shift >= 64 {
+                        return Err(Error::Parse("string length too long in replay".to_owned()));
+                    }
+                    let byte = self.u8()?;
+                    length |= ((byte & 0x7f) as usize) << shift;
+                    if byte & 0x80 == 0 {
+                        break;
+                    }
+                    shift += 7;
+                }
+                Ok(String::from_utf8_lossy(self.take(length)?).into_owned())
+            }
+            other => Err(Error::Parse(format!("invalid string marker {:#04x} in replay", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// an osu!standard replay of 98 300s, 1 100 and 1 miss with HDDT, and the frames as they're stored
+    fn osr(frames: &str) -> Vec<u8> {
+        //all the strings are short enough for a one byte length
+        fn string(bytes: &mut Vec<u8>, string: &str) {
+            bytes.extend([0x0b, string.len() as u8]);
+            bytes.extend(string.as_bytes());
+        }
+
+        let mut bytes = vec![0];
+        bytes.extend(20221219u32.to_le_bytes());
+        string(&mut bytes, "d41d8cd98f00b204e9800998ecf8427e");
+        string(&mut bytes, "peppy");
+        bytes.push(0x00);
+        for count in [98u16, 1, 0, 12, 1, 1] {
+            bytes.extend(count.to_le_bytes());
+        }
+        bytes.extend(1_000_000u32.to_le_bytes());
+        bytes.extend(150u16.to_le_bytes());
+        bytes.push(0);
+        bytes.extend((Mods::HIDDEN | Mods::DOUBLE_TIME).bits().to_le_bytes());
+        string(&mut bytes, "0|1,5000|0.75,");
+        bytes.extend(638_000_000_000_000_000i64.to_le_bytes());
+
+        let mut compressed = Vec::new();
+        lzma_rs::lzma_compress(&mut frames.as_bytes(), &mut compressed).unwrap();
+        bytes.extend((compressed.len() as u32).to_le_bytes());
+        bytes.extend(compressed);
+        bytes.extend(4_000_000_000i64.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn header_fields() {
+        let replay = Replay::load_from_bytes(&osr("")).unwrap();
+        assert_eq!(replay.mode, GameMode::Osu);
+        assert_eq!(replay.version, 20221219);
+        assert_eq!(replay.beatmap_hash, "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(replay.player_name, "peppy");
+        assert_eq!(replay.replay_hash, "");
+        assert_eq!((replay.n300, replay.n100, replay.n50), (98, 1, 0));
+        assert_eq!((replay.n_geki, replay.n_katu, replay.n_misses), (12, 1, 1));
+        assert_eq!((replay.score, replay.max_combo, replay.perfect), (1_000_000, 150, false));
+        assert_eq!(replay.mods, Mods::HIDDEN | Mods::DOUBLE_TIME);
+        assert_eq!(replay.life_bar, [(0.0, 1.0), (5000.0, 0.75)]);
+        assert_eq!(replay.timestamp, 638_000_000_000_000_000);
+        assert_eq!(replay.online_id, 4_000_000_000);
+        assert!(replay.frames.is_empty());
+    }
+
+    #[test]
+    fn frames_add_up_time_deltas() {
+        let replay = Replay::load_from_bytes(&osr("0|256|-500|0,-1|256|-500|0,16|100.5|200|1,17|120|210.25|5,-12345|0|0|8172,")).unwrap();
+        let frames: Vec<(f64, f32, f32, u32)> = replay.frames.iter().map(|f| (f.time, f.x, f.y, f.keys)).collect();
+        //the seed frame is left out
+        assert_eq!(frames, [
+            (0.0, 256.0, -500.0, 0),
+            (-1.0, 256.0, -500.0, 0),
+            (15.0, 100.5, 200.0, 1),
+            (32.0, 120.0, 210.25, 5),
+        ]);
+        assert_eq!(replay.rng_seed, Some(8172));
+    }
+
+    #[test]
+    fn truncated_replay() {
+        let bytes = osr("0|256|-500|0,16|100|200|1,");
+        //only the score id at the end is optional
+        assert!(Replay::load_from_bytes(&bytes[..bytes.len() - 8]).is_ok());
+        assert!(Replay::load_from_bytes(&bytes[..bytes.len() - 9]).is_err());
+        assert!(Replay::load_from_bytes(&bytes[..30]).is_err());
+    }
+
+    #[test]
+    fn string_length_too_long() {
+        let mut bytes = vec![0];
+        bytes.extend(20221219u32.to_le_bytes());
+        bytes.push(0x0b);
+        bytes.extend([0xff; 12]);
+        assert!(Replay::load_from_bytes(&bytes).is_err());
+    }
+}
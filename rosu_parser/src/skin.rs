@@ -0,0 +1,318 @@
+use std::{path::{Path, PathBuf}, str::FromStr};
+
+pub mod elements;
+pub mod sections;
+use sections::*;
+use elements::ElementLookup;
+
+use crate::beatmap::Error;
+
+/// a parsed skin.ini, every section falls back to the game defaults when missing
+#[derive(Debug, Default)]
+pub struct Skin {
+    pub path: Option<PathBuf>,
+
+    pub general: General,
+    pub colours: Colours,
+    pub fonts: Fonts,
+    pub catch_the_beat: CatchTheBeat,
+    pub mania: Vec<Mania>,
+}
+
+impl Skin {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn load_from_string(string: String) -> Result<Skin, Error> {
+        SkinParser::default().parse(&string)
+    }
+
+    /// loads the skin.ini inside a skin folder or .osk file, a skin without one is the default skin
+    pub fn load_from_path(path: &Path) -> Result<Skin, Error> {
+        if !path.exists() {
+            return Err(Error::Parse(format!("skin \"{}\" not found", path.display())));
+        }
+
+        let lookup = ElementLookup::new().with_source(path);
+
+        let mut skin = match lookup.file("skin.ini") {
+            Some(ini) => match lookup.read(&ini) {
+                Some(bytes) => Skin::load_from_bytes(&bytes)?,
+                None => return Err(Error::Parse(format!("couldn't read \"{}\"", ini.display()))),
+            },
+            None => Skin::new(),
+        };
+
+        skin.path = Some(path.to_path_buf());
+        Ok(skin)
+    }
+
+    /// skin.ini files aren't always utf-8, so decode lossily
+    pub fn load_from_bytes(bytes: &[u8]) -> Result<Skin, Error> {
+        Skin::load_from_string(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    /// prefix of the default-0 to default-9 number sprites
+    pub fn hit_circle_prefix(&self) -> &str {
+        self.fonts.hit_circle_prefix.as_deref().unwrap_or("default")
+    }
+
+    /// how far the number sprites overlap each other, in pixels
+    pub fn hit_circle_overlap(&self) -> isize {
+        self.fonts.hit_circle_overlap.unwrap_or(-2)
+    }
+
+    /// the [Mania] section for a key count, if the skin has one
+    pub fn mania(&self, keys: usize) -> Option<&Mania> {
+        self.mania.iter().find(|m| m.keys == Some(keys))
+    }
+}
+
+enum Section {
+    General,
+    Colours,
+    Fonts,
+    CatchTheBeat,
+    Mania,
+    Unknown,
+}
+
+#[derive(Default)]
+struct SkinParser {
+    section: Option<Section>,
+    result: Skin,
+}
+
+impl SkinParser {
+
+    fn parse(mut self, string: &str) -> Result<Skin, Error> {
+
+        for line in string.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                self.parse_section(line.trim_start_matches('[').trim_end_matches(']').trim());
+                continue;
+            }
+
+            //lines that aren't "key: value" are skipped like the game does
+            if let Some((key, val)) = line.split_once(':') {
+                //skins commonly have trailing comments after values
+                let val = match val.find("//") {
+                    Some(i) => &val[..i],
+                    None => val,
+                };
+                self.parse_val(key.trim(), val.trim());
+            }
+        }
+
+        Ok(self.result)
+    }
+
+    fn parse_section(&mut self, section_str: &str) {
+        self.section = Some(match section_str {
+            "General" => Section::General,
+            "Colours" => Section::Colours,
+            "Fonts" => Section::Fonts,
+            "CatchTheBeat" => Section::CatchTheBeat,
+            "Mania" => {
+                self.result.mania.push(Mania::default());
+                Section::Mania
+            }
+            //unknown sections are skipped, the game ignores them too
+            _ => Section::Unknown,
+        });
+    }
+
+    fn parse_val(&mut self, key: &str, val: &str) {
+        let s = &mut self.result;
+
+        match self.section {
+            Some(Section::General) => match key {
+                "Name" => s.general.name = Some(String::from(val)),
+                "Author" => s.general.author = Some(String::from(val)),
+                "Version" => s.general.version = Some(String::from(val)),
+                "AnimationFramerate" => s.general.animation_framerate = parse(val),
+                "AllowSliderBallTint" => s.general.allow_slider_ball_tint = parse_bool(val),
+                "ComboBurstRandom" => s.general.combo_burst_random = parse_bool(val),
+                "CursorCentre" => s.general.cursor_centre = parse_bool(val),
+                "CursorExpand" => s.general.cursor_expand = parse_bool(val),
+                "CursorRotate" => s.general.cursor_rotate = parse_bool(val),
+                "CursorTrailRotate" => s.general.cursor_trail_rotate = parse_bool(val),
+                "CustomComboBurstSounds" => s.general.custom_combo_burst_sounds = Some(String::from(val)),
+                //the misspelling is what older skins use
+                "HitCircleOverlayAboveNumber" | "HitCircleOverlayAboveNumer" =>
+                    s.general.hit_circle_overlay_above_number = parse_bool(val),
+                "LayeredHitSounds" => s.general.layered_hit_sounds = parse_bool(val),
+                "SliderBallFlip" => s.general.slider_ball_flip = parse_bool(val),
+                "SpinnerFadePlayfield" => s.general.spinner_fade_playfield = parse_bool(val),
+                "SpinnerFrequencyModulate" => s.general.spinner_frequency_modulate = parse_bool(val),
+                "SpinnerNoBlink" => s.general.spinner_no_blink = parse_bool(val),
+                _ => {}
+            },
+            Some(Section::Colours) => match key {
+                "InputOverlayText" => s.colours.input_overlay_text = parse(val),
+                "MenuGlow" => s.colours.menu_glow = parse(val),
+                "SliderBall" => s.colours.slider_ball = parse(val),
+                "SliderBorder" => s.colours.slider_border = parse(val),
+                "SliderTrackOverride" => s.colours.slider_track_override = parse(val),
+                "SongSelectActiveText" => s.colours.song_select_active_text = parse(val),
+                "SongSelectInactiveText" => s.colours.song_select_inactive_text = parse(val),
+                "SpinnerBackground" => s.colours.spinner_background = parse(val),
+                "StarBreakAdditive" => s.colours.star_break_additive = parse(val),
+                _ => {
+                    if let Some(Ok(i)) = key.strip_prefix("Combo").map(str::parse::<usize>) {
+                        if (1..=8).contains(&i) {
+                            s.colours.combo[i - 1] = parse(val);
+                        }
+                    }
+                }
+            },
+            Some(Section::Fonts) => match key {
+                "HitCirclePrefix" => s.fonts.hit_circle_prefix = Some(String::from(val)),
+                "HitCircleOverlap" => s.fonts.hit_circle_overlap = parse(val),
+                "ScorePrefix" => s.fonts.score_prefix = Some(String::from(val)),
+                "ScoreOverlap" => s.fonts.score_overlap = parse(val),
+                "ComboPrefix" => s.fonts.combo_prefix = Some(String::from(val)),
+                "ComboOverlap" => s.fonts.combo_overlap = parse(val),
+                _ => {}
+            },
+            Some(Section::CatchTheBeat) => match key {
+                "HyperDash" => s.catch_the_beat.hyper_dash = parse(val),
+                "HyperDashFruit" => s.catch_the_beat.hyper_dash_fruit = parse(val),
+                "HyperDashAfterImage" => s.catch_the_beat.hyper_dash_after_image = parse(val),
+                _ => {}
+            },
+            Some(Section::Mania) => {
+                //parse_section always pushes before we get here
+                let m = s.mania.last_mut().unwrap();
+                match key {
+                    "Keys" => m.keys = parse(val),
+                    "ColumnStart" => m.column_start = parse(val),
+                    "ColumnRight" => m.column_right = parse(val),
+                    "ColumnSpacing" => m.column_spacing = Some(String::from(val)),
+                    "ColumnWidth" => m.column_width = Some(String::from(val)),
+                    "ColumnLineWidth" => m.column_line_width = Some(String::from(val)),
+                    "BarlineHeight" => m.barline_height = parse(val),
+                    "HitPosition" => m.hit_position = parse(val),
+                    "LightPosition" => m.light_position = parse(val),
+                    "ScorePosition" => m.score_position = parse(val),
+                    "ComboPosition" => m.combo_position = parse(val),
+                    "JudgementLine" => m.judgement_line = parse_bool(val),
+                    "SpecialStyle" => m.special_style = parse(val),
+                    "UpsideDown" => m.upside_down = parse_bool(val),
+                    "ColourColumnLine" => m.colour_column_line = parse(val),
+                    "ColourJudgementLine" => m.colour_judgement_line = parse(val),
+                    "ColourBarline" => m.colour_barline = parse(val),
+                    _ => {
+                        let column_colour = key.strip_prefix("Colour")
+                            .and_then(|i| i.parse::<usize>().ok());
+
+                        match (column_colour, parse::<crate::colour::Rgb>(val)) {
+                            (Some(i), Some(colour)) => m.column_colours.push((i, colour)),
+                            _ => {
+                                m.images.insert(String::from(key), String::from(val));
+                            }
+                        }
+                    }
+                }
+            }
+            Some(Section::Unknown) | None => {}
+        }
+    }
+}
+
+/// skins are full of hand edited values, so invalid ones are treated as missing
+fn parse<T: FromStr>(val: &str) -> Option<T> {
+    val.parse().ok()
+}
+
+fn parse_bool(val: &str) -> Option<bool> {
+    match val {
+        "1" | "true" | "True" => Some(true),
+        "0" | "false" | "False" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::colour::Rgb;
+
+    use super::*;
+
+    fn parse_skin(ini: &str) -> Skin {
+        Skin::load_from_string(String::from(ini)).unwrap()
+    }
+
+    #[test]
+    fn general_values() {
+        let skin = parse_skin("[General]\nName: My Skin\nVersion: 2.7\nCursorCentre: 0\nHitCircleOverlayAboveNumer: 1\nAnimationFramerate: twelve");
+        assert_eq!(skin.general.name.as_deref(), Some("My Skin"));
+        assert_eq!(skin.general.version.as_deref(), Some("2.7"));
+        assert_eq!(skin.general.cursor_centre, Some(false));
+        assert_eq!(skin.general.hit_circle_overlay_above_number, Some(true));
+        //invalid values are treated as missing
+        assert_eq!(skin.general.animation_framerate, None);
+    }
+
+    #[test]
+    fn colour_lines() {
+        let skin = parse_skin("[Colours]\nCombo1: 255,0,0\nCombo3 : 0, 128 ,255\nCombo9: 1,2,3\nSliderBorder: 10,20,30,40\nSliderBall: 10,20");
+        assert_eq!(skin.colours.combo[0], Some(Rgb::new(255, 0, 0)));
+        assert_eq!(skin.colours.combo[1], None);
+        assert_eq!(skin.colours.combo[2], Some(Rgb::new(0, 128, 255)));
+        assert_eq!(skin.colours.combo_colours(), [Rgb::new(255, 0, 0), Rgb::new(0, 128, 255)]);
+        assert_eq!(skin.colours.slider_border, Some(Rgb { r: 10, g: 20, b: 30, a: 40 }));
+        assert_eq!(skin.colours.slider_ball, None);
+    }
+
+    #[test]
+    fn comments_and_spacing() {
+        let skin = parse_skin("// a comment\n\n  [ Fonts ]  \n  HitCirclePrefix  :  numbers/default // where they are\nHitCircleOverlap: 3\n//ScoreOverlap: 5");
+        assert_eq!(skin.hit_circle_prefix(), "numbers/default");
+        assert_eq!(skin.hit_circle_overlap(), 3);
+        assert_eq!(skin.fonts.score_overlap, None);
+    }
+
+    #[test]
+    fn catch_the_beat_and_unknown_sections() {
+        let skin = parse_skin("[CatchTheBeat]\nHyperDash: 255,0,0\n[Unknown]\nHyperDashFruit: 0,0,0");
+        assert_eq!(skin.catch_the_beat.hyper_dash, Some(Rgb::new(255, 0, 0)));
+        assert_eq!(skin.catch_the_beat.hyper_dash_fruit, None);
+    }
+
+    #[test]
+    fn mania_sections_per_key_count() {
+        let skin = parse_skin(
+            "[Mania]\nKeys: 4\nColumnWidth: 30,30,30,30\nColour1: 10,10,10\nKeyImage0: mania/key1\n\
+             [Mania]\nKeys: 7\nJudgementLine: 1\nColourBarline: 255,255,255"
+        );
+        assert_eq!(skin.mania.len(), 2);
+
+        let four = skin.mania(4).unwrap();
+        assert_eq!(four.column_width.as_deref(), Some("30,30,30,30"));
+        assert_eq!(four.column_colours, [(1, Rgb::new(10, 10, 10))]);
+        assert_eq!(four.images.get("KeyImage0").map(String::as_str), Some("mania/key1"));
+        assert_eq!(four.judgement_line, None);
+
+        let seven = skin.mania(7).unwrap();
+        assert_eq!(seven.judgement_line, Some(true));
+        assert_eq!(seven.colour_barline, Some(Rgb::new(255, 255, 255)));
+        assert!(skin.mania(5).is_none());
+    }
+
+    #[test]
+    fn lines_without_a_value_are_skipped() {
+        let skin = parse_skin("[General]\nName: My Skin\nthis line is broken\nAuthor: someone\n[Fonts]\nHitCircleOverlap: 3");
+        assert_eq!(skin.general.name.as_deref(), Some("My Skin"));
+        assert_eq!(skin.general.author.as_deref(), Some("someone"));
+        assert_eq!(skin.hit_circle_overlap(), 3);
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    path::{Path, PathBuf},
+    fs::{self, File},
+    collections::HashMap,
+    io::Read,
+    sync::{Arc, Mutex},
+};
+
+use zip::ZipArchive;
+
+const IMAGE_EXTENSIONS: [&str; 2] = ["png", "jpg"];
+const SAMPLE_EXTENSIONS: [&str; 3] = ["wav", "ogg", "mp3"];
+
+/// a skin file that was found in one of the lookup sources
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub path: PathBuf, //for .osk files this is the archive path joined with the entry name
+    pub high_res: bool, //@2x variant, drawn at half size
+}
+
+#[derive(Debug, Clone)]
+enum Origin {
+    Dir,
+    Archive(Arc<Mutex<ZipArchive<File>>>), //.osk skins are zip files
+}
+
+/// a folder or .osk archive that skin elements can come from
+#[derive(Debug, Clone)]
+struct Source {
+    root: PathBuf,
+    origin: Origin,
+    files: HashMap<String, PathBuf>, //lowercase relative path -> real path
+}
+
+impl Source {
+
+    fn new(root: &Path) -> Self {
+        if root.is_file() {
+            if let Some(source) = Source::from_archive(root) {
+                return source;
+            }
+        }
+
+        let mut files = HashMap::new();
+
+        if let Ok(entries) = fs::read_dir(root) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_lowercase();
+                files.insert(name, entry.path());
+            }
+        }
+
+        Self {
+            root: root.to_path_buf(),
+            origin: Origin::Dir,
+            files,
+        }
+    }
+
+    fn from_archive(root: &Path) -> Option<Self> {
+        let archive = ZipArchive::new(File::open(root).ok()?).ok()?;
+        let names: Vec<&str> = archive.file_names()
+            .filter(|name| !name.ends_with('/'))
+            .collect();
+
+        //many .osk files have everything in one folder, elements are looked up inside it
+        let prefix = common_folder(&names).unwrap_or_default();
+        let files = names.iter()
+            .map(|name| (name[prefix.len()..].to_lowercase(), root.join(name)))
+            .collect();
+
+        Some(Self {
+            root: root.to_path_buf(),
+            origin: Origin::Archive(Arc::new(Mutex::new(archive))),
+            files,
+        })
+    }
+
+    /// osu! was made for windows, so file names are matched case insensitively
+    fn get(&self, name: &str) -> Option<PathBuf> {
+        let name = name.replace('\\', "/");
+
+        if let Some(path) = self.files.get(&name.to_lowercase()) {
+            return Some(path.clone());
+        }
+
+        //only the top level of folders is indexed
+        match self.origin {
+            Origin::Dir if name.contains('/') => {
+                let path = self.root.join(name);
+                path.exists().then_some(path)
+            }
+            _ => None,
+        }
+    }
+
+    fn read(&self, path: &Path) -> Option<Vec<u8>> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+
+        match &self.origin {
+            Origin::Dir => fs::read(path).ok(),
+            Origin::Archive(archive) => {
+                //zip entries always use forward slashes
+                let name = relative.iter()
+                    .map(|part| part.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                let mut archive = archive.lock().unwrap();
+                let mut file = archive.by_name(&name).ok()?;
+                let mut bytes = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut bytes).ok()?;
+                Some(bytes)
+            }
+        }
+    }
+
+    fn is_archive(&self) -> bool {
+        matches!(self.origin, Origin::Archive(_))
+    }
+}
+
+/// resolves skin element names to files.
+///
+/// sources are searched in the order they were added, the usual order being
+/// the beatmap folder followed by the user skin. `None` means the element
+/// should come from the built-in default skin
+#[derive(Debug, Default, Clone)]
+pub struct ElementLookup {
+    sources: Vec<Source>,
+}
+
+impl ElementLookup {
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// adds a folder or .osk file to search after the existing ones
+    pub fn with_source(mut self, path: &Path) -> Self {
+        self.sources.push(Source::new(path));
+        self
+    }
+
+    /// finds a file by its exact name
+    pub fn file(&self, name: &str) -> Option<PathBuf> {
+        self.sources.iter().find_map(|source| source.get(name))
+    }
+
+    /// finds an image, preferring the @2x variant within the same source
+    pub fn texture(&self, name: &str) -> Option<Element> {
+        self.sources.iter().find_map(|source| texture_in(source, name))
+    }
+
+    /// finds the frames of an animated element like `sliderb` or `hit300`.
+    ///
+    /// frames are `{name}{separator}0`, `{name}{separator}1`, ... and all have to
+    /// come from the same source. falls back to the single `{name}` image
+    pub fn animation(&self, name: &str, separator: &str) -> Vec<Element> {
+        for source in &self.sources {
+            let frames: Vec<Element> = (0..)
+                .map_while(|i| texture_in(source, &format!("{}{}{}", name, separator, i)))
+                .collect();
+
+            if !frames.is_empty() {
+                return frames;
+            }
+
+            if let Some(single) = texture_in(source, name) {
+                return vec![single];
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// finds a sample like `soft-hitclap2`, with any of the supported extensions
+    pub fn sample(&self, name: &str) -> Option<PathBuf> {
+        self.sources.iter().find_map(|source| {
+            SAMPLE_EXTENSIONS.iter()
+                .find_map(|ext| source.get(&format!("{}.{}", name, ext)))
+        })
+    }
+
+    /// reads a path returned by this lookup, extracting it if it's inside an .osk
+    pub fn read(&self, path: &Path) -> Option<Vec<u8>> {
+        self.sources.iter()
+            .filter(|source| path.starts_with(&source.root))
+            .find_map(|source| source.read(path))
+    }
+
+    /// whether a path points inside one of the .osk sources
+    pub fn is_archived(&self, path: &Path) -> bool {
+        self.sources.iter()
+            .any(|source| source.is_archive() && path.starts_with(&source.root))
+    }
+}
+
+/// the single top level folder every entry is in, with its trailing slash
+fn common_folder(names: &[&str]) -> Option<String> {
+    let (folder, _) = names.first()?.split_once('/')?;
+    let prefix = format!("{}/", folder);
+    names.iter().all(|name| name.starts_with(&prefix)).then_some(prefix)
+}
+
+fn texture_in(source: &Source, name: &str) -> Option<Element> {
+    for ext in IMAGE_EXTENSIONS {
+        if let Some(path) = source.get(&format!("{}@2x.{}", name, ext)) {
+            return Some(Element { path, high_res: true });
+        }
+        if let Some(path) = source.get(&format!("{}.{}", name, ext)) {
+            return Some(Element { path, high_res: false });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{ZipWriter, write::FileOptions};
+
+    use super::*;
+
+    fn write_osk(name: &str, entries: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rosu_parser_{}_{}.osk", name, std::process::id()));
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        for entry in entries {
+            zip.start_file(*entry, FileOptions::default()).unwrap();
+            zip.write_all(entry.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn osk_with_a_top_level_folder() {
+        let path = write_osk("folder", &["My Skin/skin.ini", "My Skin/hitcircle@2x.png"]);
+        let lookup = ElementLookup::new().with_source(&path);
+
+        let element = lookup.texture("hitcircle").unwrap();
+        assert!(element.high_res);
+        assert_eq!(lookup.read(&element.path).unwrap(), b"My Skin/hitcircle@2x.png");
+        assert!(lookup.file("skin.ini").is_some());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn osk_without_a_common_folder() {
+        let path = write_osk("flat", &["skin.ini", "sounds/soft-hitclap.wav"]);
+        let lookup = ElementLookup::new().with_source(&path);
+
+        assert!(lookup.file("skin.ini").is_some());
+        assert!(lookup.file("soft-hitclap.wav").is_none());
+        assert_eq!(lookup.read(&lookup.file("sounds/soft-hitclap.wav").unwrap()).unwrap(), b"sounds/soft-hitclap.wav");
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use crate::colour::Rgb;
+
+#[derive(Debug, Default)]
+pub struct General {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub version: Option<String>, //"latest" or a number like "2.7"
+    pub animation_framerate: Option<isize>,
+    pub allow_slider_ball_tint: Option<bool>,
+    pub combo_burst_random: Option<bool>,
+    pub cursor_centre: Option<bool>,
+    pub cursor_expand: Option<bool>,
+    pub cursor_rotate: Option<bool>,
+    pub cursor_trail_rotate: Option<bool>,
+    pub custom_combo_burst_sounds: Option<String>, //comma separated list of ints
+    pub hit_circle_overlay_above_number: Option<bool>,
+    pub layered_hit_sounds: Option<bool>,
+    pub slider_ball_flip: Option<bool>,
+    pub spinner_fade_playfield: Option<bool>,
+    pub spinner_frequency_modulate: Option<bool>,
+    pub spinner_no_blink: Option<bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct Colours {
+    pub combo: [Option<Rgb>; 8], //Combo1 to Combo8
+    pub input_overlay_text: Option<Rgb>,
+    pub menu_glow: Option<Rgb>,
+    pub slider_ball: Option<Rgb>,
+    pub slider_border: Option<Rgb>,
+    pub slider_track_override: Option<Rgb>,
+    pub song_select_active_text: Option<Rgb>,
+    pub song_select_inactive_text: Option<Rgb>,
+    pub spinner_background: Option<Rgb>,
+    pub star_break_additive: Option<Rgb>,
+}
+
+impl Colours {
+
+    /// the defined combo colours in order, skipping gaps
+    pub fn combo_colours(&self) -> Vec<Rgb> {
+        self.combo.iter().flatten().copied().collect()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Fonts {
+    pub hit_circle_prefix: Option<String>,
+    pub hit_circle_overlap: Option<isize>,
+    pub score_prefix: Option<String>,
+    pub score_overlap: Option<isize>,
+    pub combo_prefix: Option<String>,
+    pub combo_overlap: Option<isize>,
+}
+
+#[derive(Debug, Default)]
+pub struct CatchTheBeat {
+    pub hyper_dash: Option<Rgb>,
+    pub hyper_dash_fruit: Option<Rgb>,
+    pub hyper_dash_after_image: Option<Rgb>,
+}
+
+/// one [Mania] section, skins have one per key count
+#[derive(Debug, Default)]
+pub struct Mania {
+    pub keys: Option<usize>,
+    pub column_start: Option<f32>,
+    pub column_right: Option<f32>,
+    pub column_spacing: Option<String>, //comma separated list of floats
+    pub column_width: Option<String>, //comma separated list of floats
+    pub column_line_width: Option<String>, //comma separated list of floats
+    pub barline_height: Option<f32>,
+    pub hit_position: Option<f32>,
+    pub light_position: Option<f32>,
+    pub score_position: Option<f32>,
+    pub combo_position: Option<f32>,
+    pub judgement_line: Option<bool>,
+    pub special_style: Option<usize>,
+    pub upside_down: Option<bool>,
+    pub colour_column_line: Option<Rgb>,
+    pub colour_judgement_line: Option<Rgb>,
+    pub colour_barline: Option<Rgb>,
+    pub column_colours: Vec<(usize, Rgb)>, //Colour1, Colour2, ...
+    pub images: HashMap<String, String>, //KeyImage0, NoteImage0L, StageLeft, ...
+}
@@ -0,0 +1,253 @@
+use std::cmp::Ordering;
+
+const QUICK_SORT_DEPTH_THRESHOLD: usize = 32;
+const INTRO_SORT_SIZE_THRESHOLD: usize = 16;
+
+/// the unstable sort of the .NET framework osu!stable ran on, ported from lazer's LegacySortHelper.
+/// notes at the same time end up in its order, which changes the strain of chords
+pub fn legacy_sort<T: Clone>(keys: &mut [T], compare: impl Fn(&T, &T) -> Ordering) {
+    if keys.is_empty() {
+        return;
+    }
+
+    depth_limited_quick_sort(keys, 0, keys.len() as isize - 1, &compare, QUICK_SORT_DEPTH_THRESHOLD);
+}
+
+/// the unstable sort of the .NET lazer runs on, List.Sort's introsort.
+/// objects at the same time end up in its order, catch marks hyper dashes in it
+pub fn list_sort<T: Clone>(keys: &mut [T], compare: impl Fn(&T, &T) -> Ordering) {
+    if keys.len() < 2 {
+        return;
+    }
+
+    let depth_limit = 2 * (keys.len().ilog2() as usize + 1);
+    intro_sort(keys, 0, keys.len() - 1, &compare, depth_limit);
+}
+
+fn intro_sort<T: Clone>(
+    keys: &mut [T],
+    lo: usize,
+    mut hi: usize,
+    compare: &impl Fn(&T, &T) -> Ordering,
+    mut depth_limit: usize,
+) {
+    while hi > lo {
+        let partition_size = hi - lo + 1;
+
+        if partition_size <= INTRO_SORT_SIZE_THRESHOLD {
+            match partition_size {
+                2 => swap_if_greater(keys, compare, lo, hi),
+                3 => {
+                    swap_if_greater(keys, compare, lo, hi - 1);
+                    swap_if_greater(keys, compare, lo, hi);
+                    swap_if_greater(keys, compare, hi - 1, hi);
+                }
+                _ => insertion_sort(keys, lo, hi, compare),
+            }
+            return;
+        }
+
+        if depth_limit == 0 {
+            heap_sort(keys, lo, hi, compare);
+            return;
+        }
+        depth_limit -= 1;
+
+        //the upper part is sorted by recursing, the lower one by the next loop
+        let p = pick_pivot_and_partition(keys, lo, hi, compare);
+        intro_sort(keys, p + 1, hi, compare, depth_limit);
+        hi = p - 1;
+    }
+}
+
+fn pick_pivot_and_partition<T: Clone>(
+    keys: &mut [T],
+    lo: usize,
+    hi: usize,
+    compare: &impl Fn(&T, &T) -> Ordering,
+) -> usize {
+    //the low, middle and high values are sorted first, the middle one is the pivot and parked before the end
+    let middle = lo + ((hi - lo) >> 1);
+    swap_if_greater(keys, compare, lo, middle);
+    swap_if_greater(keys, compare, lo, hi);
+    swap_if_greater(keys, compare, middle, hi);
+
+    let pivot = keys[middle].clone();
+    keys.swap(middle, hi - 1);
+
+    let mut left = lo;
+    let mut right = hi - 1;
+
+    while left < right {
+        left += 1;
+        while compare(&keys[left], &pivot) == Ordering::Less {
+            left += 1;
+        }
+        right -= 1;
+        while compare(&pivot, &keys[right]) == Ordering::Less {
+            right -= 1;
+        }
+
+        if left >= right {
+            break;
+        }
+        keys.swap(left, right);
+    }
+
+    if left != hi - 1 {
+        keys.swap(left, hi - 1);
+    }
+    left
+}
+
+fn insertion_sort<T: Clone>(keys: &mut [T], lo: usize, hi: usize, compare: &impl Fn(&T, &T) -> Ordering) {
+    for i in lo..hi {
+        let t = keys[i + 1].clone();
+
+        let mut j = i + 1;
+        while j > lo && compare(&t, &keys[j - 1]) == Ordering::Less {
+            keys[j] = keys[j - 1].clone();
+            j -= 1;
+        }
+        keys[j] = t;
+    }
+}
+
+//indices are signed like the original's, j goes one below left when a partition ends
+fn depth_limited_quick_sort<T: Clone>(
+    keys: &mut [T],
+    mut left: isize,
+    mut right: isize,
+    compare: &impl Fn(&T, &T) -> Ordering,
+    mut depth_limit: usize,
+) {
+    loop {
+        if depth_limit == 0 {
+            heap_sort(keys, left as usize, right as usize, compare);
+            return;
+        }
+
+        let mut i = left;
+        let mut j = right;
+
+        //the low, middle and high values are sorted first, the middle one is the pivot
+        let middle = i + ((j - i) >> 1);
+        swap_if_greater(keys, compare, i as usize, middle as usize);
+        swap_if_greater(keys, compare, i as usize, j as usize);
+        swap_if_greater(keys, compare, middle as usize, j as usize);
+
+        let pivot = keys[middle as usize].clone();
+
+        loop {
+            while compare(&keys[i as usize], &pivot) == Ordering::Less {
+                i += 1;
+            }
+            while compare(&pivot, &keys[j as usize]) == Ordering::Less {
+                j -= 1;
+            }
+
+            if i > j {
+                break;
+            }
+            if i < j {
+                keys.swap(i as usize, j as usize);
+            }
+
+            i += 1;
+            j -= 1;
+
+            if i > j {
+                break;
+            }
+        }
+
+        depth_limit -= 1;
+
+        //the smaller half is sorted by recursing, the larger one by the next loop
+        if j - left <= right - i {
+            if left < j {
+                depth_limited_quick_sort(keys, left, j, compare, depth_limit);
+            }
+            left = i;
+        } else {
+            if i < right {
+                depth_limited_quick_sort(keys, i, right, compare, depth_limit);
+            }
+            right = j;
+        }
+
+        if left >= right {
+            break;
+        }
+    }
+}
+
+fn heap_sort<T: Clone>(keys: &mut [T], lo: usize, hi: usize, compare: &impl Fn(&T, &T) -> Ordering) {
+    let n = hi - lo + 1;
+
+    for i in (1..=n / 2).rev() {
+        down_heap(keys, i, n, lo, compare);
+    }
+
+    for i in (2..=n).rev() {
+        keys.swap(lo, lo + i - 1);
+        down_heap(keys, 1, i - 1, lo, compare);
+    }
+}
+
+fn down_heap<T: Clone>(keys: &mut [T], mut i: usize, n: usize, lo: usize, compare: &impl Fn(&T, &T) -> Ordering) {
+    let d = keys[lo + i - 1].clone();
+
+    while i <= n / 2 {
+        let mut child = 2 * i;
+        if child < n && compare(&keys[lo + child - 1], &keys[lo + child]) == Ordering::Less {
+            child += 1;
+        }
+
+        if compare(&d, &keys[lo + child - 1]) != Ordering::Less {
+            break;
+        }
+
+        keys[lo + i - 1] = keys[lo + child - 1].clone();
+        i = child;
+    }
+
+    keys[lo + i - 1] = d;
+}
+
+fn swap_if_greater<T>(keys: &mut [T], compare: &impl Fn(&T, &T) -> Ordering, a: usize, b: usize) {
+    if a != b && compare(&keys[a], &keys[b]) == Ordering::Greater {
+        keys.swap(a, b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_sorts_order_their_keys() {
+        //lengths around the insertion sort threshold, and one long enough to go through the partitions
+        for len in [0, 1, 2, 3, 16, 17, 100, 1000] {
+            let keys: Vec<i32> = (0..len).map(|i| (i * 7919 % 211) - 100).collect();
+            let mut expected = keys.clone();
+            expected.sort();
+
+            let mut list = keys.clone();
+            list_sort(&mut list, i32::cmp);
+            assert_eq!(list, expected, "list sort of {}", len);
+
+            let mut legacy = keys;
+            legacy_sort(&mut legacy, i32::cmp);
+            assert_eq!(legacy, expected, "legacy sort of {}", len);
+        }
+    }
+
+    #[test]
+    fn heap_sort_past_the_depth_limit() {
+        let mut keys: Vec<i32> = (0..50).rev().collect();
+        let len = keys.len();
+        intro_sort(&mut keys, 0, len - 1, &i32::cmp, 0);
+        assert_eq!(keys, (0..50).collect::<Vec<_>>());
+    }
+}
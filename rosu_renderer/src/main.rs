@@ -1,6 +1,7 @@
 
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use argh::FromArgs;
+use rosu_parser::{beatmap::sections::GameMode, mods::Mods, replay::Replay};
 
 mod player;
 use player::Player;
@@ -13,10 +14,38 @@ struct PlayBeatmap {
     #[argh(positional)]
     beatmap_path: Option<String>,
 
+    /// path to a skin folder or .osk file
+    #[argh(option)]
+    skin: Option<String>,
+
+    /// mods to play with, e.g. HDHR
+    #[argh(option)]
+    mods: Option<Mods>,
+
+    /// mode to play an osu!standard map in, e.g. mania
+    #[argh(option)]
+    mode: Option<GameMode>,
+
+    /// mania scroll speed from 1 to 40
+    #[argh(option)]
+    scroll_speed: Option<f64>,
+
+    /// background dim from 0 to 100, 80 by default
+    #[argh(option)]
+    background_dim: Option<f32>,
+
+    /// keep the song's pitch when the playback rate is changed
+    #[argh(switch)]
+    preserve_pitch: bool,
+
+    /// path to an .osr file to show the pp of, its mods are used unless --mods is given
+    #[argh(option)]
+    replay: Option<String>,
+
 }
 
-fn path_from_args(args: PlayBeatmap) -> Option<PathBuf> {
-    if let Some(beatmap_path) = args.beatmap_path {
+fn path_from_args(args: &PlayBeatmap) -> Option<PathBuf> {
+    if let Some(beatmap_path) = &args.beatmap_path {
         return Some(PathBuf::from(beatmap_path));
     }
     None
@@ -24,8 +53,32 @@ fn path_from_args(args: PlayBeatmap) -> Option<PathBuf> {
 
 fn main() {
 
-    let beatmap_path = path_from_args(argh::from_env());
+    let args: PlayBeatmap = argh::from_env();
+    let beatmap_path = path_from_args(&args);
+
+    let replay = match args.replay.as_ref().map(|path| Replay::load_from_file(&PathBuf::from(path))).transpose() {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("couldn't load the replay: {:?}", err);
+            std::process::exit(1);
+        }
+    };
+    let mods = args.mods
+        .or_else(|| replay.as_ref().map(|replay| replay.mods))
+        .unwrap_or_default();
 
-    Player::new(beatmap_path.unwrap()).run();
+    let mut player = Player::new(beatmap_path.unwrap())
+        .skin(args.skin.map(PathBuf::from))
+        .mods(mods)
+        .mode(args.mode)
+        .preserve_pitch(args.preserve_pitch)
+        .replay(replay);
+    if let Some(scroll_speed) = args.scroll_speed {
+        player = player.scroll_speed(scroll_speed);
+    }
+    if let Some(background_dim) = args.background_dim {
+        player = player.background_dim(background_dim);
+    }
+    player.run();
 
 }
\ No newline at end of file
@@ -1,17 +1,49 @@
 
 use std::{
+    fs,
     path::PathBuf
 };
 
 use bevy_kira_audio::*;
 use bevy::prelude::*;
-use rosu_parser::beatmap::*;
+use rosu_parser::{beatmap::{*, sections::GameMode}, mods::Mods, replay::Replay};
 
-use self::hit_object::hit_object_system;
+use self::background::BackgroundSettings;
+use self::clock::PlaybackClock;
+use self::skin::{SkinInfo, SkinAssetIo};
+use self::stats::ReplayInfo;
+use self::transport::{Transport, TransportAction, Seeked};
+pub mod background;
+pub mod breaks;
+pub mod catch;
+pub mod clock;
+pub mod countdown;
+pub mod follow_point;
 pub mod hit_object;
+pub mod hitsound;
+pub mod mania;
+pub mod playfield;
+pub mod skin;
+pub mod slider;
+pub mod spinner;
+pub mod stats;
+pub mod stretch;
+pub mod taiko;
+pub mod timeline;
+pub mod transport;
+
+/// the built-in font of all the player's text
+pub(crate) const FONT: &str = "fonts/FiraSans-Bold.ttf";
 
 pub struct Player {
-    beatmap_path: PathBuf
+    beatmap_path: PathBuf,
+    skin_path: Option<PathBuf>,
+    mods: Mods,
+    mode: Option<GameMode>,
+    scroll_speed: f64,
+    background_dim: f32,
+    preserve_pitch: bool,
+    replay: Option<Replay>,
 }
 
 impl Player {
@@ -19,26 +51,143 @@ impl Player {
     /// make a new player with default path
     pub fn new(beatmap_path: PathBuf) -> Self {
         Self {
-            beatmap_path
+            beatmap_path,
+            skin_path: None,
+            mods: Mods::empty(),
+            mode: None,
+            scroll_speed: mania::DEFAULT_SCROLL_SPEED,
+            background_dim: background::DEFAULT_BACKGROUND_DIM,
+            preserve_pitch: false,
+            replay: None,
         }
     }
 
-    /// runs the player
+    /// skin folder or .osk file to draw with, the built-in skin is used without one
+    pub fn skin(mut self, skin_path: Option<PathBuf>) -> Self {
+        self.skin_path = skin_path;
+        self
+    }
+
+    /// mods change the difficulty, HR flips the map and DT/HT change the speed
+    pub fn mods(mut self, mods: Mods) -> Self {
+        self.mods = mods;
+        self
+    }
+
+    /// plays an osu!standard map converted to another mode, other maps stay in their own
+    pub fn mode(mut self, mode: Option<GameMode>) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// mania scroll speed from 1 to 40, like the game's setting
+    pub fn scroll_speed(mut self, scroll_speed: f64) -> Self {
+        self.scroll_speed = scroll_speed;
+        self
+    }
+
+    /// background dim from 0 to 100 like the game's setting, breaks undim it
+    pub fn background_dim(mut self, background_dim: f32) -> Self {
+        self.background_dim = background_dim;
+        self
+    }
+
+    /// keeps the song's pitch when the playback rate is changed, the mods' rate still changes it
+    pub fn preserve_pitch(mut self, preserve_pitch: bool) -> Self {
+        self.preserve_pitch = preserve_pitch;
+        self
+    }
+
+    /// replay to show the pp of, the player's mods are used for the calculation
+    pub fn replay(mut self, replay: Option<Replay>) -> Self {
+        self.replay = replay;
+        self
+    }
+
+    /// runs the player, the map's mode decides how it's drawn
     pub fn run(&mut self) {
         
         let path = self.beatmap_path.clone();
+        let lookup = SkinInfo::lookup(&path, self.skin_path.as_deref());
+        let beatmap = BeatmapInfo::new(path.clone(), self.mods, self.mode);
+        let mode = beatmap.data.mode();
 
-        bevy::prelude::App::new()
-            .add_plugins(DefaultPlugins)
+        let mut app = bevy::prelude::App::new();
+        app
+            //replaces the default asset server so .osk contents can be loaded without extracting
+            .insert_resource(AssetServer::new(SkinAssetIo::new(lookup)))
+            .add_plugins(DefaultPlugins);
+
+        //once the log is set up, it reports a skin or replay it leaves out
+        let skin = SkinInfo::new(&path, self.skin_path.as_deref());
+        let replay = ReplayInfo::new(self.replay.take(), &beatmap);
+        app
             .add_plugin(AudioPlugin)
-            .insert_resource(BeatmapInfo {
-                data: Beatmap::load_from_file(&path).unwrap(),
-                path,
-            })
+            .add_audio_channel::<hitsound::HitSoundChannel>()
+            .insert_resource(Transport::new(&beatmap, self.preserve_pitch))
+            .init_resource::<stretch::StretchedSong>()
+            .insert_resource(PlaybackClock::new(beatmap.attributes.clock_rate, &beatmap.data))
+            .insert_resource(beatmap)
+            .insert_resource(skin)
+            .insert_resource(replay)
+            .insert_resource(BackgroundSettings { dim: self.background_dim })
             .add_startup_system(setup)
-            .add_startup_system(hit_object::spawn_objects.after(setup))
-            .add_system(hit_object::hit_object_system)
-            .run();
+            .add_startup_system(background::spawn_letterbox)
+            .add_startup_system(countdown::spawn_countdown)
+            .add_startup_system_to_stage(StartupStage::PostStartup, breaks::spawn_break_overlay)
+            .add_startup_system(skin::load_skin_textures)
+            .add_startup_system(hitsound::queue_hit_sounds)
+            .add_startup_system(stats::spawn_stats_text)
+            .add_startup_system(transport::spawn_controls)
+            .add_startup_system(timeline::spawn_timeline)
+            .add_event::<TransportAction>()
+            .add_event::<Seeked>()
+            .add_system(transport::keyboard_controls)
+            .add_system(transport::button_controls)
+            .add_system(transport::update_controls_text)
+            .add_system(timeline::spawn_timeline_markers)
+            .add_system(timeline::timeline_system)
+            .add_system(background::background_system)
+            .add_system(breaks::break_overlay_system)
+            .add_system(countdown::countdown_system)
+            //seeks and pauses are applied before the clock moves, and it moves before anything reads it
+            .add_system_to_stage(CoreStage::PreUpdate, transport::transport_system)
+            .add_system_to_stage(CoreStage::PreUpdate, stretch::stretch_system.after(transport::transport_system))
+            .add_system_to_stage(CoreStage::PreUpdate, clock::clock_system.after(stretch::stretch_system))
+            .add_system(hitsound::hit_sound_system);
+
+        match mode {
+            GameMode::Mania => app
+                .insert_resource(mania::ManiaSettings { scroll_speed: self.scroll_speed })
+                .add_startup_system(playfield::spawn_screen)
+                .add_startup_system_to_stage(StartupStage::PostStartup, mania::spawn_stage)
+                .add_system(playfield::resize_screen)
+                .add_system(mania::mania_system),
+            GameMode::Taiko => app
+                .add_startup_system(playfield::spawn_screen)
+                .add_startup_system_to_stage(StartupStage::PostStartup, taiko::spawn_lane)
+                .add_system(playfield::resize_screen)
+                .add_system(taiko::taiko_system)
+                .add_system(taiko::kiai_system),
+            GameMode::Catch => app
+                .add_startup_system(playfield::spawn_screen)
+                .add_startup_system_to_stage(StartupStage::PostStartup, catch::spawn_fruits)
+                .add_system(playfield::resize_screen)
+                .add_system(catch::catch_system),
+            _ => app
+                .add_startup_system(playfield::spawn_playfield)
+                .add_startup_system_to_stage(StartupStage::PostStartup, hit_object::spawn_objects)
+                .add_startup_system_to_stage(StartupStage::PostStartup, follow_point::spawn_follow_points)
+                .add_system(playfield::resize_playfield)
+                .add_system(hit_object::hit_object_system)
+                .add_system(hit_object::combo_number_layout_system)
+                .add_system(slider::slider_body_system)
+                .add_system(slider::slider_parts_system)
+                .add_system(spinner::spinner_system)
+                .add_system(follow_point::follow_point_system),
+        };
+
+        app.run();
     }
 
 }
@@ -46,11 +195,47 @@ impl Player {
 #[derive(Resource)]
 pub struct BeatmapInfo {
     path: PathBuf,
-    data: Beatmap
+    data: Beatmap,
+    original: Beatmap, //as it's stored, for the difficulty calculators
+    hash: String, //md5 of the .osu file, like replays have
+    attributes: attributes::DifficultyAttributes,
+    mods: Mods,
 }
 
+impl BeatmapInfo {
+    fn new(path: PathBuf, mods: Mods, mode: Option<GameMode>) -> Self {
+        let contents = fs::read_to_string(&path).unwrap();
+        let hash = format!("{:x}", md5::compute(&contents));
+        let original = Beatmap::load_from_string(contents).unwrap();
+
+        let mut data = original.clone();
+        //the game converts first and applies mods to the convert
+        if let Some(converted) = mode.and_then(|mode| data.convert(mode, mods)) {
+            data = converted;
+        }
+        data.apply_mods(mods);
+
+        Self {
+            //HR/EZ are already in the map, only the rate is left
+            attributes: attributes::DifficultyAttributes {
+                clock_rate: mods.clock_rate(),
+                ..data.difficulty_attributes()
+            },
+            data,
+            original,
+            hash,
+            path,
+            mods,
+        }
+    }
+}
+
+/// the song playing, `stretch` is the rate its audio was time-stretched to, 1 for the original
 #[derive(Resource)]
-pub struct SongHandle(Handle<AudioInstance>);
+pub struct SongHandle {
+    instance: Handle<AudioInstance>,
+    stretch: f64,
+}
 
 
 fn setup(
@@ -59,28 +244,39 @@ fn setup(
     audio: Res<Audio>,
     beatmap: Res<BeatmapInfo>,
     ){
-    //background
-    let events = beatmap.data.events.as_ref().unwrap();
-    let background_path = &events.backgrounds[0].filename;
+    //background, maps without one are played on black
+    let background_path = beatmap.data.events.as_ref()
+        .and_then(|events| events.backgrounds.first())
+        .map(|background| &background.filename);
 
-    let background_absolute_path = format!("{}/{}", 
-        beatmap.path.parent().unwrap().to_str().unwrap(), 
-        background_path.to_str().unwrap()
-    );
+    if let Some(background_path) = background_path {
+        let background_absolute_path = format!("{}/{}", 
+            beatmap.path.parent().unwrap().to_str().unwrap(), 
+            background_path.to_str().unwrap()
+        );
 
-    
-    let background = asset_server.load(
-        background_absolute_path.as_str());
+        let background = asset_server.load(
+            background_absolute_path.as_str());
 
-    commands.spawn(SpriteBundle{
-        texture: background,
-        transform: Transform::from_xyz(0.0, 0.0, 0.0),
-        ..default()
-    });
+        commands.spawn((
+            background::Background,
+            SpriteBundle{
+                texture: background,
+                transform: Transform::from_xyz(0.0, 0.0, 0.0),
+                ..default()
+            },
+        ));
+    }
 
     //text
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
     commands.spawn(Camera2dBundle::default());
+
+    //maps without a song still play, silently
+    let audio_path = beatmap.data.general.as_ref()
+        .and_then(|general| general.audio_filename.as_ref());
+
+    /*
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
     let text_style = TextStyle {
         font,
         font_size: 60.0,
@@ -88,10 +284,7 @@ fn setup(
         
     };
     let text_alignment = TextAlignment::CENTER;
-    
 
-    let general = beatmap.data.general.as_ref().unwrap();
-    let audio_path = general.audio_filename.as_ref().unwrap();
     let metadata = beatmap.data.metadata.as_ref().unwrap();
     let title = metadata.title.as_ref().unwrap();
     let version = beatmap.data.version.as_ref().unwrap();
@@ -99,7 +292,6 @@ fn setup(
     let text = format!("(.osu v{})\n\nnow playing:\n{}\n{}", 
         version, title, audio_path.to_str().unwrap()); 
 
-    /*
     commands.spawn(
         Text2dBundle {
             text: Text::from_section(text, text_style)
@@ -110,16 +302,19 @@ fn setup(
     );*/
 
     //audio
-    let audio_absolute_path = format!("{}/{}", 
-        beatmap.path.parent().unwrap().to_str().unwrap(), 
-        audio_path.to_str().unwrap()
-    );
-
-    let music = asset_server.load(
-        audio_absolute_path.as_str());
-    let song_handle = audio.play(music)
-        .looped()
-        .handle();
-
-    commands.insert_resource(SongHandle(song_handle));
+    if let Some(audio_path) = audio_path {
+        let audio_absolute_path = format!("{}/{}", 
+            beatmap.path.parent().unwrap().to_str().unwrap(), 
+            audio_path.to_str().unwrap()
+        );
+
+        let music = asset_server.load(
+            audio_absolute_path.as_str());
+        let song_handle = audio.play(music.clone())
+            .with_playback_rate(beatmap.attributes.clock_rate)
+            .handle();
+
+        commands.insert_resource(SongHandle { instance: song_handle, stretch: 1.0 });
+        commands.insert_resource(transport::SongSource(music));
+    }
 }
\ No newline at end of file
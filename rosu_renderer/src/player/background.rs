@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use super::{BeatmapInfo, breaks::break_fade, clock::PlaybackClock};
+
+pub const DEFAULT_BACKGROUND_DIM: f32 = 80.0;
+
+/// the bars are this much of the window's height
+const LETTERBOX_HEIGHT: f32 = 0.125;
+/// above everything but the interface
+const LETTERBOX_Z: f32 = 700.0;
+
+/// how dark the background is, 0 to 100 like the game's setting. breaks show it undimmed
+#[derive(Resource)]
+pub struct BackgroundSettings {
+    pub dim: f32,
+}
+
+/// the map's background image
+#[derive(Component)]
+pub struct Background;
+
+/// a black bar at the top or bottom of the window that slides in during breaks
+#[derive(Component)]
+pub struct Letterbox {
+    side: f32, //1 at the top, -1 at the bottom
+}
+
+/// only for maps that ask for letterboxing in breaks
+pub fn spawn_letterbox(
+    mut commands: Commands,
+    beatmap: Res<BeatmapInfo>) {
+
+    let letterbox = beatmap.data.general.as_ref()
+        .and_then(|general| general.letter_box_in_breaks)
+        .unwrap_or(false);
+    if !letterbox {
+        return;
+    }
+
+    for side in [1.0, -1.0] {
+        commands.spawn((
+            Letterbox { side },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::BLACK,
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, LETTERBOX_Z),
+                //hidden until the window's size is known
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+        ));
+    }
+}
+
+pub fn background_system(
+    clock: Res<PlaybackClock>,
+    beatmap: Res<BeatmapInfo>,
+    settings: Res<BackgroundSettings>,
+    windows: Res<Windows>,
+    mut backgrounds: Query<&mut Sprite, With<Background>>,
+    mut letterboxes: Query<(&Letterbox, &mut Sprite, &mut Transform, &mut Visibility), Without<Background>>) {
+
+    let fade = break_fade(&beatmap, clock.time()) as f32;
+
+    let dim = settings.dim.clamp(0.0, 100.0) / 100.0 * (1.0 - fade);
+    for mut sprite in &mut backgrounds {
+        sprite.color = Color::rgb(1.0 - dim, 1.0 - dim, 1.0 - dim);
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let (width, height) = (window.width(), window.height());
+    let bar_height = height * LETTERBOX_HEIGHT;
+    for (letterbox, mut sprite, mut transform, mut visibility) in &mut letterboxes {
+        //slides in from outside the window
+        let y = height / 2.0 - bar_height / 2.0 + bar_height * (1.0 - fade);
+        sprite.custom_size = Some(Vec2::new(width, bar_height));
+        transform.translation.y = letterbox.side * y;
+        visibility.is_visible = fade > 0.0;
+    }
+}
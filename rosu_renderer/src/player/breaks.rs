@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use rosu_parser::replay::Replay;
+
+use super::{
+    BeatmapInfo,
+    FONT,
+    clock::PlaybackClock,
+    playfield::screen_scale,
+    skin::{SkinTexture, SkinTextures},
+    stats::ReplayInfo,
+};
+
+const FONT_SIZE: f32 = 32.0;
+
+/// breaks fade in after they start and out before they end over this long, in ms
+const BREAK_FADE: f64 = 500.0;
+
+/// warning arrows blink for this long before a break ends, switching every `ARROW_BLINK` ms
+const WARNING_TIME: f64 = 1000.0;
+const ARROW_BLINK: f64 = 125.0;
+
+/// pass or fail shows up this long before a break ends, for `SECTION_DURATION` ms
+const SECTION_BEFORE_END: f64 = 2500.0;
+const SECTION_DURATION: f64 = 1000.0;
+/// health the replay needs at that point to pass
+const PASS_HEALTH: f64 = 0.5;
+
+/// the overlay is laid out on a 640x480 screen, like the game's
+const BAR_WIDTH: f32 = 300.0;
+const BAR_HEIGHT: f32 = 4.0;
+const ARROW_X: f32 = 240.0;
+const Z: f32 = 800.0;
+
+/// scaled to the window every frame, the parts are its children
+#[derive(Component)]
+pub struct BreakOverlay;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum BreakPart {
+    RemainingTime,
+    RemainingBar, //shrinks until the break ends
+    WarningArrow,
+    Section { pass: bool },
+}
+
+/// the break `time` is in, as its start and end
+fn break_at(beatmap: &BeatmapInfo, time: f64) -> Option<(f64, f64)> {
+    beatmap.data.events.iter()
+        .flat_map(|events| &events.breaks)
+        .map(|b| (b.start_time as f64, b.end() as f64))
+        .find(|(start, end)| (*start..*end).contains(&time))
+}
+
+/// how far into a break `time` is, fading from 0 outside of breaks to 1 and back before it ends
+pub fn break_fade(beatmap: &BeatmapInfo, time: f64) -> f64 {
+    break_at(beatmap, time).map_or(0.0, |(start, end)| {
+        ((time - start) / BREAK_FADE).min((end - time) / BREAK_FADE).clamp(0.0, 1.0)
+    })
+}
+
+/// whether the replay's health is high enough at `time`, plays without one always pass
+fn passing(replay: Option<&Replay>, time: f64) -> bool {
+    let health = replay
+        .and_then(|replay| replay.life_bar.iter().rev().find(|(t, _)| *t <= time))
+        .map_or(1.0, |(_, health)| *health);
+    health >= PASS_HEALTH
+}
+
+pub fn spawn_break_overlay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    textures: Res<SkinTextures>) {
+
+    let sprite = |texture: &SkinTexture, x: f32, flip_x: bool| SpriteBundle {
+        texture: texture.handle.clone(),
+        sprite: Sprite {
+            color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            flip_x,
+            ..default()
+        },
+        transform: Transform::from_xyz(x, 0.0, 0.0)
+            .with_scale(Vec3::new(texture.scale, texture.scale, 1.0)),
+        ..default()
+    };
+
+    commands.spawn((
+        BreakOverlay,
+        SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, Z)),
+    )).with_children(|parent| {
+        parent.spawn((BreakPart::RemainingTime, Text2dBundle {
+            text: Text::from_section("", TextStyle {
+                font: asset_server.load(FONT),
+                font_size: FONT_SIZE,
+                color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            }).with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_xyz(0.0, FONT_SIZE, 0.0),
+            ..default()
+        }));
+        parent.spawn((BreakPart::RemainingBar, SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                custom_size: Some(Vec2::new(BAR_WIDTH, BAR_HEIGHT)),
+                ..default()
+            },
+            ..default()
+        }));
+
+        //both point inwards, the texture points right
+        parent.spawn((BreakPart::WarningArrow, sprite(&textures.warning_arrow, -ARROW_X, false)));
+        parent.spawn((BreakPart::WarningArrow, sprite(&textures.warning_arrow, ARROW_X, true)));
+
+        parent.spawn((BreakPart::Section { pass: true }, sprite(&textures.section_pass, 0.0, false)));
+        parent.spawn((BreakPart::Section { pass: false }, sprite(&textures.section_fail, 0.0, false)));
+    });
+}
+
+pub fn break_overlay_system(
+    clock: Res<PlaybackClock>,
+    beatmap: Res<BeatmapInfo>,
+    replay: Res<ReplayInfo>,
+    windows: Res<Windows>,
+    mut overlays: Query<&mut Transform, With<BreakOverlay>>,
+    mut sprites: Query<(&BreakPart, &mut Sprite, &mut Transform), Without<BreakOverlay>>,
+    mut texts: Query<(&BreakPart, &mut Text)>) {
+
+    let scale = windows.get_primary()
+        .map_or(1.0, |window| screen_scale(window.width(), window.height()));
+    for mut transform in &mut overlays {
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+
+    let pos = clock.time();
+    let fade = break_fade(&beatmap, pos) as f32;
+    let (start, end) = break_at(&beatmap, pos).unwrap_or((pos, pos));
+    let remaining = end - pos;
+
+    let replay = replay.0.as_ref();
+    //breaks too short to fit it don't get a pass or fail
+    let section_start = end - SECTION_BEFORE_END;
+    let section = section_start >= start + BREAK_FADE
+        && (section_start..section_start + SECTION_DURATION).contains(&pos);
+    let pass = passing(replay, pos);
+
+    for (part, mut sprite, mut transform) in &mut sprites {
+        let alpha = match *part {
+            BreakPart::RemainingBar => {
+                let progress = if end > start { (remaining / (end - start)) as f32 } else { 0.0 };
+                transform.scale.x = progress;
+                fade
+            }
+            BreakPart::WarningArrow => {
+                let blink = ((remaining / ARROW_BLINK) as usize).is_multiple_of(2);
+                if remaining > 0.0 && remaining < WARNING_TIME && blink { 1.0 } else { 0.0 }
+            }
+            BreakPart::Section { pass: section_pass } => {
+                if section && pass == section_pass { 1.0 } else { 0.0 }
+            }
+            BreakPart::RemainingTime => fade,
+        };
+        sprite.color.set_a(alpha);
+    }
+
+    //the countdown is in real seconds, breaks are shorter with DT
+    let seconds = (remaining / 1000.0 / beatmap.attributes.clock_rate).ceil();
+    for (part, mut text) in &mut texts {
+        if *part == BreakPart::RemainingTime {
+            let section = &mut text.sections[0];
+            section.value = format!("{}", seconds);
+            section.style.color.set_a(fade);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use rosu_parser::{
+    beatmap::{catch::{catcher_width, CatchObject, CatchObjectKind, ALLOWED_CATCH_RANGE}, pos::PLAYFIELD_WIDTH},
+    mods::Mods,
+};
+
+use super::{BeatmapInfo, clock::PlaybackClock, playfield::Screen, skin::{SkinInfo, SkinTextures}};
+
+/// fruits are caught here, near the bottom of the 640x480 screen
+const CATCHER_Y: f32 = -240.0 + 64.0;
+/// and start falling from the top
+const FALL_START_Y: f32 = 240.0;
+const CATCHER_HEIGHT: f32 = 16.0;
+
+/// the autoplay walks at this speed in osu!pixels per ms, and dashes at twice it
+const BASE_WALK_SPEED: f64 = 0.5;
+
+const BANANA_COLOUR: Color = Color::rgb(1.0, 0.9, 0.2);
+const CATCHER_COLOUR: Color = Color::rgb(0.95, 0.95, 0.95);
+
+/// anything that falls, hidden once it's caught
+#[derive(Component)]
+pub struct Faller {
+    start_time: f64,
+}
+
+#[derive(Component)]
+pub struct Catcher;
+
+/// where the autoplay has the catcher at `time`
+#[derive(Clone, Copy)]
+struct CatcherFrame {
+    time: f64,
+    x: f32,
+    hyper_dash: bool, //the move into this frame is a hyperdash
+}
+
+/// the autoplay's movement and how long fruits take to fall
+#[derive(Resource)]
+pub struct CatchPath {
+    frames: Vec<CatcherFrame>,
+    fall_time: f64,
+    hyper_dash_colour: Color,
+}
+
+impl CatchPath {
+
+    /// catcher x and whether it's hyperdashing, frames are interpolated linearly
+    fn at(&self, time: f64) -> (f32, bool) {
+        let i = self.frames.partition_point(|f| f.time <= time);
+        match (i.checked_sub(1).map(|i| self.frames[i]), self.frames.get(i)) {
+            (Some(from), Some(to)) if to.time > from.time => {
+                let t = ((time - from.time) / (to.time - from.time)) as f32;
+                (from.x + (to.x - from.x) * t, to.hyper_dash)
+            }
+            (Some(from), _) => (from.x, false),
+            (None, Some(to)) => (to.x, false),
+            (None, None) => (PLAYFIELD_WIDTH / 2.0, false),
+        }
+    }
+}
+
+/// the osu!pixel x of the playfield on screen
+fn screen_x(x: f32) -> f32 {
+    x - PLAYFIELD_WIDTH / 2.0
+}
+
+pub fn spawn_fruits(
+    mut commands: Commands,
+    textures: Res<SkinTextures>,
+    skin: Res<SkinInfo>,
+    beatmap: Res<BeatmapInfo>,
+    screens: Query<Entity, With<Screen>>) {
+
+    let screen = match screens.get_single() {
+        Ok(screen) => screen,
+        Err(_) => return,
+    };
+
+    let circle_size = beatmap.attributes.circle_size;
+    let objects = beatmap.data.catch_objects(circle_size, beatmap.mods.contains(Mods::HARD_ROCK));
+
+    let combo_colours = skin.combo_colours(&beatmap.data);
+    let hyper_dash_fruit_colour = skin.hyper_dash_colour(true);
+    let fruit_size = 2.0 * beatmap.attributes.radius() as f32;
+
+    commands.entity(screen).with_children(|screen| {
+        //later objects are drawn below earlier ones
+        for (i, object) in objects.iter().enumerate() {
+            let combo_colour = combo_colours[object.combo_colour % combo_colours.len()];
+            let (size, colour) = match object.kind {
+                CatchObjectKind::Fruit => (fruit_size, combo_colour),
+                CatchObjectKind::Droplet => (fruit_size * 0.6, combo_colour),
+                CatchObjectKind::TinyDroplet => (fruit_size * 0.3, combo_colour),
+                CatchObjectKind::Banana => (fruit_size * 0.8, BANANA_COLOUR),
+            };
+            let z = 20.0 + (objects.len() - i) as f32 * 0.0001;
+
+            screen.spawn((
+                Faller { start_time: object.start_time },
+                SpriteBundle {
+                    texture: textures.hit_circle.handle.clone(),
+                    sprite: Sprite {
+                        color: colour,
+                        custom_size: Some(Vec2::splat(size)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(screen_x(object.x), FALL_START_Y, z),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                },
+            )).with_children(|parent| {
+                //hyperdash fruits get a glow behind them
+                if object.hyper_dash && object.kind == CatchObjectKind::Fruit {
+                    parent.spawn(SpriteBundle {
+                        texture: textures.hit_circle.handle.clone(),
+                        sprite: Sprite {
+                            color: hyper_dash_fruit_colour,
+                            custom_size: Some(Vec2::splat(size * 1.4)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(0.0, 0.0, -0.00005),
+                        ..default()
+                    });
+                }
+            });
+        }
+
+        let full_catcher_width = (catcher_width(circle_size) / ALLOWED_CATCH_RANGE) as f32;
+        screen.spawn((
+            Catcher,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: CATCHER_COLOUR,
+                    custom_size: Some(Vec2::new(full_catcher_width, CATCHER_HEIGHT)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, CATCHER_Y - (fruit_size + CATCHER_HEIGHT) / 2.0, 30.0),
+                ..default()
+            },
+        ));
+    });
+
+    commands.insert_resource(CatchPath {
+        frames: autoplay(&objects, circle_size),
+        fall_time: beatmap.attributes.preempt(),
+        hyper_dash_colour: skin.hyper_dash_colour(false),
+    });
+}
+
+/// catches everything the way the game's autoplay does, walking when there's time and dashing when there isn't
+fn autoplay(objects: &[CatchObject], circle_size: f64) -> Vec<CatcherFrame> {
+    //the autoplay only moves once an object is out of the middle of the catcher
+    let half_width = (catcher_width(circle_size) * 0.3 * 0.5) as f32;
+
+    let mut last = CatcherFrame { time: 0.0, x: PLAYFIELD_WIDTH / 2.0, hyper_dash: false };
+    let mut frames = vec![last];
+    let mut hyper_dashing = false;
+
+    for object in objects {
+        let time_available = object.start_time - last.time;
+        let distance = (object.x - last.x).abs() as f64;
+        let speed_required = distance / time_available;
+        let target = CatcherFrame { time: object.start_time, x: object.x, hyper_dash: hyper_dashing };
+
+        if (object.x - last.x).abs() < half_width {
+            //already under it
+            last.time = object.start_time;
+            hyper_dashing = object.hyper_dash;
+            continue;
+        }
+
+        if hyper_dashing || speed_required > BASE_WALK_SPEED * 2.0 {
+            //straight there, hyperdashes cover the whole gap
+            frames.push(CatcherFrame { hyper_dash: false, ..last });
+        } else if speed_required > BASE_WALK_SPEED {
+            //dash for part of the way, walk the rest. dashing covers twice the distance,
+            //so every ms of it saves one of walking
+            let time_at_walk_speed = distance / BASE_WALK_SPEED;
+            let time_at_dash_speed = time_at_walk_speed - time_available;
+            let progress = (time_at_dash_speed * BASE_WALK_SPEED * 2.0 / distance) as f32;
+
+            frames.push(CatcherFrame { time: last.time + 1.0, ..last });
+            frames.push(CatcherFrame {
+                time: last.time + time_at_dash_speed,
+                x: last.x + (object.x - last.x) * progress,
+                hyper_dash: false,
+            });
+        } else {
+            //walk, leaving as late as possible
+            let time_walking = distance / BASE_WALK_SPEED;
+            frames.push(CatcherFrame { time: object.start_time - time_walking, ..last });
+        }
+
+        frames.push(target);
+        last = target;
+        hyper_dashing = object.hyper_dash;
+    }
+    frames
+}
+
+pub fn catch_system(
+    clock: Res<PlaybackClock>,
+    path: Res<CatchPath>,
+    mut fallers: Query<(&Faller, &mut Transform, &mut Visibility)>,
+    mut catchers: Query<(&Catcher, &mut Transform, &mut Sprite), Without<Faller>>) {
+
+    let pos = clock.time();
+
+    for (faller, mut transform, mut visibility) in &mut fallers {
+        let progress = ((faller.start_time - pos) / path.fall_time) as f32;
+
+        //everything is caught right on time
+        visibility.is_visible = (0.0..=1.0).contains(&progress);
+        transform.translation.y = CATCHER_Y + progress * (FALL_START_Y - CATCHER_Y);
+    }
+
+    let (x, hyper_dash) = path.at(pos);
+    for (_, mut transform, mut sprite) in &mut catchers {
+        transform.translation.x = screen_x(x);
+        sprite.color = if hyper_dash { path.hyper_dash_colour } else { CATCHER_COLOUR };
+    }
+}
@@ -0,0 +1,267 @@
+use bevy_kira_audio::*;
+use bevy::{prelude::*, asset::LoadState};
+use rosu_parser::beatmap::{Beatmap, sections::ObjectParams, slider::Slider};
+
+use super::{SongHandle, transport::SongSource};
+
+/// part of the difference to the audio made up every frame, small enough not to be seen
+const DRIFT_CORRECTION: f64 = 0.1;
+/// the clock jumps ahead to the audio when it's this far behind, in ms
+const MAX_DRIFT: f64 = 100.0;
+
+/// the audio has applied a seek once it reports a position this close to it, in ms
+const SEEK_TOLERANCE: f64 = 50.0;
+/// frames to wait for a seek before correcting towards the audio again, e.g. after seeking past the end
+const SEEK_TIMEOUT: u32 = 30;
+
+/// map time in ms that everything is drawn and played at.
+///
+/// it runs on frame time at the playback rate and is pulled towards the song's position,
+/// so it doesn't stutter with the audio's buffers. it only goes back when it's seeked,
+/// and keeps running without a song
+#[derive(Resource)]
+pub struct PlaybackClock {
+    time: f64,
+    rate: f64, //the mods' clock rate times the chosen one
+    paused: bool,
+    started: bool,
+    seeking: Option<u32>, //frames waited for the audio to get to the last seek
+    length: f64, //of the song once it's loaded, the map's until then
+}
+
+impl PlaybackClock {
+
+    pub fn new(rate: f64, beatmap: &Beatmap) -> Self {
+        Self {
+            time: 0.0,
+            rate,
+            paused: false,
+            started: false,
+            seeking: None,
+            length: map_length(beatmap),
+        }
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// the song has started playing, or there's no song to wait for
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    /// how long the song is in ms, or the map without one
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate;
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// jumps to `time`, the audio isn't trusted again until it's got there too
+    pub fn seek(&mut self, time: f64) {
+        self.time = time;
+        self.seeking = Some(0);
+    }
+
+    /// moves on by `delta` seconds of real time, `audio_position` is the song's in ms while it's playing
+    fn tick(&mut self, delta: f64, audio_position: Option<f64>, has_audio: bool) {
+        if !self.started {
+            //the song takes a moment to load and shouldn't start behind the objects
+            self.started = audio_position.is_some() || !has_audio;
+            if !self.started {
+                return;
+            }
+        }
+        if self.paused {
+            return;
+        }
+
+        let mut time = self.time + delta * 1000.0 * self.rate;
+
+        if let Some(audio_position) = audio_position {
+            match self.seeking {
+                Some(waited) if (audio_position - self.time).abs() > SEEK_TOLERANCE && waited < SEEK_TIMEOUT => {
+                    self.seeking = Some(waited + 1);
+                }
+                Some(_) => self.seeking = None,
+                None => {
+                    let drift = audio_position - time;
+                    if drift > MAX_DRIFT {
+                        time = audio_position;
+                    } else {
+                        time += drift * DRIFT_CORRECTION;
+                    }
+                }
+            }
+        }
+
+        //audio that's behind slows the clock down instead of sending it back
+        self.time = time.max(self.time);
+    }
+}
+
+/// when the last object ends, for playing without a song
+fn map_length(beatmap: &Beatmap) -> f64 {
+    let timing = beatmap.timing_map();
+
+    beatmap.hit_objects.iter().flatten()
+        .map(|hit_object| match &hit_object.object_params {
+            ObjectParams::Slider(_) => Slider::new(beatmap, &timing, hit_object)
+                .map_or(hit_object.time as f64, |slider| slider.timing.end_time()),
+            ObjectParams::EndTime(end_time) => *end_time as f64,
+            ObjectParams::None => hit_object.time as f64,
+        })
+        .fold(0.0, f64::max)
+}
+
+pub fn clock_system(
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    source: Option<Res<SongSource>>,
+    song_handle: Option<Res<SongHandle>>,
+    audio_sources: Res<Assets<AudioSource>>,
+    audio_instances: Res<Assets<AudioInstance>>,
+    mut clock: ResMut<PlaybackClock>) {
+
+    //maps without a song, or with one that can't be loaded, play silently
+    let has_audio = source.as_ref()
+        .is_some_and(|source| asset_server.get_load_state(&source.0) != LoadState::Failed);
+
+    if let Some(source) = source.as_ref().and_then(|source| audio_sources.get(&source.0)) {
+        clock.length = source.sound.duration().as_secs_f64() * 1000.0;
+    }
+
+    //a stretched song is longer or shorter than the map's time
+    let audio_position = song_handle
+        .and_then(|song_handle| audio_instances.get(&song_handle.instance)
+            .and_then(|song| song.state().position())
+            .map(|pos| pos * 1000.0 * song_handle.stretch));//into ms
+
+    clock.tick(time.delta_seconds_f64(), audio_position, has_audio);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a 60 fps frame in seconds
+    const FRAME: f64 = 1.0 / 60.0;
+
+    fn clock(rate: f64) -> PlaybackClock {
+        PlaybackClock::new(rate, &Beatmap::default())
+    }
+
+    #[test]
+    fn waits_for_the_song_to_start() {
+        let mut clock = clock(1.0);
+        clock.tick(FRAME, None, true);
+        assert!(!clock.started());
+        assert_eq!(clock.time(), 0.0);
+
+        clock.tick(FRAME, Some(0.0), true);
+        assert!(clock.started());
+        assert!(clock.time() > 0.0);
+    }
+
+    #[test]
+    fn runs_on_frame_time_without_audio() {
+        let mut clock = clock(1.0);
+        clock.tick(0.5, None, false);
+        assert!(clock.started());
+        assert_eq!(clock.time(), 500.0);
+    }
+
+    #[test]
+    fn rate_scales_frame_time() {
+        for rate in [0.5, 1.5] {
+            let mut clock = clock(rate);
+            clock.tick(0.1, None, false);
+            assert!((clock.time() - 100.0 * rate).abs() < 1e-9, "{}x: {}", rate, clock.time());
+        }
+    }
+
+    #[test]
+    fn never_goes_back_without_a_seek() {
+        let mut clock = clock(1.0);
+        let mut last = 0.0;
+        //the audio reports positions in buffer sized steps, sometimes behind the clock
+        for (i, audio) in [0.0, 40.0, 20.0, 20.0, 80.0, 60.0, 60.0, 150.0].into_iter().enumerate() {
+            clock.tick(FRAME, Some(audio), true);
+            assert!(clock.time() >= last, "frame {}: {} after {}", i, clock.time(), last);
+            last = clock.time();
+        }
+
+        //paused, it stays put
+        clock.set_paused(true);
+        clock.tick(FRAME, Some(1000.0), true);
+        assert_eq!(clock.time(), last);
+    }
+
+    #[test]
+    fn small_drift_is_made_up_slowly_and_big_drift_at_once() {
+        let mut clock = clock(1.0);
+        clock.tick(0.0, Some(0.0), true);
+
+        let drift = MAX_DRIFT / 2.0;
+        clock.tick(0.0, Some(drift), true);
+        assert!((clock.time() - drift * DRIFT_CORRECTION).abs() < 1e-9);
+
+        let audio = clock.time() + MAX_DRIFT + 1.0;
+        clock.tick(0.0, Some(audio), true);
+        assert_eq!(clock.time(), audio);
+    }
+
+    #[test]
+    fn seeks_wait_for_the_audio() {
+        let mut clock = clock(1.0);
+        clock.tick(FRAME, Some(0.0), true);
+
+        //the audio still reports the old position, it's ignored
+        clock.seek(10000.0);
+        clock.tick(FRAME, Some(0.0), true);
+        assert!((clock.time() - (10000.0 + FRAME * 1000.0)).abs() < 1e-9);
+
+        //until it gets there, after that it's followed again
+        clock.tick(0.0, Some(clock.time()), true);
+        let audio = clock.time() + MAX_DRIFT + 1.0;
+        clock.tick(0.0, Some(audio), true);
+        assert_eq!(clock.time(), audio);
+    }
+
+    #[test]
+    fn seeks_stop_waiting_after_a_timeout() {
+        let mut clock = clock(1.0);
+        clock.tick(0.0, Some(0.0), true);
+
+        //e.g. seeking past the end, the audio never gets there
+        clock.seek(10000.0);
+        for _ in 0..SEEK_TIMEOUT {
+            clock.tick(0.0, Some(0.0), true);
+        }
+        assert_eq!(clock.time(), 10000.0);
+
+        //the next tick gives up on it, from then on the audio is followed again but can't send it back
+        clock.tick(0.0, Some(0.0), true);
+        assert_eq!(clock.time(), 10000.0);
+        let audio = clock.time() + MAX_DRIFT + 1.0;
+        clock.tick(0.0, Some(audio), true);
+        assert_eq!(clock.time(), audio);
+        clock.tick(0.0, Some(0.0), true);
+        assert_eq!(clock.time(), audio);
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use super::{BeatmapInfo, FONT, clock::PlaybackClock, playfield::screen_scale};
+
+const FONT_SIZE: f32 = 96.0;
+const Z: f32 = 800.0;
+
+/// shown a beat each, the last one a beat before the first object
+const LABELS: [&str; 4] = ["3", "2", "1", "go!"];
+
+/// the countdown before the first object, at the map's speed
+#[derive(Component)]
+pub struct Countdown {
+    start_time: f64, //when "3" shows
+    beat_length: f64,
+}
+
+impl Countdown {
+
+    /// `None` when the map turns it off or the first object comes too early for it, like in the game
+    fn new(beatmap: &BeatmapInfo) -> Option<Self> {
+        let general = beatmap.data.general.as_ref()?;
+        //0 is off, then normal, half and double speed. maps without the setting get the normal one
+        let speed = match general.countdown.unwrap_or(1) {
+            0 => return None,
+            2 => 0.5,
+            3 => 2.0,
+            _ => 1.0,
+        };
+
+        let first = beatmap.data.hit_objects.iter().flatten().next()?.time as f64;
+        let beat_length = beatmap.data.timing_map().beat_length_at(first) / speed;
+        //the offset moves it this many beats earlier
+        let offset = general.countdown_offset.unwrap_or(0) as f64;
+        let start_time = first - (LABELS.len() as f64 + offset) * beat_length;
+
+        (start_time >= 0.0).then_some(Self { start_time, beat_length })
+    }
+}
+
+pub fn spawn_countdown(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    beatmap: Res<BeatmapInfo>) {
+
+    let countdown = match Countdown::new(&beatmap) {
+        Some(countdown) => countdown,
+        None => return,
+    };
+
+    commands.spawn((
+        countdown,
+        Text2dBundle {
+            text: Text::from_section("", TextStyle {
+                font: asset_server.load(FONT),
+                font_size: FONT_SIZE,
+                color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            }).with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_xyz(0.0, 0.0, Z),
+            ..default()
+        },
+    ));
+}
+
+pub fn countdown_system(
+    clock: Res<PlaybackClock>,
+    windows: Res<Windows>,
+    mut countdowns: Query<(&Countdown, &mut Text, &mut Transform)>) {
+
+    let pos = clock.time();
+    let scale = windows.get_primary()
+        .map_or(1.0, |window| screen_scale(window.width(), window.height()));
+
+    for (countdown, mut text, mut transform) in &mut countdowns {
+        transform.scale = Vec3::new(scale, scale, 1.0);
+
+        //each label fades out over its beat
+        let beats = (pos - countdown.start_time) / countdown.beat_length;
+        let section = &mut text.sections[0];
+        let alpha = match LABELS.get(beats.floor() as usize) {
+            Some(label) if beats >= 0.0 => {
+                section.value = label.to_string();
+                1.0 - beats.fract() as f32
+            }
+            _ => 0.0,
+        };
+        section.style.color.set_a(alpha);
+    }
+}
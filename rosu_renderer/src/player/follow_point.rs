@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use rosu_parser::beatmap::{sections::{HitObjectKind, ObjectParams}, slider::Slider};
+
+use super::{
+    BeatmapInfo,
+    clock::PlaybackClock,
+    playfield::{Playfield, to_local},
+    skin::SkinTextures,
+};
+
+/// osu!pixels between follow points
+const SPACING: f32 = 32.0;
+/// how long before its time a follow point appears, shorter when the approach is faster than AR10
+const PREEMPT: f64 = 800.0;
+/// follow points start bigger and shrink to their size as they fade in
+const START_SCALE: f32 = 1.5;
+/// they're drawn below every object
+const Z: f32 = 5.0;
+
+/// one point of a trail from the end of an object to the start of the next one in its combo
+#[derive(Component)]
+pub struct FollowPoint {
+    fade_in_time: f64,
+    fade_out_time: f64,
+    fade: f64, //how long fading in and out takes
+    from: Vec2, //slides from here to `to` while it fades in
+    to: Vec2,
+    scale: f32,
+}
+
+/// where and when an object starts and ends, after stacking
+struct Endpoints {
+    start_time: f64,
+    end_time: f64,
+    start: Vec2,
+    end: Vec2,
+    new_combo: bool,
+    spinner: bool,
+}
+
+pub fn spawn_follow_points(
+    mut commands: Commands,
+    textures: Res<SkinTextures>,
+    beatmap: Res<BeatmapInfo>,
+    playfields: Query<Entity, With<Playfield>>) {
+
+    let playfield = match playfields.get_single() {
+        Ok(playfield) => playfield,
+        Err(_) => return,
+    };
+
+    let timing = beatmap.data.timing_map();
+    let stacking = beatmap.data.stacking();
+    let objects: Vec<Endpoints> = beatmap.data.hit_objects.iter().flatten()
+        .zip(stacking)
+        .map(|(hit_object, stack)| {
+            let (end_time, end) = match Slider::new(&beatmap.data, &timing, hit_object) {
+                //the path isn't stacked, only the head is
+                Some(slider) => (
+                    slider.timing.end_time(),
+                    stack.position + (slider.end_position() - slider.path.position_at(0.0)),
+                ),
+                None => match hit_object.object_params {
+                    ObjectParams::EndTime(end_time) => (end_time as f64, stack.position),
+                    _ => (hit_object.time as f64, stack.position),
+                },
+            };
+            Endpoints {
+                start_time: hit_object.time as f64,
+                end_time,
+                start: to_local(stack.position),
+                end: to_local(end),
+                new_combo: hit_object.new_combo,
+                spinner: matches!(hit_object.kind, HitObjectKind::Spinner),
+            }
+        })
+        .collect();
+
+    //faster approaches than AR10 shorten the trail's preempt too
+    let attributes = &beatmap.attributes;
+    let preempt = PREEMPT * (attributes.fade_in() / 400.0);
+    let fade = attributes.fade_in();
+
+    commands.entity(playfield).with_children(|playfield| {
+        for (start, end) in objects.iter().zip(objects.iter().skip(1)) {
+            if end.new_combo || start.spinner || end.spinner {
+                continue;
+            }
+
+            let vector = end.start - start.end;
+            let distance = vector.length();
+            let rotation = Quat::from_rotation_z(vector.y.atan2(vector.x));
+            let duration = end.start_time - start.end_time;
+
+            //the first point leaves some room after the object, the last one before the next
+            let mut d = SPACING * 1.5;
+            while d < distance - SPACING {
+                let fraction = d / distance;
+                let fade_out_time = start.end_time + fraction as f64 * duration;
+                let follow_point = FollowPoint {
+                    fade_in_time: fade_out_time - preempt,
+                    fade_out_time,
+                    fade,
+                    from: start.end + vector * (fraction - 0.1),
+                    to: start.end + vector * fraction,
+                    scale: textures.follow_point.scale,
+                };
+
+                playfield.spawn((
+                    SpriteBundle {
+                        texture: textures.follow_point.handle.clone(),
+                        sprite: Sprite {
+                            color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(follow_point.from.extend(Z))
+                            .with_rotation(rotation),
+                        ..default()
+                    },
+                    follow_point,
+                ));
+                d += SPACING;
+            }
+        }
+    });
+}
+
+pub fn follow_point_system(
+    clock: Res<PlaybackClock>,
+    mut follow_points: Query<(&FollowPoint, &mut Transform, &mut Sprite)>) {
+
+    let pos = clock.time();
+
+    for (point, mut transform, mut sprite) in &mut follow_points {
+        let fade_in = ((pos - point.fade_in_time) / point.fade).clamp(0.0, 1.0);
+        let fade_out = ((pos - point.fade_out_time) / point.fade).clamp(0.0, 1.0);
+        sprite.color.set_a((fade_in - fade_out) as f32);
+
+        //slides into place and shrinks while fading in, easing out
+        let eased = (1.0 - (1.0 - fade_in).powi(2)) as f32;
+        let position = point.from.lerp(point.to, eased);
+        transform.translation = position.extend(Z);
+        let scale = point.scale * (START_SCALE + (1.0 - START_SCALE) * eased);
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
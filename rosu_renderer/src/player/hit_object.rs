@@ -1,86 +1,314 @@
-
-use bevy_kira_audio::*;
 use bevy::{prelude::*};
-use rosu_parser::beatmap::{self};
+use rosu_parser::{beatmap::{sections::{HitObjectKind, ObjectParams as Params}, slider::Slider}, mods::Mods};
+
+use super::{
+    BeatmapInfo,
+    FONT,
+    clock::PlaybackClock,
+    playfield::{Playfield, to_local},
+    skin::{SkinInfo, SkinTextures, SkinTexture},
+    slider,
+    spinner::{self, Spinner},
+    stats::ReplayInfo,
+};
+
+/// parts of the approach time hidden takes to fade objects in and out
+const HIDDEN_FADE_IN: f64 = 0.4;
+const HIDDEN_FADE_OUT: f64 = 0.3;
 
-use super::{BeatmapInfo, SongHandle};
+/// approach circles start this many times bigger than the object
+const APPROACH_CIRCLE_SCALE: f32 = 4.0;
+/// skins without their own hit animation fade objects out over this long, in ms
+const HIT_FADE_OUT: f64 = 240.0;
+/// while growing to this size
+const HIT_SCALE: f32 = 1.4;
 
+/// combo numbers are drawn smaller than the circle's skin pixels
+const COMBO_NUMBER_SCALE: f32 = 0.8;
+const COMBO_NUMBER_FONT_SIZE: f32 = 48.0;
+
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_objects(
     mut commands: Commands, 
-    asset_server: Res<AssetServer>, 
-    beatmap: Res<BeatmapInfo>) {
-
-    for hit_object in beatmap.data.hit_objects.as_ref().unwrap() {
-        commands.spawn(
-            HitObjectBundle{
-                time: Time(hit_object.time),
-                kind: Kind(hit_object.kind.clone()),
-                hit_sound: HitSound(hit_object.hit_sound),
-                sprite: SpriteBundle{
-                    transform: Transform::from_xyz(
-                        hit_object.x as f32 - 320.0 + 64.0, 
-                        hit_object.y as f32 - 240.0 + 64.0, 20.0),
-                    texture: asset_server.load("sliderstartcircleoverlay.png"),
-                    sprite: Sprite {
-                        color: Color::rgba(1.0,1.0,1.0, 0.0),
-                        ..default()
+    asset_server: Res<AssetServer>,
+    textures: Res<SkinTextures>, 
+    skin: Res<SkinInfo>,
+    beatmap: Res<BeatmapInfo>,
+    replay: Res<ReplayInfo>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    playfields: Query<Entity, With<Playfield>>) {
+
+    let stacking = beatmap.data.stacking();
+    let timing = beatmap.data.timing_map();
+    //skin textures are made for a 64 osu!pixel radius
+    let object_scale = beatmap.attributes.scale() as f32;
+    let hidden = beatmap.mods.contains(Mods::HIDDEN);
+    let combo_colours = skin.combo_colours(&beatmap.data);
+    let combo_colour_indices = beatmap.data.combo_colour_indices();
+    let combo_numbers = beatmap.data.combo_numbers();
+    let overlap = skin.skin.hit_circle_overlap() as f32;
+    let font = asset_server.load(FONT);
+
+    let playfield = match playfields.get_single() {
+        Ok(playfield) => playfield,
+        Err(_) => return,
+    };
+
+    commands.entity(playfield).with_children(|playfield| {
+        let hit_objects = beatmap.data.hit_objects.iter().flatten();
+        for (i, (hit_object, stack)) in hit_objects.zip(stacking).enumerate() {
+            //slider heads are hit at the start, the body stays for the rest
+            let end_time = match &hit_object.object_params {
+                Params::EndTime(end_time) => *end_time as f64,
+                _ => hit_object.time as f64,
+            };
+            let position = to_local(stack.position);
+
+            //earlier objects are drawn on top, the parts of one are spread below the next one
+            let z = 20.0 - i as f32 * 0.001;
+
+            //spinners replace the circle and its approach circle
+            if matches!(hit_object.kind, HitObjectKind::Spinner) {
+                let spinner = Spinner::new(&beatmap, &replay, hit_object.time as f64, end_time);
+                spinner::spawn_spinner(playfield, &asset_server, &textures, spinner, z);
+                continue;
+            }
+
+            let combo_colour = combo_colours[combo_colour_indices[i] % combo_colours.len()];
+            if let Some(slider) = Slider::new(&beatmap.data, &timing, hit_object) {
+                slider::spawn_slider(playfield, &mut meshes, &mut materials, slider::SliderSpawn {
+                    stack_offset: stack.position - slider.path.position_at(0.0),
+                    slider,
+                    colours: slider::SliderColours::new(&beatmap.data, &skin, combo_colour),
+                    textures: &textures,
+                    object_scale,
+                    z: z - 0.0004,
+                });
+            }
+            let mut sprite = skin_sprite(&textures.hit_circle, object_scale,
+                Transform::from_translation(position.extend(z)));
+            sprite.sprite.color = *combo_colour.clone().set_a(0.0);
+
+            let mut object = playfield.spawn(
+                HitObjectBundle{
+                    time: Time(hit_object.time),
+                    appearance: Appearance {
+                        end_time,
+                        scale: textures.hit_circle.scale * object_scale,
+                    },
+                    sprite,
+                }
+            );
+            object.with_children(|parent| {
+                //the object's scale already covers the size and its own @2x
+                parent.spawn(skin_sprite(
+                    &textures.hit_circle_overlay, 1.0 / textures.hit_circle.scale,
+                    Transform::from_xyz(0.0, 0.0, 0.00001)));
+
+                let number_scale = COMBO_NUMBER_SCALE / textures.hit_circle.scale;
+                let number = combo_numbers[i].to_string();
+                match &textures.numbers {
+                    //placed side by side by `combo_number_layout_system` once their sizes are known
+                    Some(digits) => for digit in number.bytes() {
+                        parent.spawn((
+                            ComboNumber,
+                            skin_sprite(&digits[(digit - b'0') as usize], number_scale,
+                                Transform::from_xyz(0.0, 0.0, 0.00002)),
+                        ));
+                    },
+                    None => {
+                        parent.spawn((
+                            ComboNumber,
+                            Text2dBundle {
+                                text: Text::from_section(number, TextStyle {
+                                    font: font.clone(),
+                                    font_size: COMBO_NUMBER_FONT_SIZE,
+                                    color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                                }).with_alignment(TextAlignment::CENTER),
+                                transform: Transform::from_xyz(0.0, 0.0, 0.00002)
+                                    .with_scale(Vec3::new(number_scale, number_scale, 1.0)),
+                                ..default()
+                            },
+                        ));
+                    }
+                }
+            });
+            if textures.numbers.is_some() {
+                let number_scale = COMBO_NUMBER_SCALE / textures.hit_circle.scale;
+                object.insert(ComboNumberLayout { overlap: overlap * number_scale });
+            }
+
+            //hidden only keeps the first approach circle, to show when the map starts
+            if !hidden || i == 0 {
+                playfield.spawn((
+                    ApproachCircle {
+                        time: hit_object.time as f64,
+                        scale: textures.approach_circle.scale * object_scale,
                     },
-                    ..default()
-                },
-                ..default()
+                    skin_sprite(&textures.approach_circle, object_scale,
+                        Transform::from_translation(position.extend(30.0 - i as f32 * 0.0001))),
+                ));
             }
-        );
+        }
+    });
+}
+
+/// a hidden sprite for a skin texture at `scale`, @2x textures are drawn at half size
+pub fn skin_sprite(texture: &SkinTexture, scale: f32, transform: Transform) -> SpriteBundle {
+    SpriteBundle{
+        transform: transform.with_scale(Vec3::new(texture.scale * scale, texture.scale * scale, 1.0)),
+        texture: texture.handle.clone(),
+        sprite: Sprite {
+            color: Color::rgba(1.0,1.0,1.0, 0.0),
+            ..default()
+        },
+        ..default()
     }
 }
 
 #[derive(Component, Default)]
-pub struct Time(usize);
+pub struct Time(isize);
 
+/// how long the object stays and the size it's drawn at before it's hit
 #[derive(Component, Default)]
-pub struct Kind(beatmap::sections::HitObjectKind);
+pub struct Appearance {
+    end_time: f64, //when a slider or spinner is over, the same as the start for circles
+    scale: f32,
+}
 
-#[derive(Component, Default)]
-pub struct HitSound(usize);
+/// shrinks onto its object until it has to be hit
+#[derive(Component)]
+pub struct ApproachCircle {
+    time: f64,
+    scale: f32, //at the object's size
+}
 
-#[derive(Component, Default)]
-pub struct ObjectParams(String);
+/// one of the digits, or the whole number in the built-in font, shown until the object is hit
+#[derive(Component)]
+pub struct ComboNumber;
 
-#[derive(Component, Default)]
-pub struct HitSample(usize);
+/// an object whose number sprites still have to be placed side by side
+#[derive(Component)]
+pub struct ComboNumberLayout {
+    overlap: f32, //how far the digits overlap, already at their scale
+}
 
 #[derive(Bundle, Default)]
 pub struct HitObjectBundle {
     pub time: Time,
-    pub kind: Kind,
-    pub hit_sound: HitSound,
-    pub object_params: ObjectParams,
-    pub hit_sample: HitSample,  
+    pub appearance: Appearance,
     pub sprite: SpriteBundle
 }
 
 
+/// how visible an object from `time` to `end_time` is at `pos`, and how far through fading out after it's over it is.
+///
+/// objects fade in from the start of the approach. hidden fades them out again before they have to be hit,
+/// sliders and spinners over their whole length
+pub fn fade_at(beatmap: &BeatmapInfo, pos: f64, time: f64, end_time: f64) -> (f64, f64) {
+    let attributes = &beatmap.attributes;
+    let preempt = attributes.preempt();
+    let hidden = beatmap.mods.contains(Mods::HIDDEN);
+    let fade_in = if hidden { preempt * HIDDEN_FADE_IN } else { attributes.fade_in() };
+    let fade_in_alpha = (pos - (time - preempt)) / fade_in;
+
+    if hidden {
+        let fade_out_start = time - preempt + fade_in;
+        let fade_out_length = if end_time > time { end_time - fade_out_start } else { preempt * HIDDEN_FADE_OUT };
+        let fade_out_alpha = 1.0 - (pos - fade_out_start) / fade_out_length;
+        (fade_in_alpha.min(fade_out_alpha).clamp(0.0, 1.0), 0.0)
+    } else if pos < end_time {
+        (fade_in_alpha.clamp(0.0, 1.0), 0.0)
+    } else {
+        let progress = ((pos - end_time) / HIT_FADE_OUT).clamp(0.0, 1.0);
+        (1.0 - progress, progress)
+    }
+}
+
 pub fn hit_object_system(
-    song_handle: Res<SongHandle>,
-    mut audio_instances: ResMut<Assets<AudioInstance>>,
-    mut query: Query<(&mut Time, &mut Sprite)>) {
+    clock: Res<PlaybackClock>,
+    beatmap: Res<BeatmapInfo>,
+    mut objects: Query<(Entity, &Time, &Appearance, &mut Transform, Option<&Children>)>,
+    mut approach_circles: Query<(&ApproachCircle, &mut Transform, &mut Sprite), Without<Time>>,
+    mut sprites: Query<(&mut Sprite, Option<&ComboNumber>), Without<ApproachCircle>>,
+    mut texts: Query<&mut Text, With<ComboNumber>>) {
     
-    if let Some(song) = audio_instances.get_mut(&song_handle.0) {
-        let pos = song.state().position();
+    let pos = clock.time();
+    let attributes = &beatmap.attributes;
+    let preempt = attributes.preempt();
 
-        if let Some(mut pos) = pos {
-            pos *= 1000.0;//into ms
-            for (time, mut sprite) in &mut query {
-                
-                //calculate hitobject alpha if its in range
-                let range = 200.0;
-                let dist = ((time.0 as f64) - pos).abs().clamp(0.0, range);
-                let inverted_dist = range - dist;
+    for (entity, time, appearance, mut transform, children) in &mut objects {
+        
+        //hit right on time, it grows as it fades
+        let (alpha, hit_progress) = fade_at(&beatmap, pos, time.0 as f64, appearance.end_time);
+        let eased = (1.0 - (1.0 - hit_progress).powi(2)) as f32;
+        let scale = appearance.scale * (1.0 + (HIT_SCALE - 1.0) * eased);
+        transform.scale = Vec3::new(scale, scale, 1.0);
 
-                sprite.color.set_a((inverted_dist / range) as f32);
+        //the number is gone as soon as the object is hit
+        let number_alpha = if hit_progress > 0.0 { 0.0 } else { alpha as f32 };
 
+        //the overlay and other parts are children of the object
+        let parts = std::iter::once(entity)
+            .chain(children.into_iter().flatten().copied());
+        for part in parts {
+            if let Ok((mut sprite, number)) = sprites.get_mut(part) {
+                sprite.color.set_a(if number.is_some() { number_alpha } else { alpha as f32 });
+            }
+            if let Ok(mut text) = texts.get_mut(part) {
+                text.sections[0].style.color.set_a(number_alpha);
             }
-
         }
+
     }
 
-}
\ No newline at end of file
+    //approach circles fade in over twice the object's fade-in and are gone once it's hit
+    let approach_fade_in = (attributes.fade_in() * 2.0).min(preempt);
+    for (circle, mut transform, mut sprite) in &mut approach_circles {
+        let progress = ((circle.time - pos) / preempt) as f32;
+        let alpha = if (0.0..=1.0).contains(&progress) {
+            ((pos - (circle.time - preempt)) / approach_fade_in).clamp(0.0, 1.0) as f32
+        } else {
+            0.0
+        };
+
+        let scale = circle.scale * (1.0 + (APPROACH_CIRCLE_SCALE - 1.0) * progress.clamp(0.0, 1.0));
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        sprite.color.set_a(alpha);
+    }
+
+}
+
+/// places number sprites next to each other around the middle of their object, once all of them are loaded
+pub fn combo_number_layout_system(
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    objects: Query<(Entity, &ComboNumberLayout, &Children)>,
+    mut digits: Query<(&Handle<Image>, &mut Transform), With<ComboNumber>>) {
+
+    for (entity, layout, children) in &objects {
+        let widths: Option<Vec<f32>> = children.iter()
+            .filter_map(|child| digits.get(*child).ok())
+            .map(|(handle, transform)| images.get(handle).map(|image| image.size().x * transform.scale.x))
+            .collect();
+        let widths = match widths {
+            Some(widths) => widths,
+            None => continue,
+        };
+
+        let overlap = layout.overlap;
+        let total = widths.iter().sum::<f32>() - overlap * widths.len().saturating_sub(1) as f32;
+
+        let mut x = -total / 2.0;
+        let mut widths = widths.into_iter();
+        for child in children {
+            if let Ok((_, mut transform)) = digits.get_mut(*child) {
+                let width = widths.next().unwrap_or_default();
+                transform.translation.x = x + width / 2.0;
+                x += width - overlap;
+            }
+        }
+        commands.entity(entity).remove::<ComboNumberLayout>();
+    }
+}
@@ -0,0 +1,143 @@
+use bevy_kira_audio::*;
+use bevy::prelude::*;
+use rosu_parser::beatmap::hitsounds::Sample;
+
+use super::{BeatmapInfo, clock::PlaybackClock, skin::{SkinInfo, DEFAULT_SKIN}, transport::Seeked};
+
+/// samples that are this late, e.g. after a lag spike, are skipped instead of played
+const MAX_LATENESS: f64 = 100.0;
+
+/// audio channel for hit sounds, so they don't share settings with the song
+#[derive(Resource)]
+pub struct HitSoundChannel;
+
+struct QueuedSample {
+    time: f64,
+    source: Handle<AudioSource>,
+    volume: f64,
+    loop_duration: Option<f64>,
+}
+
+/// every sample of the map in time order, and how far playback has got
+#[derive(Resource, Default)]
+pub struct HitSoundQueue {
+    samples: Vec<QueuedSample>,
+    next: usize,
+    looping: Vec<(Handle<AudioInstance>, f64)>, //slider slides and when they stop
+    interrupted: Vec<usize>, //slides a seek landed in the middle of, to start again once the song plays
+}
+
+pub fn queue_hit_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    beatmap: Res<BeatmapInfo>,
+    skin: Res<SkinInfo>) {
+
+    let samples = beatmap.data.hit_sounds()
+        .iter()
+        .filter_map(|sample| Some(QueuedSample {
+            time: sample.time,
+            source: load_sample(&asset_server, &skin, sample)?,
+            volume: sample.volume as f64 / 100.0,
+            loop_duration: sample.loop_duration,
+        }))
+        .collect();
+
+    commands.insert_resource(HitSoundQueue {
+        samples,
+        ..default()
+    });
+}
+
+/// beatmap samples first, then the user skin, then the built-in ones
+fn load_sample(asset_server: &AssetServer, skin: &SkinInfo, sample: &Sample) -> Option<Handle<AudioSource>> {
+    if let Some(filename) = &sample.filename {
+        return skin.lookup.file(filename).map(|path| asset_server.load(path));
+    }
+
+    let path = sample.beatmap_name()
+        .and_then(|name| skin.lookup.sample(&name))
+        .or_else(|| skin.skin_lookup.sample(&sample.skin_name()));
+
+    Some(match path {
+        Some(path) => asset_server.load(path),
+        None => asset_server.load(format!("{}/{}.wav", DEFAULT_SKIN, sample.skin_name())),
+    })
+}
+
+pub fn hit_sound_system(
+    clock: Res<PlaybackClock>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    channel: Res<AudioChannel<HitSoundChannel>>,
+    mut seeks: EventReader<Seeked>,
+    mut queue: ResMut<HitSoundQueue>) {
+
+    let queue = queue.as_mut();
+
+    //nothing that was playing belongs to the new position, and nothing skipped over should play
+    //except the slides of sliders the new position is in
+    if let Some(Seeked(target)) = seeks.iter().last() {
+        for (handle, _) in queue.looping.drain(..) {
+            if let Some(instance) = audio_instances.get_mut(&handle) {
+                instance.stop(AudioTween::default());
+            }
+        }
+        queue.next = queue.samples.partition_point(|s| s.time < *target);
+        queue.interrupted = (0..queue.next)
+            .filter(|&i| queue.samples[i].loop_duration.is_some_and(|duration| queue.samples[i].time + duration > *target))
+            .collect();
+    }
+
+    //nothing plays before the song does
+    if !clock.started() {
+        return;
+    }
+    let pos = clock.time();
+
+    for i in queue.interrupted.drain(..) {
+        let sample = &queue.samples[i];
+        let end = sample.time + sample.loop_duration.unwrap_or_default();
+        if end > pos {
+            queue.looping.push((play_looped(&channel, sample), end));
+        }
+    }
+
+    while let Some(sample) = queue.samples.get(queue.next) {
+        if sample.time > pos {
+            break;
+        }
+        queue.next += 1;
+
+        if pos - sample.time > MAX_LATENESS {
+            continue;
+        }
+
+        match sample.loop_duration {
+            Some(duration) => {
+                queue.looping.push((play_looped(&channel, sample), sample.time + duration));
+            }
+            None => {
+                channel.play(sample.source.clone())
+                    .with_volume(sample.volume);
+            }
+        }
+    }
+
+    queue.looping.retain(|(handle, end)| {
+        if *end > pos {
+            return true;
+        }
+        if let Some(instance) = audio_instances.get_mut(handle) {
+            instance.stop(AudioTween::default());
+        }
+        false
+    });
+}
+
+/// slides loop until they're stopped at the end of their slider
+fn play_looped(channel: &AudioChannel<HitSoundChannel>, sample: &QueuedSample) -> Handle<AudioInstance> {
+    channel.play(sample.source.clone())
+        .with_volume(sample.volume)
+        .looped()
+        .handle()
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+use rosu_parser::beatmap::{pos::PLAYFIELD_WIDTH, sections::{HitObjectKind, ObjectParams}};
+
+use super::{BeatmapInfo, clock::PlaybackClock, playfield::Screen};
+
+const COLUMN_WIDTH: f32 = 40.0;
+const NOTE_HEIGHT: f32 = 14.0;
+const JUDGEMENT_LINE_HEIGHT: f32 = 4.0;
+/// notes are hit here, near the bottom of the 640x480 screen
+const JUDGEMENT_LINE_Y: f32 = -240.0 + 64.0;
+/// and come in from the top
+const STAGE_TOP_Y: f32 = 240.0;
+
+/// ms of notes on screen at scroll speed 1, faster speeds divide it like the game does
+const MAX_TIME_RANGE: f64 = 11485.0;
+pub const DEFAULT_SCROLL_SPEED: f64 = 8.0;
+
+const STAGE_COLOUR: Color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+const JUDGEMENT_LINE_COLOUR: Color = Color::rgb(1.0, 1.0, 1.0);
+
+/// how fast notes scroll, 1 to 40 like the game's setting
+#[derive(Resource)]
+pub struct ManiaSettings {
+    pub scroll_speed: f64,
+}
+
+impl ManiaSettings {
+
+    /// how long a note takes from the top of the stage to the judgement line
+    fn time_range(&self) -> f64 {
+        MAX_TIME_RANGE / self.scroll_speed.clamp(1.0, 40.0)
+    }
+}
+
+/// the column of an object from its x position, mania maps store the key count as circle size
+pub fn column(x: isize, key_count: usize) -> usize {
+    let column_width = PLAYFIELD_WIDTH / key_count as f32;
+    ((x as f32 / column_width).floor() as usize).min(key_count - 1)
+}
+
+/// the outer columns and every other one after them are white, the ones between blue, the middle one yellow
+fn column_colour(column: usize, key_count: usize) -> Color {
+    if key_count % 2 == 1 && column == key_count / 2 {
+        return Color::rgb(1.0, 0.8, 0.2);
+    }
+
+    //mirrored, so both hands see the same colours
+    let from_edge = column.min(key_count - 1 - column);
+    if from_edge.is_multiple_of(2) {
+        Color::rgb(0.95, 0.95, 0.95)
+    } else {
+        Color::rgb(0.3, 0.6, 1.0)
+    }
+}
+
+fn column_x(column: usize, key_count: usize) -> f32 {
+    (column as f32 + 0.5 - key_count as f32 / 2.0) * COLUMN_WIDTH
+}
+
+/// a note head, holds stay on the judgement line until they're released
+#[derive(Component)]
+pub struct ManiaNote {
+    start_time: f64,
+    end_time: Option<f64>,
+}
+
+/// the part of a hold between its head and its end
+#[derive(Component)]
+pub struct HoldBody {
+    start_time: f64,
+    end_time: f64,
+}
+
+pub fn spawn_stage(
+    mut commands: Commands,
+    beatmap: Res<BeatmapInfo>,
+    screens: Query<Entity, With<Screen>>) {
+
+    let screen = match screens.get_single() {
+        Ok(screen) => screen,
+        Err(_) => return,
+    };
+
+    let key_count = (beatmap.attributes.circle_size.round_ties_even() as usize).max(1);
+    let stage_width = key_count as f32 * COLUMN_WIDTH;
+
+    commands.entity(screen).with_children(|screen| {
+        screen.spawn(SpriteBundle {
+            sprite: Sprite {
+                color: STAGE_COLOUR,
+                custom_size: Some(Vec2::new(stage_width, STAGE_TOP_Y - JUDGEMENT_LINE_Y + 240.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, (STAGE_TOP_Y + JUDGEMENT_LINE_Y - 240.0) / 2.0, 10.0),
+            ..default()
+        });
+
+        screen.spawn(SpriteBundle {
+            sprite: Sprite {
+                color: JUDGEMENT_LINE_COLOUR,
+                custom_size: Some(Vec2::new(stage_width, JUDGEMENT_LINE_HEIGHT)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, JUDGEMENT_LINE_Y, 11.0),
+            ..default()
+        });
+
+        for hit_object in beatmap.data.hit_objects.iter().flatten() {
+            let column = column(hit_object.x, key_count);
+            let x = column_x(column, key_count);
+            let colour = column_colour(column, key_count);
+
+            let start_time = hit_object.time as f64;
+            let end_time = match (hit_object.kind, &hit_object.object_params) {
+                (HitObjectKind::ManiaHold, ObjectParams::EndTime(end_time)) => Some(*end_time as f64),
+                _ => None,
+            };
+
+            if let Some(end_time) = end_time {
+                let mut body_colour = colour;
+                body_colour.set_a(0.6);
+
+                screen.spawn((
+                    HoldBody { start_time, end_time },
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: body_colour,
+                            custom_size: Some(Vec2::new(COLUMN_WIDTH * 0.8, 0.0)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(x, STAGE_TOP_Y, 12.0),
+                        visibility: Visibility { is_visible: false },
+                        ..default()
+                    },
+                ));
+            }
+
+            screen.spawn((
+                ManiaNote { start_time, end_time },
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: colour,
+                        custom_size: Some(Vec2::new(COLUMN_WIDTH - 2.0, NOTE_HEIGHT)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(x, STAGE_TOP_Y, 13.0),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                },
+            ));
+        }
+    });
+}
+
+pub fn mania_system(
+    clock: Res<PlaybackClock>,
+    settings: Res<ManiaSettings>,
+    mut notes: Query<(&ManiaNote, &mut Transform, &mut Visibility)>,
+    mut bodies: Query<(&HoldBody, &mut Transform, &mut Sprite, &mut Visibility), Without<ManiaNote>>) {
+
+    let pos = clock.time();
+
+    let time_range = settings.time_range();
+    let y_at = |time: f64| {
+        let progress = ((time - pos) / time_range) as f32;
+        JUDGEMENT_LINE_Y + progress * (STAGE_TOP_Y - JUDGEMENT_LINE_Y)
+    };
+
+    for (note, mut transform, mut visibility) in &mut notes {
+        //everything is hit perfectly, holds until their end
+        let hit_time = note.end_time.unwrap_or(note.start_time);
+        let y = y_at(note.start_time.max(pos));
+
+        visibility.is_visible = pos <= hit_time && y <= STAGE_TOP_Y + NOTE_HEIGHT;
+        transform.translation.y = y;
+    }
+
+    for (body, mut transform, mut sprite, mut visibility) in &mut bodies {
+        let bottom = y_at(body.start_time.max(pos));
+        let top = y_at(body.end_time).min(STAGE_TOP_Y);
+
+        visibility.is_visible = pos <= body.end_time && bottom < STAGE_TOP_Y;
+        transform.translation.y = (bottom + top) / 2.0;
+        if let Some(size) = sprite.custom_size.as_mut() {
+            size.y = (top - bottom).max(0.0);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::{prelude::*, window::WindowResized};
+use rosu_parser::beatmap::pos::{Pos2, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+
+/// the 4:3 screen the playfield sits in, fitted to the window with bars on the longer side
+const SCREEN_WIDTH: f32 = 640.0;
+const SCREEN_HEIGHT: f32 = 480.0;
+/// the game moves the playfield down a little from the middle of the screen
+const PLAYFIELD_SHIFT_Y: f32 = 8.0;
+
+/// osu!standard objects are its children and placed in osu!pixels with [`to_local`]
+#[derive(Component)]
+pub struct Playfield;
+
+/// taiko, catch and mania are laid out on the 640x480 screen as its children
+#[derive(Component)]
+pub struct Screen;
+
+/// an osu!pixel position relative to the playfield's middle, osu!'s y goes down and bevy's up
+pub fn to_local(position: Pos2) -> Vec2 {
+    Vec2::new(position.x - PLAYFIELD_WIDTH / 2.0, PLAYFIELD_HEIGHT / 2.0 - position.y)
+}
+
+/// how much bigger the biggest 4:3 screen that fits the window is than 640x480
+pub fn screen_scale(width: f32, height: f32) -> f32 {
+    (width / SCREEN_WIDTH).min(height / SCREEN_HEIGHT)
+}
+
+/// scales the 640x480 screen to the biggest 4:3 screen that fits the window
+fn fit_screen(width: f32, height: f32) -> Transform {
+    let scale = screen_scale(width, height);
+    Transform::from_scale(Vec3::new(scale, scale, 1.0))
+}
+
+/// the same for the playfield, which sits a little below the screen's middle
+fn fit(width: f32, height: f32) -> Transform {
+    let transform = fit_screen(width, height);
+    transform.with_translation(Vec3::new(0.0, -PLAYFIELD_SHIFT_Y * transform.scale.y, 0.0))
+}
+
+/// the window's new size when the primary one was resized, only it draws the map
+fn primary_resize(resized: &mut EventReader<WindowResized>, windows: &Windows) -> Option<(f32, f32)> {
+    let primary = windows.get_primary().map(|window| window.id());
+    resized.iter().rev()
+        .find(|event| Some(event.id) == primary)
+        .map(|event| (event.width, event.height))
+}
+
+pub fn spawn_playfield(
+    mut commands: Commands,
+    windows: Res<Windows>) {
+
+    let transform = windows.get_primary()
+        .map_or(Transform::IDENTITY, |window| fit(window.width(), window.height()));
+
+    commands.spawn((
+        Playfield,
+        SpatialBundle {
+            transform,
+            ..default()
+        },
+    ));
+}
+
+pub fn resize_playfield(
+    mut resized: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    mut playfields: Query<&mut Transform, With<Playfield>>) {
+
+    if let Some((width, height)) = primary_resize(&mut resized, &windows) {
+        for mut transform in &mut playfields {
+            *transform = fit(width, height);
+        }
+    }
+}
+
+pub fn spawn_screen(
+    mut commands: Commands,
+    windows: Res<Windows>) {
+
+    let transform = windows.get_primary()
+        .map_or(Transform::IDENTITY, |window| fit_screen(window.width(), window.height()));
+
+    commands.spawn((
+        Screen,
+        SpatialBundle {
+            transform,
+            ..default()
+        },
+    ));
+}
+
+pub fn resize_screen(
+    mut resized: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    mut screens: Query<&mut Transform, With<Screen>>) {
+
+    if let Some((width, height)) = primary_resize(&mut resized, &windows) {
+        for mut transform in &mut screens {
+            *transform = fit_screen(width, height);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// where an osu!pixel position ends up in a window of that size, from its top left corner
+    fn on_window(position: Pos2, width: f32, height: f32) -> Vec2 {
+        let point = fit(width, height).transform_point(to_local(position).extend(0.0));
+        Vec2::new(point.x + width / 2.0, height / 2.0 - point.y)
+    }
+
+    #[test]
+    fn playfield_corners() {
+        assert_eq!(to_local(Pos2::new(0.0, 0.0)), Vec2::new(-256.0, 192.0));
+        assert_eq!(to_local(Pos2::new(512.0, 384.0)), Vec2::new(256.0, -192.0));
+        assert_eq!(to_local(Pos2::new(256.0, 192.0)), Vec2::ZERO);
+    }
+
+    #[test]
+    fn playfield_on_the_screen() {
+        //64 from the sides, and 48 from the top and bottom before it's moved down 8
+        assert_eq!(screen_scale(640.0, 480.0), 1.0);
+        assert_eq!(on_window(Pos2::new(0.0, 0.0), 640.0, 480.0), Vec2::new(64.0, 56.0));
+        assert_eq!(on_window(Pos2::new(512.0, 384.0), 640.0, 480.0), Vec2::new(576.0, 440.0));
+
+        assert_eq!(screen_scale(1280.0, 960.0), 2.0);
+        assert_eq!(on_window(Pos2::new(0.0, 0.0), 1280.0, 960.0), Vec2::new(128.0, 112.0));
+    }
+
+    #[test]
+    fn wide_window() {
+        //the screen is 1440 wide with 240 wide bars either side
+        assert_eq!(screen_scale(1920.0, 1080.0), 2.25);
+        assert_eq!(on_window(Pos2::new(0.0, 0.0), 1920.0, 1080.0), Vec2::new(240.0 + 144.0, 126.0));
+        assert_eq!(on_window(Pos2::new(512.0, 384.0), 1920.0, 1080.0), Vec2::new(1920.0 - 240.0 - 144.0, 990.0));
+    }
+
+    #[test]
+    fn tall_window() {
+        //the screen is 600 high with 300 high bars above and below
+        assert_eq!(screen_scale(800.0, 1200.0), 1.25);
+        assert_eq!(on_window(Pos2::new(0.0, 0.0), 800.0, 1200.0), Vec2::new(80.0, 300.0 + 70.0));
+        assert_eq!(on_window(Pos2::new(512.0, 384.0), 800.0, 1200.0), Vec2::new(720.0, 1200.0 - 300.0 - 50.0));
+    }
+}
@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    prelude::*,
+    asset::{AssetIo, AssetIoError, FileType, Metadata},
+    utils::BoxedFuture,
+};
+use rosu_parser::{beatmap::Beatmap, colour::Rgb, skin::{Skin, elements::{ElementLookup, Element}}};
+
+/// folder in the renderer's assets with the built-in skin
+pub const DEFAULT_SKIN: &str = "default_skin";
+
+/// combo colours of the default skin, for skins without their own
+const DEFAULT_COMBO_COLOURS: [Color; 4] = [
+    Color::rgb(1.0, 0.75, 0.0),
+    Color::rgb(0.0, 0.79, 0.0),
+    Color::rgb(0.07, 0.49, 1.0),
+    Color::rgb(0.95, 0.09, 0.22),
+];
+
+/// hyperdash fruit and catcher tint when the skin doesn't set one
+const DEFAULT_HYPER_DASH_COLOUR: Color = Color::rgb(1.0, 0.0, 0.0);
+
+/// the parsed skin.ini and where to look for its elements
+#[derive(Resource)]
+pub struct SkinInfo {
+    pub skin: Skin,
+    pub lookup: ElementLookup, //beatmap folder, then the user skin
+    pub skin_lookup: ElementLookup, //only the user skin
+}
+
+impl SkinInfo {
+
+    /// beatmap skin files take priority over the user skin, which takes priority over the built-in one.
+    ///
+    /// a user skin that can't be loaded is left out with an error, the default skin is used instead
+    pub fn new(beatmap_path: &Path, skin_path: Option<&Path>) -> Self {
+        let loaded = skin_path.and_then(|path| match Skin::load_from_path(path) {
+            Ok(skin) => Some((path, skin)),
+            Err(err) => {
+                error!("couldn't load the skin \"{}\", using the default one: {:?}", path.display(), err);
+                None
+            }
+        });
+        let skin_path = loaded.as_ref().map(|(path, _)| *path);
+
+        Self {
+            lookup: Self::lookup(beatmap_path, skin_path),
+            skin_lookup: skin_path.map_or_else(ElementLookup::new, |path| ElementLookup::new().with_source(path)),
+            skin: loaded.map_or_else(Skin::new, |(_, skin)| skin),
+        }
+    }
+
+    /// where to look for elements, the beatmap folder and then the user skin
+    pub fn lookup(beatmap_path: &Path, skin_path: Option<&Path>) -> ElementLookup {
+        let mut lookup = ElementLookup::new();
+        if let Some(beatmap_dir) = beatmap_path.parent() {
+            lookup = lookup.with_source(beatmap_dir);
+        }
+        if let Some(path) = skin_path {
+            lookup = lookup.with_source(path);
+        }
+        lookup
+    }
+
+    /// the map's combo colours, or the skin's, or the default skin's
+    pub fn combo_colours(&self, beatmap: &Beatmap) -> Vec<Color> {
+        let mut colours = beatmap.colours.as_ref().map_or_else(Vec::new, |c| c.combo_colours());
+        if colours.is_empty() {
+            colours = self.skin.colours.combo_colours();
+        }
+        if colours.is_empty() {
+            return DEFAULT_COMBO_COLOURS.to_vec();
+        }
+        colours.into_iter().map(rgb_colour).collect()
+    }
+
+    /// catch's hyperdash colour, fruits use their own one when the skin sets it
+    pub fn hyper_dash_colour(&self, fruit: bool) -> Color {
+        let ctb = &self.skin.catch_the_beat;
+        let colour = if fruit { ctb.hyper_dash_fruit.or(ctb.hyper_dash) } else { ctb.hyper_dash };
+        colour.map_or(DEFAULT_HYPER_DASH_COLOUR, rgb_colour)
+    }
+}
+
+pub fn rgb_colour(rgb: Rgb) -> Color {
+    let [r, g, b, a] = rgb.as_f32();
+    Color::rgba(r, g, b, a)
+}
+
+/// asset io that serves files from .osk skins and everything else from disk
+pub struct SkinAssetIo {
+    default_io: Box<dyn AssetIo>,
+    lookup: ElementLookup,
+}
+
+impl SkinAssetIo {
+
+    pub fn new(lookup: ElementLookup) -> Self {
+        Self {
+            default_io: AssetPlugin::default().create_platform_default_asset_io(),
+            lookup,
+        }
+    }
+}
+
+impl AssetIo for SkinAssetIo {
+
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        if !self.lookup.is_archived(path) {
+            return self.default_io.load_path(path);
+        }
+
+        Box::pin(async move {
+            self.lookup.read(path)
+                .ok_or_else(|| AssetIoError::NotFound(path.to_path_buf()))
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        self.default_io.read_directory(path)
+    }
+
+    fn get_metadata(&self, path: &Path) -> Result<Metadata, AssetIoError> {
+        if self.lookup.is_archived(path) {
+            return Ok(Metadata::new(FileType::File));
+        }
+        self.default_io.get_metadata(path)
+    }
+
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        self.default_io.watch_path_for_changes(path)
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.default_io.watch_for_changes()
+    }
+}
+
+/// a loaded skin image and the scale it should be drawn at
+#[derive(Clone, Default)]
+pub struct SkinTexture {
+    pub handle: Handle<Image>,
+    pub scale: f32,
+}
+
+#[derive(Resource)]
+pub struct SkinTextures {
+    pub hit_circle: SkinTexture,
+    pub hit_circle_overlay: SkinTexture,
+    pub approach_circle: SkinTexture,
+    pub numbers: Option<Vec<SkinTexture>>, //default-0 to default-9, None when the skin doesn't have all of them
+    pub slider_ball: Vec<SkinTexture>,
+    pub slider_follow_circle: SkinTexture,
+    pub slider_score_point: SkinTexture,
+    pub reverse_arrow: SkinTexture,
+    pub spinner_circle: SkinTexture,
+    pub spinner_approach_circle: SkinTexture,
+    pub follow_point: SkinTexture,
+    pub warning_arrow: SkinTexture,
+    pub section_pass: SkinTexture,
+    pub section_fail: SkinTexture,
+}
+
+pub fn load_skin_textures(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    skin: Res<SkinInfo>) {
+
+    let load = |element: &Element| SkinTexture {
+        handle: asset_server.load(element.path.clone()),
+        scale: if element.high_res { 0.5 } else { 1.0 },
+    };
+
+    let texture = |name: &str| match skin.lookup.texture(name) {
+        Some(element) => load(&element),
+        None => SkinTexture {
+            handle: asset_server.load(format!("{}/{}.png", DEFAULT_SKIN, name)),
+            scale: 1.0,
+        },
+    };
+
+    let prefix = skin.skin.hit_circle_prefix();
+    let numbers: Option<Vec<SkinTexture>> = (0..10)
+        .map(|i| skin.lookup.texture(&format!("{}-{}", prefix, i)).map(|e| load(&e)))
+        .collect();
+
+    let mut slider_ball: Vec<SkinTexture> = skin.lookup.animation("sliderb", "")
+        .iter()
+        .map(load)
+        .collect();
+
+    if slider_ball.is_empty() {
+        slider_ball.push(texture("sliderb0"));
+    }
+
+    commands.insert_resource(SkinTextures {
+        hit_circle: texture("hitcircle"),
+        hit_circle_overlay: texture("hitcircleoverlay"),
+        approach_circle: texture("approachcircle"),
+        numbers,
+        slider_ball,
+        slider_follow_circle: texture("sliderfollowcircle"),
+        slider_score_point: texture("sliderscorepoint"),
+        reverse_arrow: texture("reversearrow"),
+        spinner_circle: texture("spinner-circle"),
+        spinner_approach_circle: texture("spinner-approachcircle"),
+        follow_point: texture("followpoint"),
+        warning_arrow: texture("play-warningarrow"),
+        section_pass: texture("section-pass"),
+        section_fail: texture("section-fail"),
+    });
+}
@@ -0,0 +1,593 @@
+use std::{f32::consts::{PI, TAU}, ops::Range};
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+    sprite::MaterialMesh2dBundle,
+};
+use rosu_parser::beatmap::{Beatmap, pos::Pos2, slider::{Slider, SliderTiming}};
+
+use super::{
+    BeatmapInfo,
+    clock::PlaybackClock,
+    hit_object::{fade_at, skin_sprite},
+    playfield::to_local,
+    skin::{rgb_colour, SkinInfo, SkinTextures},
+};
+
+/// the border takes this part of the body's radius, the track the rest
+const BORDER_PORTION: f32 = 0.128;
+/// triangles around the round ends and joints of the body
+const JOINT_SEGMENTS: usize = 24;
+/// joints that turn less than this, in radians, are mitred on both sides
+const MIN_JOINT_ANGLE: f32 = 0.02;
+/// how far the inside of a joint can reach, in radii, sharper turns fold over there
+const MITER_LIMIT: f32 = 4.0;
+/// the path is cut into pieces this long at most, in osu!pixels, for the body to snake along
+const MAX_SEGMENT_LENGTH: f32 = 4.0;
+
+/// the body draws itself along the path over this part of the approach
+const SNAKING_IN_PORTION: f64 = 1.0 / 3.0;
+
+/// the follow circle grows in over this long once the slider starts, and fades out over this long after it, in ms
+const FOLLOW_CIRCLE_IN: f64 = 180.0;
+const FOLLOW_CIRCLE_OUT: f64 = 200.0;
+/// how long each sliderb frame is shown, in ms
+const BALL_FRAME_LENGTH: f64 = 1000.0 / 60.0;
+
+/// what a slider is drawn in, the map's colours over the skin's
+pub struct SliderColours {
+    border: Color,
+    track: Color, //the combo colour unless it's overridden
+    ball: Color,
+}
+
+impl SliderColours {
+
+    pub fn new(beatmap: &Beatmap, skin: &SkinInfo, combo_colour: Color) -> Self {
+        let map = beatmap.colours.as_ref();
+        let skin = &skin.skin.colours;
+
+        Self {
+            border: map.and_then(|c| c.slider_border).or(skin.slider_border).map_or(Color::WHITE, rgb_colour),
+            track: map.and_then(|c| c.slider_track_override).or(skin.slider_track_override).map_or(combo_colour, rgb_colour),
+            ball: skin.slider_ball.map_or(Color::WHITE, rgb_colour),
+        }
+    }
+}
+
+/// everything [`spawn_slider`] places a slider with
+pub struct SliderSpawn<'a> {
+    pub slider: Slider,
+    pub stack_offset: Pos2, //the path isn't stacked, only the head is
+    pub colours: SliderColours,
+    pub textures: &'a SkinTextures,
+    pub object_scale: f32,
+    pub z: f32,
+}
+
+/// a mesh along the whole path, only the triangles of the part that's shown are drawn
+struct PathMesh {
+    handle: Handle<Mesh>,
+    indices: Vec<u32>, //all of them
+    segments: Vec<Range<usize>>,
+}
+
+impl PathMesh {
+
+    fn new(meshes: &mut Assets<Mesh>, stroke: Stroke) -> Self {
+        Self {
+            handle: meshes.add(stroke.mesh(stroke.indices.clone())),
+            indices: stroke.indices,
+            segments: stroke.segments,
+        }
+    }
+
+    /// the indices of the segments and joints from point `from` to point `to`
+    fn indices(&self, from: usize, to: usize) -> Vec<u32> {
+        if to <= from {
+            return Vec::new();
+        }
+        self.indices[self.segments[from].start..self.segments[to - 1].end].to_vec()
+    }
+}
+
+/// the track and border along the path, built once and cut down to the part that's shown
+#[derive(Component)]
+pub struct SliderBody {
+    slider: Slider,
+    stack_offset: Pos2,
+    points: Vec<Vec2>, //the path on the playfield
+    distances: Vec<f32>, //along the path to each point
+    border: PathMesh,
+    track: PathMesh,
+    material: Handle<ColorMaterial>, //shared by both, to fade them together
+    drawn: Option<(usize, usize)>, //the points the meshes go between
+}
+
+impl SliderBody {
+
+    /// where the ball is at `time`, on the playfield
+    fn ball_position(&self, time: f64) -> Vec2 {
+        to_local(self.slider.position_at_time(time) + self.stack_offset)
+    }
+
+    /// the first and last point between `from` and `to` along the path, as parts of its length
+    fn point_range(&self, from: f32, to: f32) -> (usize, usize) {
+        let length = self.distances.last().copied().unwrap_or(0.0);
+        let from = self.distances.partition_point(|d| *d < from * length).min(self.points.len() - 1);
+        let to = self.distances.partition_point(|d| *d <= to * length).saturating_sub(1).max(from);
+        (from, to)
+    }
+
+    /// which way the segment from point `i` goes, the last segment's for the last point
+    fn direction(&self, i: usize) -> Vec2 {
+        let i = i.min(self.points.len().saturating_sub(2));
+        self.points.get(i + 1).map_or(Vec2::X, |next| (*next - self.points[i]).normalize_or_zero())
+    }
+}
+
+/// the border or track, hidden on their own so the ball still shows without them
+#[derive(Component)]
+pub struct SliderMesh;
+
+/// closes off the start or end of the part of the body that's shown
+#[derive(Component)]
+pub struct SliderCap {
+    end: bool,
+}
+
+/// children of a slider body that follow its timing
+#[derive(Component)]
+pub enum SliderPart {
+    Ball,
+    FollowCircle,
+    /// shown from the start of the slide leading to it until the slider turns around
+    ReverseArrow { shown_from: f64, time: f64 },
+    /// shown from the start of its slide until the ball gets to it
+    Tick { shown_from: f64, time: f64 },
+}
+
+pub fn spawn_slider(
+    playfield: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    spawn: SliderSpawn) {
+
+    let SliderSpawn { slider, stack_offset, colours, textures, object_scale, z } = spawn;
+
+    let mut points: Vec<Vec2> = slider.path.points().iter()
+        .map(|point| to_local(*point + stack_offset))
+        .collect();
+    if points.is_empty() {
+        points.push(to_local(slider.path.position_at(0.0) + stack_offset));
+    }
+    let points = subdivide(&points);
+    let distances = std::iter::once(0.0)
+        .chain(points.windows(2).scan(0.0, |distance, pair| {
+            *distance += pair[0].distance(pair[1]);
+            Some(*distance)
+        }))
+        .collect();
+
+    let timing = &slider.timing;
+    let head = points[0];
+    let tail = points[points.len() - 1];
+    //reverse arrows point back along the path
+    let head_direction = points.get(1).map_or(Vec2::X, |next| *next - head);
+    let tail_direction = points.len().checked_sub(2).map_or(-Vec2::X, |i| points[i] - tail);
+
+    let mut ticks = Vec::new();
+    for tick in &timing.ticks {
+        let shown_from = if tick.span == 0 {
+            f64::NEG_INFINITY
+        } else {
+            timing.start_time + tick.span as f64 * timing.span_duration
+        };
+        let position = to_local(slider.path.position_at(tick.progress) + stack_offset);
+        ticks.push((SliderPart::Tick { shown_from, time: tick.time }, position));
+    }
+
+    let mut arrows = Vec::new();
+    for (i, time) in timing.repeat_times().enumerate() {
+        let shown_from = if i == 0 {
+            f64::NEG_INFINITY
+        } else {
+            timing.start_time + i as f64 * timing.span_duration
+        };
+        //the first repeat is at the end of the path, then they alternate
+        let (position, direction) = if i % 2 == 0 { (tail, tail_direction) } else { (head, head_direction) };
+        arrows.push((SliderPart::ReverseArrow { shown_from, time }, position, direction.y.atan2(direction.x)));
+    }
+
+    let radius = 64.0 * object_scale;
+    let track_radius = radius * (1.0 - BORDER_PORTION);
+    let mut build = |inner, outer, inner_colour, outer_colour| (
+        PathMesh::new(meshes, stroke(&points, inner, outer, inner_colour, outer_colour)),
+        meshes.add(cap(inner, outer, inner_colour, outer_colour)),
+    );
+    //like the game, the track is lighter in the middle
+    let (border, border_cap) = build(track_radius, radius, colours.border, colours.border);
+    let (track, track_cap) = build(0.0, track_radius, lighten(colours.track, 0.5), darken(colours.track, 0.1));
+
+    let material = materials.add(ColorMaterial::from(Color::rgba(1.0, 1.0, 1.0, 0.0)));
+    let meshes = [(0.0, border.handle.clone(), border_cap), (0.00005, track.handle.clone(), track_cap)];
+
+    let body = SliderBody {
+        slider,
+        stack_offset,
+        points,
+        distances,
+        border,
+        track,
+        material: material.clone(),
+        drawn: None,
+    };
+
+    playfield.spawn((
+        body,
+        SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, z)),
+    )).with_children(|body| {
+        for (z, path, cap) in meshes {
+            body.spawn((SliderMesh, MaterialMesh2dBundle {
+                mesh: path.into(),
+                material: material.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, z),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            }));
+            //placed at the ends by the body system once it's shown
+            for end in [false, true] {
+                body.spawn((SliderMesh, SliderCap { end }, MaterialMesh2dBundle {
+                    mesh: cap.clone().into(),
+                    material: material.clone(),
+                    transform: Transform::from_xyz(0.0, 0.0, z),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                }));
+            }
+        }
+
+        for (part, position) in ticks {
+            body.spawn((part, skin_sprite(&textures.slider_score_point, object_scale,
+                Transform::from_translation(position.extend(0.0001)))));
+        }
+        for (part, position, angle) in arrows {
+            body.spawn((part, skin_sprite(&textures.reverse_arrow, object_scale,
+                Transform::from_translation(position.extend(0.00015))
+                    .with_rotation(Quat::from_rotation_z(angle)))));
+        }
+
+        //the ball and follow circle go over the head, which is gone by the time they show
+        let mut ball = skin_sprite(&textures.slider_ball[0], object_scale,
+            Transform::from_translation(head.extend(0.0006)));
+        ball.sprite.color = *colours.ball.clone().set_a(0.0);
+        body.spawn((SliderPart::Ball, ball));
+        body.spawn((SliderPart::FollowCircle, skin_sprite(&textures.slider_follow_circle, object_scale,
+            Transform::from_translation(head.extend(0.0007)))));
+    });
+}
+
+/// `points` with segments no longer than [`MAX_SEGMENT_LENGTH`], so the shown part can end at any of them
+fn subdivide(points: &[Vec2]) -> Vec<Vec2> {
+    let mut subdivided = vec![points[0]];
+    for pair in points.windows(2) {
+        let length = pair[0].distance(pair[1]);
+        //points on top of each other have no direction to draw the line in
+        if length <= f32::EPSILON {
+            continue;
+        }
+        let pieces = (length / MAX_SEGMENT_LENGTH).ceil() as usize;
+        subdivided.extend((1..=pieces).map(|k| pair[0].lerp(pair[1], k as f32 / pieces as f32)));
+    }
+    subdivided
+}
+
+/// triangles built up along a path, each one drawn once so nothing is blended twice where they'd overlap
+#[derive(Default)]
+struct Stroke {
+    positions: Vec<[f32; 3]>,
+    colours: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+    segments: Vec<Range<usize>>, //the indices of each segment, the joint after it comes before the next one
+}
+
+impl Stroke {
+
+    /// a vertex `inner` and one `outer` along `direction` from `point`, returning the first's index
+    fn pair(&mut self, point: Vec2, direction: Vec2, inner: f32, outer: f32, colours: [[f32; 4]; 2]) -> u32 {
+        let start = self.positions.len() as u32;
+        self.positions.extend([point + direction * inner, point + direction * outer].map(|p| [p.x, p.y, 0.0]));
+        self.colours.extend(colours);
+        start
+    }
+
+    /// the quad between the pairs starting at `a` and `b`, turned to face the camera since back faces are culled
+    fn quad(&mut self, a: u32, b: u32) {
+        for triangle in [[a, a + 1, b], [b, a + 1, b + 1]] {
+            let [p0, p1, p2] = triangle.map(|i| Vec2::from_slice(&self.positions[i as usize]));
+            if (p1 - p0).perp_dot(p2 - p0) < 0.0 {
+                self.indices.extend([triangle[0], triangle[2], triangle[1]]);
+            } else {
+                self.indices.extend(triangle);
+            }
+        }
+    }
+
+    fn mesh(&self, indices: Vec<u32>) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colours.clone());
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+}
+
+/// a line through `points`, from `inner` to `outer` away from it on both sides and coloured
+/// from `inner_colour` to `outer_colour` across that. the ends are left open for [`cap`]s.
+///
+/// joints are rounded on the outside of the turn and mitred on the inside, so the segments meet without overlapping
+fn stroke(points: &[Vec2], inner: f32, outer: f32, inner_colour: Color, outer_colour: Color) -> Stroke {
+    let colours = [inner_colour.as_linear_rgba_f32(), outer_colour.as_linear_rgba_f32()];
+    let mut stroke = Stroke::default();
+    let direction = |i: usize| (points[i + 1] - points[i]).normalize_or_zero();
+
+    //the pairs at each point on the left and right, in order along the joint
+    let mut sides: [Vec<Vec<u32>>; 2] = Default::default();
+    for (i, point) in points.iter().enumerate() {
+        let previous = i.checked_sub(1).map(direction);
+        let next = (i + 1 < points.len()).then(|| direction(i));
+
+        for (side, pairs) in [1.0, -1.0].into_iter().zip(&mut sides) {
+            let normals = match (previous, next) {
+                (Some(previous), Some(next)) => {
+                    let (from, to) = (previous.perp() * side, next.perp() * side);
+                    let turn = previous.angle_between(next);
+                    if turn.abs() >= MIN_JOINT_ANGLE && turn * side < 0.0 {
+                        let steps = (turn.abs() / TAU * JOINT_SEGMENTS as f32).ceil() as usize;
+                        (0..=steps).map(|k| Vec2::from_angle(turn * k as f32 / steps as f32).rotate(from)).collect()
+                    } else {
+                        //where the edges of both segments cross, kept from reaching too far on sharp turns
+                        let miter = (from + to).normalize_or_zero();
+                        vec![miter * (1.0 / miter.dot(from)).min(MITER_LIMIT)]
+                    }
+                }
+                (Some(direction), None) | (None, Some(direction)) => vec![direction.perp() * side],
+                (None, None) => Vec::new(),
+            };
+            pairs.push(normals.into_iter().map(|normal| stroke.pair(*point, normal, inner, outer, colours)).collect());
+        }
+    }
+
+    for i in 0..points.len().saturating_sub(1) {
+        let start = stroke.indices.len();
+        for pairs in &sides {
+            stroke.quad(pairs[i][pairs[i].len() - 1], pairs[i + 1][0]);
+        }
+        stroke.segments.push(start..stroke.indices.len());
+
+        for pairs in &sides {
+            for joint in pairs[i + 1].windows(2) {
+                stroke.quad(joint[0], joint[1]);
+            }
+        }
+    }
+    stroke
+}
+
+/// a half circle facing right that closes off an end of a [`stroke`] with the same radii and colours
+fn cap(inner: f32, outer: f32, inner_colour: Color, outer_colour: Color) -> Mesh {
+    let colours = [inner_colour.as_linear_rgba_f32(), outer_colour.as_linear_rgba_f32()];
+    let mut stroke = Stroke::default();
+    let steps = JOINT_SEGMENTS / 2;
+    let pairs: Vec<u32> = (0..=steps)
+        .map(|k| Vec2::from_angle(PI * (k as f32 / steps as f32 - 0.5)))
+        .map(|direction| stroke.pair(Vec2::ZERO, direction, inner, outer, colours))
+        .collect();
+    for pair in pairs.windows(2) {
+        stroke.quad(pair[0], pair[1]);
+    }
+    stroke.mesh(stroke.indices.clone())
+}
+
+fn lighten(colour: Color, amount: f32) -> Color {
+    let amount = amount * 0.5;
+    let channel = |c: f32| (c * (1.0 + 0.5 * amount) + amount).min(1.0);
+    Color::rgba(channel(colour.r()), channel(colour.g()), channel(colour.b()), colour.a())
+}
+
+fn darken(colour: Color, amount: f32) -> Color {
+    let channel = |c: f32| c / (1.0 + amount);
+    Color::rgba(channel(colour.r()), channel(colour.g()), channel(colour.b()), colour.a())
+}
+
+/// the part of the path the body covers at `pos`.
+///
+/// it draws itself in during the approach, and the ball takes it along on the last slide
+fn snaking_range(timing: &SliderTiming, pos: f64, preempt: f64) -> (f32, f32) {
+    let snaked_in = ((pos - (timing.start_time - preempt)) / (preempt * SNAKING_IN_PORTION)).clamp(0.0, 1.0);
+    let last_slide_start = timing.end_time() - timing.span_duration;
+    let snaked_out = if timing.span_duration > 0.0 {
+        ((pos - last_slide_start) / timing.span_duration).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    //sliders with an even number of slides end back at the head
+    let (from, to) = if timing.spans % 2 == 1 {
+        (snaked_out, snaked_in)
+    } else {
+        (0.0, snaked_in.min(1.0 - snaked_out))
+    };
+    (from as f32, to as f32)
+}
+
+pub fn slider_body_system(
+    clock: Res<PlaybackClock>,
+    beatmap: Res<BeatmapInfo>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut bodies: Query<(&mut SliderBody, &Children)>,
+    mut visibilities: Query<&mut Visibility, With<SliderMesh>>,
+    mut caps: Query<(&SliderCap, &mut Transform)>) {
+
+    let pos = clock.time();
+    let preempt = beatmap.attributes.preempt();
+
+    for (mut body, children) in &mut bodies {
+        let timing = &body.slider.timing;
+        let (alpha, _) = fade_at(&beatmap, pos, timing.start_time, timing.end_time());
+        let (from, to) = snaking_range(timing, pos, preempt);
+
+        let visible = alpha > 0.0 && to > from;
+        for child in children {
+            if let Ok(mut visibility) = visibilities.get_mut(*child) {
+                visibility.is_visible = visible;
+            }
+        }
+        if !visible {
+            continue;
+        }
+
+        if let Some(material) = materials.get_mut(&body.material) {
+            material.color.set_a(alpha as f32);
+        }
+
+        let range = body.point_range(from, to);
+        if body.drawn == Some(range) {
+            continue;
+        }
+        let (from, to) = range;
+        for path in [&body.border, &body.track] {
+            if let Some(mesh) = meshes.get_mut(&path.handle) {
+                mesh.set_indices(Some(Indices::U32(path.indices(from, to))));
+            }
+        }
+
+        //both caps on one point make a circle, for bodies shorter than a segment
+        let start = (body.points[from], -body.direction(from));
+        let end = (body.points[to], body.direction(to.saturating_sub(1)));
+        for child in children {
+            if let Ok((cap, mut transform)) = caps.get_mut(*child) {
+                let (point, direction) = if cap.end { end } else { start };
+                transform.translation = point.extend(transform.translation.z);
+                transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+            }
+        }
+        body.drawn = Some(range);
+    }
+}
+
+pub fn slider_parts_system(
+    clock: Res<PlaybackClock>,
+    beatmap: Res<BeatmapInfo>,
+    textures: Res<SkinTextures>,
+    bodies: Query<(&SliderBody, &Children)>,
+    mut parts: Query<(&SliderPart, &mut Transform, &mut Sprite, &mut Handle<Image>)>) {
+
+    let pos = clock.time();
+    let follow_circle_scale = textures.slider_follow_circle.scale * beatmap.attributes.scale() as f32;
+
+    for (body, children) in &bodies {
+        let timing = &body.slider.timing;
+        let (start, end) = (timing.start_time, timing.end_time());
+        let (body_alpha, _) = fade_at(&beatmap, pos, start, end);
+
+        let ball_time = pos.clamp(start, end);
+        let ball = body.ball_position(ball_time);
+
+        for child in children {
+            let (part, mut transform, mut sprite, mut image) = match parts.get_mut(*child) {
+                Ok(part) => part,
+                Err(_) => continue,
+            };
+
+            let alpha = match *part {
+                SliderPart::Ball => {
+                    transform.translation = ball.extend(transform.translation.z);
+
+                    //it faces where it's going
+                    let direction = body.ball_position(ball_time + 1.0) - ball;
+                    if direction.length_squared() > 0.0 {
+                        transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+                    }
+
+                    let frames = &textures.slider_ball;
+                    let frame = &frames[((pos - start).max(0.0) / BALL_FRAME_LENGTH) as usize % frames.len()];
+                    if *image != frame.handle {
+                        *image = frame.handle.clone();
+                    }
+
+                    if (start..=end).contains(&pos) { 1.0 } else { 0.0 }
+                }
+                SliderPart::FollowCircle => {
+                    transform.translation = ball.extend(transform.translation.z);
+
+                    //grows from half size as the slider starts, shrinks a little as it fades after
+                    let grown = ((pos - start) / FOLLOW_CIRCLE_IN).clamp(0.0, 1.0);
+                    let faded = ((pos - end) / FOLLOW_CIRCLE_OUT).clamp(0.0, 1.0);
+                    let eased = 1.0 - (1.0 - grown).powi(2);
+                    let scale = follow_circle_scale * (0.5 + 0.5 * eased as f32) * (1.0 - 0.2 * faded as f32);
+                    transform.scale = Vec3::new(scale, scale, 1.0);
+
+                    if pos < start { 0.0 } else { grown * (1.0 - faded) }
+                }
+                SliderPart::ReverseArrow { shown_from, time } | SliderPart::Tick { shown_from, time } => {
+                    if (shown_from..time).contains(&pos) { body_alpha } else { 0.0 }
+                }
+            };
+            sprite.color.set_a(alpha as f32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the area the triangles cover, counting overlaps twice. back faces take away from it
+    fn area(stroke: &Stroke, indices: &[u32]) -> f32 {
+        indices.chunks(3)
+            .map(|triangle| {
+                let [p0, p1, p2] = [0, 1, 2].map(|i| Vec2::from_slice(&stroke.positions[triangle[i] as usize]));
+                (p1 - p0).perp_dot(p2 - p0) / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn straight_stroke_covers_its_rectangle() {
+        let points = subdivide(&[Vec2::ZERO, Vec2::new(100.0, 0.0)]);
+        let stroke = stroke(&points, 0.0, 10.0, Color::WHITE, Color::WHITE);
+        assert!((area(&stroke, &stroke.indices) - 2000.0).abs() < 0.1);
+
+        //points 0 to 12 are 12 segments of MAX_SEGMENT_LENGTH
+        let part = &stroke.indices[stroke.segments[0].start..stroke.segments[11].end];
+        assert!((area(&stroke, part) - 20.0 * 12.0 * MAX_SEGMENT_LENGTH).abs() < 0.1);
+    }
+
+    #[test]
+    fn joints_dont_overlap() {
+        //a right angle, round outside and mitred inside
+        let points = [Vec2::ZERO, Vec2::new(100.0, 0.0), Vec2::new(100.0, 100.0)];
+        let (inner, outer) = (5.0, 10.0);
+        for (inner, outer) in [(0.0, outer), (inner, outer)] {
+            let stroke = stroke(&points, inner, outer, Color::WHITE, Color::WHITE);
+            let sides = 2.0 * (outer - inner) * 200.0 - (outer * outer - inner * inner);
+            let wedge = PI / 4.0 * (outer * outer - inner * inner);
+            let expected = sides + wedge;
+            let area = area(&stroke, &stroke.indices);
+            //the wedge's curve is cut into straight pieces
+            assert!(area <= expected && area > expected - 2.0, "{} {}: {} vs {}", inner, outer, area, expected);
+        }
+    }
+
+    #[test]
+    fn triangles_face_the_camera() {
+        let points = subdivide(&[Vec2::ZERO, Vec2::new(100.0, 0.0), Vec2::new(50.0, 30.0), Vec2::new(80.0, -60.0)]);
+        let stroke = stroke(&points, 4.0, 10.0, Color::WHITE, Color::WHITE);
+        for triangle in stroke.indices.chunks(3) {
+            assert!(area(&stroke, triangle) >= 0.0);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::f64::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use rosu_parser::{beatmap::pos::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH}, replay::Replay};
+
+use super::{
+    BeatmapInfo,
+    FONT,
+    clock::PlaybackClock,
+    skin::SkinTextures,
+    stats::ReplayInfo,
+};
+
+const FONT_SIZE: f32 = 24.0;
+
+/// the spinner nearly fills the playfield's height, in osu!pixels
+const SPINNER_SIZE: f32 = PLAYFIELD_HEIGHT * 0.9;
+
+/// how fast the game's autoplay spins, in rpm, and the most a cursor can spin it
+const MAX_RPM: f64 = 477.0;
+/// rpm is measured over this long, in ms
+const RPM_WINDOW: f64 = 1000.0;
+
+/// spinners fade in over this long before they start and out over this long after, in ms
+const FADE_IN: f64 = 500.0;
+const FADE_OUT: f64 = 240.0;
+
+/// M1, M2, K1 and K2, spinners only turn while one is held
+const SPIN_KEYS: u32 = 0b1111;
+
+/// a spinner in the middle of the playfield and how far it had turned at any time
+#[derive(Component)]
+pub struct Spinner {
+    start_time: f64,
+    end_time: f64,
+    spins_required: usize,
+    rotation: Vec<(f64, f64)>, //time and radians turned since the start, interpolated between
+}
+
+impl Spinner {
+
+    /// spun by the replay's cursor when it's for this mode, or at the autoplay's speed
+    pub fn new(beatmap: &BeatmapInfo, replay: &ReplayInfo, start_time: f64, end_time: f64) -> Self {
+        let replay = replay.0.as_ref();
+        let rotation = match replay {
+            Some(replay) => replay_rotation(replay, start_time, end_time),
+            None => vec![(start_time, 0.0), (end_time, max_rotation(end_time - start_time))],
+        };
+
+        Self {
+            start_time,
+            end_time,
+            spins_required: beatmap.attributes.spins_required(end_time - start_time),
+            rotation,
+        }
+    }
+
+    /// radians turned since the start
+    fn rotation_at(&self, time: f64) -> f64 {
+        let i = self.rotation.partition_point(|(t, _)| *t <= time);
+        match (i.checked_sub(1).map(|i| self.rotation[i]), self.rotation.get(i)) {
+            (Some((t0, r0)), Some(&(t1, r1))) if t1 > t0 => r0 + (r1 - r0) * (time - t0) / (t1 - t0),
+            (Some((_, r)), _) => r,
+            _ => 0.0,
+        }
+    }
+
+    /// spins per minute over the last second, less at the start
+    fn rpm_at(&self, time: f64) -> f64 {
+        let time = time.min(self.end_time);
+        let window = RPM_WINDOW.min(time - self.start_time);
+        if window <= 0.0 {
+            return 0.0;
+        }
+        (self.rotation_at(time) - self.rotation_at(time - window)) / TAU * 60000.0 / window
+    }
+}
+
+/// the most a spinner can turn in `duration` ms
+fn max_rotation(duration: f64) -> f64 {
+    duration.max(0.0) * MAX_RPM / 60000.0 * TAU
+}
+
+/// how far the cursor turned around the middle of the playfield while a key was held
+fn replay_rotation(replay: &Replay, start_time: f64, end_time: f64) -> Vec<(f64, f64)> {
+    let centre = (PLAYFIELD_WIDTH as f64 / 2.0, PLAYFIELD_HEIGHT as f64 / 2.0);
+    let angle = |x: f32, y: f32| (y as f64 - centre.1).atan2(x as f64 - centre.0);
+
+    let mut rotation = vec![(start_time, 0.0)];
+    let mut total = 0.0;
+    let mut last: Option<(f64, f64)> = None; //time and angle of the previous frame
+
+    let frames = replay.frames.iter().filter(|f| f.time >= start_time && f.time <= end_time);
+    for frame in frames {
+        let frame_angle = angle(frame.x, frame.y);
+        if let Some((last_time, last_angle)) = last {
+            if frame.keys & SPIN_KEYS != 0 {
+                //the shorter way around, either direction counts
+                let delta = (frame_angle - last_angle + PI).rem_euclid(TAU) - PI;
+                total += delta.abs().min(max_rotation(frame.time - last_time));
+            }
+        }
+        rotation.push((frame.time, total));
+        last = Some((frame.time, frame_angle));
+    }
+    rotation
+}
+
+#[derive(Component)]
+pub enum SpinnerPart {
+    Circle,
+    ApproachCircle, //shrinks until the spinner ends
+    Rpm,
+    Spins,
+}
+
+pub fn spawn_spinner(
+    playfield: &mut ChildBuilder,
+    asset_server: &AssetServer,
+    textures: &SkinTextures,
+    spinner: Spinner,
+    z: f32) {
+
+    let text_style = TextStyle {
+        font: asset_server.load(FONT),
+        font_size: FONT_SIZE,
+        color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+    };
+    let sprite = |texture: &Handle<Image>, z: f32| SpriteBundle {
+        texture: texture.clone(),
+        sprite: Sprite {
+            color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            custom_size: Some(Vec2::splat(SPINNER_SIZE)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, 0.0, z),
+        ..default()
+    };
+    let text = |y: f32| Text2dBundle {
+        text: Text::from_section("", text_style.clone())
+            .with_alignment(TextAlignment::CENTER),
+        transform: Transform::from_xyz(0.0, y, 0.0003),
+        ..default()
+    };
+
+    //spinners are always in the middle, wherever the map puts them
+    playfield.spawn((
+        spinner,
+        SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, z)),
+    )).with_children(|parent| {
+        parent.spawn((SpinnerPart::Circle, sprite(&textures.spinner_circle.handle, 0.0)));
+        parent.spawn((SpinnerPart::ApproachCircle, sprite(&textures.spinner_approach_circle.handle, 0.0001)));
+        parent.spawn((SpinnerPart::Spins, text(-SPINNER_SIZE / 2.0 - FONT_SIZE)));
+        parent.spawn((SpinnerPart::Rpm, text(-SPINNER_SIZE / 2.0 - FONT_SIZE * 2.0)));
+    });
+}
+
+pub fn spinner_system(
+    clock: Res<PlaybackClock>,
+    spinners: Query<(&Spinner, &Children)>,
+    mut sprites: Query<(&SpinnerPart, &mut Transform, &mut Sprite)>,
+    mut texts: Query<(&SpinnerPart, &mut Text)>) {
+
+    let pos = clock.time();
+
+    for (spinner, children) in &spinners {
+        let fade_in = (pos - (spinner.start_time - FADE_IN)) / FADE_IN;
+        let fade_out = 1.0 - (pos - spinner.end_time) / FADE_OUT;
+        let alpha = fade_in.min(fade_out).clamp(0.0, 1.0) as f32;
+
+        let duration = spinner.end_time - spinner.start_time;
+        let progress = if duration > 0.0 { ((pos - spinner.start_time) / duration).clamp(0.0, 1.0) } else { 1.0 };
+        let rotation = spinner.rotation_at(pos);
+        let spins = (rotation / TAU) as usize;
+        let cleared = spins >= spinner.spins_required;
+
+        for child in children {
+            if let Ok((part, mut transform, mut sprite)) = sprites.get_mut(*child) {
+                match part {
+                    //osu! spins clockwise on screen, bevy turns anticlockwise
+                    SpinnerPart::Circle => transform.rotation = Quat::from_rotation_z(-rotation as f32),
+                    SpinnerPart::ApproachCircle => {
+                        let scale = (1.0 - progress) as f32;
+                        transform.scale = Vec3::new(scale, scale, 1.0);
+                    }
+                    _ => {}
+                }
+                sprite.color.set_a(alpha);
+            }
+
+            if let Ok((part, mut text)) = texts.get_mut(*child) {
+                let section = &mut text.sections[0];
+                section.value = match part {
+                    SpinnerPart::Spins if cleared => format!("{} / {} clear!", spins, spinner.spins_required),
+                    SpinnerPart::Spins => format!("{} / {}", spins, spinner.spins_required),
+                    SpinnerPart::Rpm => format!("{:.0} rpm", spinner.rpm_at(pos)),
+                    _ => continue,
+                };
+                section.style.color.set_a(alpha);
+            }
+        }
+    }
+}
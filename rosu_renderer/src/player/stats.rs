@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use rosu_difficulty::{Stars, osu::performance::{OsuPerformance, OsuPerformanceAttributes, OsuScore, PROJECTED_ACCURACIES}};
+use rosu_parser::{beatmap::sections::GameMode, replay::Replay};
+
+use super::{BeatmapInfo, FONT};
+
+const FONT_SIZE: f32 = 20.0;
+
+/// the replay being watched, if any
+#[derive(Resource, Default)]
+pub struct ReplayInfo(pub Option<Replay>);
+
+impl ReplayInfo {
+
+    /// replays of another map or mode are left out, with a warning
+    pub fn new(replay: Option<Replay>, beatmap: &BeatmapInfo) -> Self {
+        let replay = replay.filter(|replay| {
+            if replay.beatmap_hash != beatmap.hash {
+                warn!("the replay is of another map (md5 {}, the map's is {}), ignoring it", replay.beatmap_hash, beatmap.hash);
+                return false;
+            }
+            if replay.mode != beatmap.data.mode() {
+                warn!("the replay is in {:?} but the map is played in {:?}, ignoring it", replay.mode, beatmap.data.mode());
+                return false;
+            }
+            true
+        });
+        Self(replay)
+    }
+}
+
+/// star rating and pp of the map with the player's mods
+pub struct PerformanceSummary {
+    stars: f64,
+    projections: Vec<(f64, OsuPerformanceAttributes)>,
+    replay: Option<(OsuPerformanceAttributes, OsuPerformanceAttributes)>, //the play and its if FC
+}
+
+impl PerformanceSummary {
+
+    /// pp is only calculated for osu!standard maps so far, other modes get their stars
+    fn new(beatmap: &BeatmapInfo, replay: Option<&Replay>) -> Self {
+        //BeatmapInfo's map already has the mods applied, the calculator wants the map as it's stored.
+        //converts are calculated from the original map too
+        let map = &beatmap.original;
+        let mode = beatmap.data.mode();
+        if mode != GameMode::Osu {
+            return Self {
+                stars: Stars::new(map).mods(beatmap.mods).mode(mode).calculate().stars(),
+                projections: Vec::new(),
+                replay: None,
+            };
+        }
+
+        let performance = OsuPerformance::new(map).mods(beatmap.mods);
+        let difficulty = performance.calculate().difficulty;
+        let performance = performance.difficulty(difficulty.clone());
+        let projections = performance.accuracy_projections(&PROJECTED_ACCURACIES);
+
+        let replay = replay.map(|replay| {
+            let performance = performance.score(OsuScore::from_replay(replay));
+            (performance.calculate(), performance.if_fc())
+        });
+
+        Self {
+            stars: difficulty.stars,
+            projections,
+            replay,
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut lines = vec![format!("{:.2}*", self.stars)];
+
+        if self.projections.is_empty() {
+            return lines.join("\n");
+        }
+
+        let projections: Vec<String> = self.projections.iter()
+            .map(|(accuracy, attributes)| format!("{}%: {:.0}pp", accuracy * 100.0, attributes.pp))
+            .collect();
+        lines.push(projections.join("  "));
+
+        match &self.replay {
+            Some((play, fc)) => {
+                lines.push(format!("{:.0}pp (if FC {:.0}pp)", play.pp, fc.pp));
+                lines.push(breakdown(play));
+            }
+            None => {
+                if let Some((_, ss)) = self.projections.last() {
+                    lines.push(breakdown(ss));
+                }
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn breakdown(attributes: &OsuPerformanceAttributes) -> String {
+    let mut text = format!("aim {:.0}  speed {:.0}  acc {:.0}", attributes.aim, attributes.speed, attributes.accuracy);
+    if attributes.flashlight > 0.0 {
+        text += &format!("  fl {:.0}", attributes.flashlight);
+    }
+    text
+}
+
+/// text in the top left corner with the map's pp
+pub fn spawn_stats_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    beatmap: Res<BeatmapInfo>,
+    replay: Res<ReplayInfo>) {
+
+    let summary = PerformanceSummary::new(&beatmap, replay.0.as_ref());
+
+    let text_style = TextStyle {
+        font: asset_server.load(FONT),
+        font_size: FONT_SIZE,
+        color: Color::WHITE,
+    };
+
+    commands.spawn(
+        TextBundle::from_section(summary.text(), text_style)
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(8.0),
+                    left: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            })
+    );
+}
@@ -0,0 +1,188 @@
+use std::{f32::consts::PI, sync::Arc, thread::{self, JoinHandle}};
+
+use bevy_kira_audio::*;
+use bevy::prelude::*;
+use kira::{dsp::Frame, sound::static_sound::StaticSoundData};
+
+use super::{SongHandle, clock::PlaybackClock, transport::{SongSource, Transport}};
+
+/// frames in each piece of the song that's overlapped, about 46 ms at 44.1 kHz
+const WINDOW: usize = 2048;
+const HOP: usize = WINDOW / 2;
+/// how many frames a piece can move from where the rate puts it to line up with the last one
+const SEARCH: usize = 256;
+const SEARCH_STEP: usize = 4;
+/// only every few frames are compared when lining pieces up, it's plenty for finding the best one
+const CORRELATION_STEP: usize = 8;
+
+/// the song time-stretched to the transport's rate, so slowing it down doesn't change the pitch.
+/// stretching takes a moment, the song keeps playing with its pitch changed until it's done
+#[derive(Resource, Default)]
+pub struct StretchedSong {
+    rate: f64,
+    source: Option<Handle<AudioSource>>,
+    task: Option<(f64, JoinHandle<AudioSource>)>, //the rate it's for
+}
+
+/// plays `sound` at `rate` times its speed without changing its pitch.
+///
+/// the song is cut into overlapping pieces taken `rate` times further apart than they're put back,
+/// each one moved a little to line up with the one before it so the waves don't cancel out (WSOLA)
+pub fn time_stretch(sound: &StaticSoundData, rate: f64) -> StaticSoundData {
+    let input = &sound.frames;
+    let len = (input.len() as f64 / rate) as usize;
+    let mut output = vec![Frame::ZERO; len + WINDOW];
+
+    //hann windows half a window apart add up to 1
+    let window: Vec<f32> = (0..WINDOW)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / WINDOW as f32).cos())
+        .collect();
+
+    //pieces are lined up on both channels together
+    let mono: Vec<f32> = input.iter().map(|frame| frame.left + frame.right).collect();
+
+    let mut previous: Option<usize> = None;
+    for out in (0..len).step_by(HOP) {
+        let nominal = (out as f64 * rate) as usize;
+        //the piece that best continues where the last one left off
+        let start = match previous {
+            Some(previous) => best_start(&mono, previous + HOP, nominal),
+            None => nominal,
+        };
+
+        for (i, weight) in window.iter().enumerate() {
+            if let Some(frame) = input.get(start + i) {
+                output[out + i] += *frame * *weight;
+            }
+        }
+        previous = Some(start);
+    }
+    output.truncate(len);
+
+    StaticSoundData {
+        sample_rate: sound.sample_rate,
+        frames: Arc::new(output),
+        settings: sound.settings,
+    }
+}
+
+/// the start near `nominal` whose frames look the most like the ones from `natural` on.
+/// every few starts are tried first, then the ones around the best of those
+fn best_start(mono: &[f32], natural: usize, nominal: usize) -> usize {
+    let best = |starts: &mut dyn Iterator<Item = usize>| starts
+        .map(|start| (start, correlation(mono, natural, start)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(nominal, |(start, _)| start);
+
+    let coarse = best(&mut (nominal.saturating_sub(SEARCH)..=nominal + SEARCH).step_by(SEARCH_STEP));
+    best(&mut (coarse.saturating_sub(SEARCH_STEP)..=coarse + SEARCH_STEP))
+}
+
+fn correlation(mono: &[f32], a: usize, b: usize) -> f32 {
+    (0..HOP).step_by(CORRELATION_STEP)
+        .map(|i| mono.get(a + i).unwrap_or(&0.0) * mono.get(b + i).unwrap_or(&0.0))
+        .sum()
+}
+
+/// swaps the song for a stretched copy when the rate changes with pitch preservation on, and back when it's off.
+/// songs are only swapped while they play, a paused one is swapped once it's resumed
+#[allow(clippy::too_many_arguments)]
+pub fn stretch_system(
+    audio: Res<Audio>,
+    transport: Res<Transport>,
+    source: Option<Res<SongSource>>,
+    mut song_handle: Option<ResMut<SongHandle>>,
+    mut clock: ResMut<PlaybackClock>,
+    mut stretched: ResMut<StretchedSong>,
+    mut audio_sources: ResMut<Assets<AudioSource>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>) {
+
+    let (source, song) = match (source, song_handle.as_mut()) {
+        (Some(source), Some(song)) => (source, song),
+        _ => return,
+    };
+
+    let wanted = if transport.preserve_pitch { transport.rate } else { 1.0 };
+
+    //stretching runs on its own thread, a finished one is picked up here
+    if stretched.task.as_ref().is_some_and(|(_, task)| task.is_finished()) {
+        if let Some((rate, task)) = stretched.task.take() {
+            if let Ok(stretched_source) = task.join() {
+                stretched.rate = rate;
+                stretched.source = Some(audio_sources.add(stretched_source));
+            }
+        }
+    }
+
+    if song.stretch == wanted {
+        return;
+    }
+
+    let target = if wanted == 1.0 {
+        Some(source.0.clone())
+    } else if stretched.rate == wanted && stretched.source.is_some() {
+        stretched.source.clone()
+    } else {
+        //started again if the rate changed while it was stretching
+        if !stretched.task.as_ref().is_some_and(|(rate, _)| *rate == wanted) {
+            if let Some(original) = audio_sources.get(&source.0) {
+                let sound = original.sound.clone();
+                stretched.task = Some((wanted, thread::spawn(move || AudioSource {
+                    sound: time_stretch(&sound, wanted),
+                })));
+            }
+        }
+        None
+    };
+
+    let playing = !clock.paused() && audio_instances.get(&song.instance)
+        .is_some_and(|instance| matches!(instance.state(), PlaybackState::Playing { .. }));
+    if let (Some(target), true) = (target, playing) {
+        if let Some(instance) = audio_instances.get_mut(&song.instance) {
+            instance.stop(AudioTween::default());
+        }
+
+        let position = clock.time();
+        song.stretch = wanted;
+        song.instance = audio.play(target)
+            .start_from(position / 1000.0 / wanted)
+            .with_playback_rate(clock.rate() / wanted)
+            .handle();
+        //the clock waits for the new song to get where the old one was
+        clock.seek(position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, len: usize) -> StaticSoundData {
+        StaticSoundData {
+            sample_rate: 44100,
+            frames: Arc::new((0..len)
+                .map(|i| Frame::from_mono((2.0 * PI * frequency * i as f32 / 44100.0).sin()))
+                .collect()),
+            settings: Default::default(),
+        }
+    }
+
+    /// rising zero crossings per second, i.e. the pitch
+    fn frequency(sound: &StaticSoundData) -> f32 {
+        //the edges fade in and out
+        let frames = &sound.frames[WINDOW..sound.frames.len() - WINDOW];
+        let crossings = frames.windows(2).filter(|pair| pair[0].left < 0.0 && pair[1].left >= 0.0).count();
+        crossings as f32 / (frames.len() as f32 / sound.sample_rate as f32)
+    }
+
+    #[test]
+    fn stretching_keeps_the_pitch() {
+        let sound = sine(440.0, 44100);
+        for rate in [0.5, 0.75, 1.5] {
+            let stretched = time_stretch(&sound, rate);
+            assert_eq!(stretched.frames.len(), (44100.0 / rate) as usize);
+            let frequency = frequency(&stretched);
+            assert!((frequency - 440.0).abs() < 5.0, "{}x: {} Hz", rate, frequency);
+        }
+    }
+}
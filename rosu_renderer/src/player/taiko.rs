@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use rosu_parser::beatmap::{
+    sections::{ObjectParams, HIT_SOUND_CLAP, HIT_SOUND_FINISH, HIT_SOUND_WHISTLE},
+    slider::Slider,
+    timing::TimingMap,
+};
+
+use super::{BeatmapInfo, clock::PlaybackClock, playfield::Screen, skin::{SkinTextures, SkinTexture}};
+
+/// objects are hit here, near the left of the 640x480 screen
+const HIT_TARGET_X: f32 = -320.0 + 100.0;
+const LANE_Y: f32 = 60.0;
+const LANE_HEIGHT: f32 = 100.0;
+const LANE_WIDTH: f32 = 640.0;
+
+const HIT_SIZE: f32 = 64.0;
+/// finishers are hit with both hands and drawn bigger
+const FINISHER_SIZE: f32 = 96.0;
+
+/// screen pixels a slider velocity of 1 covers in one beat, drum rolls line up with their ticks like in the game
+const BASE_SCROLL_DISTANCE: f64 = 100.0 * 1.4;
+
+const LANE_COLOUR: Color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+const DON_COLOUR: Color = Color::rgb(0.92, 0.27, 0.17);
+const KAT_COLOUR: Color = Color::rgb(0.27, 0.55, 0.67);
+const DRUM_ROLL_COLOUR: Color = Color::rgb(0.99, 0.72, 0.02);
+const SWELL_COLOUR: Color = Color::rgb(0.95, 0.57, 0.0);
+const KIAI_COLOUR: Color = Color::rgb(1.0, 0.5, 0.1);
+
+/// a hit, or the head of a drum roll or swell
+#[derive(Component)]
+pub struct TaikoObject {
+    start_time: f64,
+    end_time: f64, //same as the start for hits
+    velocity: f32, //screen pixels per ms
+    is_swell: bool, //swells stop at the hit target until they're done
+}
+
+/// the bar behind a drum roll's head, as long as the roll
+#[derive(Component)]
+pub struct DrumRollBody {
+    start_time: f64,
+    end_time: f64,
+    velocity: f32,
+}
+
+/// the glow around the hit target during kiai
+#[derive(Component)]
+pub struct KiaiFlame;
+
+/// timing points for kiai and the beat the flame pulses to
+#[derive(Resource)]
+pub struct TaikoTiming(TimingMap);
+
+pub fn spawn_lane(
+    mut commands: Commands,
+    textures: Res<SkinTextures>,
+    beatmap: Res<BeatmapInfo>,
+    screens: Query<Entity, With<Screen>>) {
+
+    let screen = match screens.get_single() {
+        Ok(screen) => screen,
+        Err(_) => return,
+    };
+
+    let timing = beatmap.data.timing_map();
+    let slider_multiplier = beatmap.data.difficulty.as_ref()
+        .and_then(|d| d.slider_multiplier)
+        .unwrap_or(1.4);
+
+    commands.entity(screen).with_children(|screen| {
+        screen.spawn(SpriteBundle {
+            sprite: Sprite {
+                color: LANE_COLOUR,
+                custom_size: Some(Vec2::new(LANE_WIDTH, LANE_HEIGHT)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, LANE_Y, 10.0),
+            ..default()
+        });
+
+        screen.spawn((
+            KiaiFlame,
+            sized_sprite(&textures.hit_circle, KIAI_COLOUR, FINISHER_SIZE * 1.3, Vec3::new(HIT_TARGET_X, LANE_Y, 11.0)),
+        ));
+
+        let mut target_colour = Color::GRAY;
+        target_colour.set_a(0.8);
+        screen.spawn(sized_sprite(&textures.hit_circle_overlay, target_colour, HIT_SIZE, Vec3::new(HIT_TARGET_X, LANE_Y, 12.0)));
+
+        //later objects are drawn below earlier ones, like the game
+        let hit_objects = beatmap.data.hit_objects.as_deref().unwrap_or_default();
+        for (i, hit_object) in hit_objects.iter().enumerate() {
+            let start_time = hit_object.time as f64;
+            let z = 20.0 + (hit_objects.len() - i) as f32 * 0.001;
+
+            let velocity = (BASE_SCROLL_DISTANCE * slider_multiplier * timing.slider_velocity_at(start_time)
+                / timing.beat_length_at(start_time)) as f32;
+            let size = if hit_object.hit_sound & HIT_SOUND_FINISH != 0 { FINISHER_SIZE } else { HIT_SIZE };
+
+            let (colour, end_time) = match &hit_object.object_params {
+                ObjectParams::Slider(_) => {
+                    let end_time = Slider::new(&beatmap.data, &timing, hit_object)
+                        .map_or(start_time, |slider| slider.timing.end_time());
+
+                    screen.spawn((
+                        DrumRollBody { start_time, end_time, velocity },
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: DRUM_ROLL_COLOUR,
+                                custom_size: Some(Vec2::new(0.0, size * 0.8)),
+                                anchor: bevy::sprite::Anchor::CenterLeft,
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(LANE_WIDTH, LANE_Y, z - 0.0005),
+                            visibility: Visibility { is_visible: false },
+                            ..default()
+                        },
+                    ));
+
+                    (DRUM_ROLL_COLOUR, end_time)
+                }
+                ObjectParams::EndTime(end_time) => (SWELL_COLOUR, *end_time as f64),
+                ObjectParams::None if hit_object.hit_sound & (HIT_SOUND_WHISTLE | HIT_SOUND_CLAP) != 0 => (KAT_COLOUR, start_time),
+                ObjectParams::None => (DON_COLOUR, start_time),
+            };
+
+            screen.spawn((
+                TaikoObject {
+                    start_time,
+                    end_time,
+                    velocity,
+                    is_swell: matches!(hit_object.object_params, ObjectParams::EndTime(_)),
+                },
+                sized_sprite(&textures.hit_circle, colour, size, Vec3::new(LANE_WIDTH, LANE_Y, z)),
+            )).with_children(|parent| {
+                //hidden along with its parent
+                parent.spawn(SpriteBundle {
+                    visibility: Visibility::VISIBLE,
+                    ..sized_sprite(&textures.hit_circle_overlay, Color::WHITE, size, Vec3::new(0.0, 0.0, 0.0001))
+                });
+            });
+        }
+    });
+
+    commands.insert_resource(TaikoTiming(timing));
+}
+
+/// a hidden skin texture tinted and stretched to `size`
+fn sized_sprite(texture: &SkinTexture, color: Color, size: f32, translation: Vec3) -> SpriteBundle {
+    SpriteBundle {
+        texture: texture.handle.clone(),
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::splat(size)),
+            ..default()
+        },
+        transform: Transform::from_translation(translation),
+        visibility: Visibility { is_visible: false },
+        ..default()
+    }
+}
+
+pub fn taiko_system(
+    clock: Res<PlaybackClock>,
+    mut objects: Query<(&TaikoObject, &mut Transform, &mut Visibility)>,
+    mut bodies: Query<(&DrumRollBody, &mut Transform, &mut Sprite, &mut Visibility), Without<TaikoObject>>) {
+
+    let pos = clock.time();
+
+    let x_at = |time: f64, velocity: f32| HIT_TARGET_X + (time - pos) as f32 * velocity;
+    let on_screen = |x: f32| x < LANE_WIDTH / 2.0 + FINISHER_SIZE;
+
+    for (object, mut transform, mut visibility) in &mut objects {
+        //swells wait at the hit target, drum rolls keep going until their end passes it
+        let x = if object.is_swell {
+            x_at(object.start_time.max(pos), object.velocity)
+        } else {
+            x_at(object.start_time, object.velocity)
+        };
+
+        visibility.is_visible = pos <= object.end_time && on_screen(x);
+        transform.translation.x = x;
+    }
+
+    for (body, mut transform, mut sprite, mut visibility) in &mut bodies {
+        let start = x_at(body.start_time, body.velocity);
+        let end = x_at(body.end_time, body.velocity);
+
+        visibility.is_visible = pos <= body.end_time && on_screen(start);
+        transform.translation.x = start;
+        if let Some(size) = sprite.custom_size.as_mut() {
+            size.x = end - start;
+        }
+    }
+}
+
+pub fn kiai_system(
+    clock: Res<PlaybackClock>,
+    timing: Res<TaikoTiming>,
+    mut flames: Query<(&mut Sprite, &mut Visibility), With<KiaiFlame>>) {
+
+    let pos = clock.time();
+
+    //the flame pulses on every beat of kiai
+    let kiai = timing.0.kiai_at(pos);
+    let beat_length = timing.0.beat_length_at(pos);
+    let beat_start = timing.0.uninherited_at(pos).map_or(0.0, |p| p.time);
+    let beat_progress = ((pos - beat_start) / beat_length).rem_euclid(1.0) as f32;
+
+    for (mut sprite, mut visibility) in &mut flames {
+        visibility.is_visible = kiai;
+        sprite.color.set_a(0.7 * (1.0 - beat_progress));
+    }
+}
@@ -0,0 +1,243 @@
+use bevy::{prelude::*, ui::FocusPolicy};
+
+use super::{BeatmapInfo, FONT, clock::PlaybackClock, transport::TransportAction};
+
+const FONT_SIZE: f32 = 16.0;
+
+const MARGIN: f32 = 8.0;
+/// the timeline sits right above the transport controls
+const BOTTOM: f32 = 44.0;
+const HEIGHT: f32 = 24.0;
+const LINE_WIDTH: f32 = 2.0;
+
+/// columns of the object density graph
+const DENSITY_BUCKETS: usize = 200;
+
+const BACKGROUND_COLOUR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const DENSITY_COLOUR: Color = Color::rgba(0.6, 0.6, 0.7, 0.6);
+const BREAK_COLOUR: Color = Color::rgba(1.0, 1.0, 1.0, 0.15);
+const KIAI_COLOUR: Color = Color::rgba(1.0, 0.5, 0.1, 0.35);
+const BOOKMARK_COLOUR: Color = Color::rgb(0.3, 0.6, 1.0);
+const PREVIEW_COLOUR: Color = Color::rgb(0.3, 0.9, 0.3);
+const PLAYHEAD_COLOUR: Color = Color::WHITE;
+
+/// the song's progress along the bottom of the screen, click or drag it to seek
+#[derive(Component, Default)]
+pub struct Timeline {
+    length: Option<f64>, //ms across its width, the markers are placed for it
+}
+
+/// breaks, kiai and the rest, placed again when the length changes
+#[derive(Component)]
+pub struct TimelineMarker;
+
+#[derive(Component)]
+pub struct Playhead;
+
+/// the time under the cursor
+#[derive(Component)]
+pub struct HoverTime;
+
+/// a time as the editor writes it, so it can be pasted there
+fn timestamp(time: f64) -> String {
+    let ms = time.max(0.0) as usize;
+    format!("{:02}:{:02}:{:03}", ms / 60000, ms / 1000 % 60, ms % 1000)
+}
+
+/// a node from `start` to `end` of the timeline as fractions of its width, a line without an end
+fn marker(start: f32, end: Option<f32>, height: f32, colour: Color) -> NodeBundle {
+    let width = match end {
+        Some(end) => Val::Percent((end - start).max(0.0) * 100.0),
+        None => Val::Px(LINE_WIDTH),
+    };
+
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Percent(start * 100.0),
+                bottom: Val::Px(0.0),
+                ..default()
+            },
+            size: Size::new(width, Val::Percent(height * 100.0)),
+            ..default()
+        },
+        background_color: colour.into(),
+        //clicks go through to the timeline
+        focus_policy: FocusPolicy::Pass,
+        ..default()
+    }
+}
+
+pub fn spawn_timeline(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>) {
+
+    let text_style = TextStyle {
+        font: asset_server.load(FONT),
+        font_size: FONT_SIZE,
+        color: Color::WHITE,
+    };
+
+    commands.spawn((
+        Timeline::default(),
+        Interaction::default(),
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(MARGIN),
+                    right: Val::Px(MARGIN),
+                    bottom: Val::Px(BOTTOM),
+                    ..default()
+                },
+                size: Size::new(Val::Auto, Val::Px(HEIGHT)),
+                ..default()
+            },
+            background_color: BACKGROUND_COLOUR.into(),
+            ..default()
+        },
+    )).with_children(|parent| {
+        //the markers are added once the length is known, these stay on top of them
+        parent.spawn((
+            Playhead,
+            NodeBundle {
+                z_index: ZIndex::Local(1),
+                ..marker(0.0, None, 1.0, PLAYHEAD_COLOUR)
+            },
+        ));
+
+        parent.spawn((
+            HoverTime,
+            TextBundle {
+                z_index: ZIndex::Local(2),
+                visibility: Visibility { is_visible: false },
+                focus_policy: FocusPolicy::Pass,
+                ..TextBundle::from_section("", text_style)
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: Val::Px(0.0),
+                            bottom: Val::Px(HEIGHT + 2.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+            },
+        ));
+    });
+}
+
+/// breaks, kiai, density, bookmarks and the preview time.
+///
+/// they're placed along the map until the song has loaded, then along the song
+pub fn spawn_timeline_markers(
+    mut commands: Commands,
+    beatmap: Res<BeatmapInfo>,
+    clock: Res<PlaybackClock>,
+    mut timelines: Query<(Entity, &mut Timeline)>,
+    markers: Query<Entity, With<TimelineMarker>>) {
+
+    let length = clock.length();
+    if length <= 0.0 {
+        return;
+    }
+    let fraction = |time: f64| (time / length).clamp(0.0, 1.0) as f32;
+
+    for (entity, mut timeline) in &mut timelines {
+        if timeline.length == Some(length) {
+            continue;
+        }
+        timeline.length = Some(length);
+
+        for marker in &markers {
+            commands.entity(marker).despawn_recursive();
+        }
+
+        let data = &beatmap.data;
+        commands.entity(entity).with_children(|parent| {
+            for b in data.events.iter().flat_map(|events| &events.breaks) {
+                parent.spawn((TimelineMarker, marker(fraction(b.start_time as f64), Some(fraction(b.end() as f64)), 1.0, BREAK_COLOUR)));
+            }
+
+            for (start, end) in data.timing_map().kiai_ranges() {
+                parent.spawn((TimelineMarker, marker(fraction(start), Some(fraction(end)), 1.0, KIAI_COLOUR)));
+            }
+
+            let mut buckets = vec![0usize; DENSITY_BUCKETS];
+            for hit_object in data.hit_objects.iter().flatten() {
+                let bucket = (hit_object.time as f64 / length * DENSITY_BUCKETS as f64) as usize;
+                if let Some(count) = buckets.get_mut(bucket) {
+                    *count += 1;
+                }
+            }
+            let most = buckets.iter().copied().max().unwrap_or(0).max(1);
+            for (i, count) in buckets.iter().enumerate().filter(|(_, count)| **count > 0) {
+                let start = i as f32 / DENSITY_BUCKETS as f32;
+                let end = (i + 1) as f32 / DENSITY_BUCKETS as f32;
+                parent.spawn((TimelineMarker, marker(start, Some(end), *count as f32 / most as f32, DENSITY_COLOUR)));
+            }
+
+            for bookmark in data.editor.iter().flat_map(|editor| editor.bookmark_times()) {
+                parent.spawn((TimelineMarker, marker(fraction(bookmark as f64), None, 1.0, BOOKMARK_COLOUR)));
+            }
+
+            //-1 means the song select plays from the middle instead
+            let preview_time = data.general.as_ref().and_then(|general| general.preview_time);
+            if let Some(preview_time) = preview_time.filter(|t| *t >= 0) {
+                parent.spawn((TimelineMarker, marker(fraction(preview_time as f64), None, 1.0, PREVIEW_COLOUR)));
+            }
+        });
+    }
+}
+
+pub fn timeline_system(
+    windows: Res<Windows>,
+    clock: Res<PlaybackClock>,
+    timelines: Query<(&Timeline, &Interaction, &Node, &GlobalTransform)>,
+    mut playheads: Query<(&Playhead, &mut Style)>,
+    mut hover_texts: Query<(&HoverTime, &mut Text, &mut Style, &mut Visibility), Without<Playhead>>,
+    mut actions: EventWriter<TransportAction>,
+    mut last_seek: Local<Option<f64>>) {
+
+    let cursor_x = windows.get_primary()
+        .and_then(|window| window.cursor_position())
+        .map(|cursor| cursor.x);
+
+    for (timeline, interaction, node, transform) in &timelines {
+        let length = match timeline.length {
+            Some(length) => length,
+            None => continue,
+        };
+
+        for (_, mut style) in &mut playheads {
+            style.position.left = Val::Percent((clock.time() / length).clamp(0.0, 1.0) as f32 * 100.0);
+        }
+
+        let width = node.size().x;
+        let left = transform.translation().x - width / 2.0;
+        let hovered = cursor_x
+            .filter(|_| *interaction != Interaction::None)
+            .map(|x| (x - left).clamp(0.0, width));
+
+        for (_, mut text, mut style, mut visibility) in &mut hover_texts {
+            visibility.is_visible = hovered.is_some();
+            if let Some(x) = hovered {
+                text.sections[0].value = timestamp(x as f64 / width as f64 * length);
+                style.position.left = Val::Px(x);
+            }
+        }
+
+        //dragging keeps seeking, but only when the cursor moves
+        match hovered {
+            Some(x) if *interaction == Interaction::Clicked => {
+                let time = x as f64 / width as f64 * length;
+                if *last_seek != Some(time) {
+                    actions.send(TransportAction::SeekTo(time));
+                    *last_seek = Some(time);
+                }
+            }
+            _ => *last_seek = None,
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use bevy_kira_audio::*;
+use bevy::prelude::*;
+
+use super::{BeatmapInfo, FONT, SongHandle, clock::PlaybackClock, hitsound::HitSoundChannel};
+
+const FONT_SIZE: f32 = 20.0;
+
+const SHORT_SEEK: f64 = 1000.0;
+const LONG_SEEK: f64 = 5000.0;
+/// one frame at 60 fps, for stepping while paused
+const FRAME_STEP: f64 = 1000.0 / 60.0;
+
+const MIN_RATE: f64 = 0.25;
+const MAX_RATE: f64 = 2.0;
+const RATE_STEP: f64 = 0.25;
+
+/// pressing play this close to the end starts the song over, in ms
+const END_TOLERANCE: f64 = 50.0;
+
+const BUTTON_COLOUR: Color = Color::rgba(0.15, 0.15, 0.15, 0.8);
+const BUTTON_HOVER_COLOUR: Color = Color::rgba(0.3, 0.3, 0.3, 0.8);
+
+/// the song's audio, kept to start it again after it has played to the end
+#[derive(Resource)]
+pub struct SongSource(pub Handle<AudioSource>);
+
+/// how fast the song plays and the times it can jump to, the rest is kept by the [`PlaybackClock`]
+#[derive(Resource)]
+pub struct Transport {
+    pub rate: f64, //on top of the mods' clock rate
+    pub preserve_pitch: bool, //the song is time-stretched to the rate instead of sped up
+    object_times: Vec<f64>,
+    bookmarks: Vec<f64>,
+}
+
+impl Transport {
+
+    pub fn new(beatmap: &BeatmapInfo, preserve_pitch: bool) -> Self {
+        Self {
+            rate: 1.0,
+            preserve_pitch,
+            object_times: beatmap.data.hit_objects.iter().flatten().map(|o| o.time as f64).collect(),
+            bookmarks: beatmap.data.editor.iter().flat_map(|e| e.bookmark_times()).map(|t| t as f64).collect(),
+        }
+    }
+}
+
+/// something the keyboard or a button asks the transport to do
+#[derive(Clone, Copy)]
+pub enum TransportAction {
+    TogglePause,
+    SeekBy(f64),
+    SeekTo(f64),
+    PreviousObject,
+    NextObject,
+    PreviousBookmark,
+    NextBookmark,
+    ChangeRate(f64),
+    TogglePitch,
+    StepFrame(f64), //only while paused
+}
+
+/// sent after every seek with the new position in ms, for systems that keep their own playback state
+pub struct Seeked(pub f64);
+
+#[derive(Component)]
+pub struct TransportButton(TransportAction);
+
+#[derive(Component)]
+pub struct PauseText;
+
+#[derive(Component)]
+pub struct RateText;
+
+pub fn spawn_controls(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>) {
+
+    let text_style = TextStyle {
+        font: asset_server.load(FONT),
+        font_size: FONT_SIZE,
+        color: Color::WHITE,
+    };
+
+    let buttons = [
+        ("<<", TransportAction::SeekBy(-LONG_SEEK)),
+        ("<", TransportAction::SeekBy(-SHORT_SEEK)),
+        ("|<", TransportAction::PreviousObject),
+        ("||", TransportAction::TogglePause),
+        (">|", TransportAction::NextObject),
+        (">", TransportAction::SeekBy(SHORT_SEEK)),
+        (">>", TransportAction::SeekBy(LONG_SEEK)),
+        ("-", TransportAction::ChangeRate(-RATE_STEP)),
+        ("+", TransportAction::ChangeRate(RATE_STEP)),
+    ];
+
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(8.0),
+                left: Val::Px(8.0),
+                ..default()
+            },
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ..default()
+    }).with_children(|parent| {
+        for (label, action) in buttons {
+            parent.spawn((
+                TransportButton(action),
+                ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(36.0), Val::Px(28.0)),
+                        margin: UiRect::all(Val::Px(2.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: BUTTON_COLOUR.into(),
+                    ..default()
+                },
+            )).with_children(|button| {
+                let text = TextBundle::from_section(label, text_style.clone());
+                match action {
+                    TransportAction::TogglePause => button.spawn((PauseText, text)),
+                    _ => button.spawn(text),
+                };
+            });
+
+            //the rate sits between its buttons
+            if let TransportAction::ChangeRate(step) = action {
+                if step < 0.0 {
+                    parent.spawn((
+                        RateText,
+                        TextBundle::from_section("1.00x", text_style.clone())
+                            .with_style(Style {
+                                margin: UiRect::horizontal(Val::Px(4.0)),
+                                ..default()
+                            }),
+                    ));
+                }
+            }
+        }
+    });
+}
+
+/// space pauses, arrows seek (5 s with shift), brackets jump between objects (bookmarks with shift),
+/// comma and period step frames while paused, minus/equals change the rate and P toggles keeping the pitch
+pub fn keyboard_controls(
+    keys: Res<Input<KeyCode>>,
+    mut actions: EventWriter<TransportAction>) {
+
+    let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    let seek = if shift { LONG_SEEK } else { SHORT_SEEK };
+
+    for key in keys.get_just_pressed() {
+        let action = match key {
+            KeyCode::Space => TransportAction::TogglePause,
+            KeyCode::Left => TransportAction::SeekBy(-seek),
+            KeyCode::Right => TransportAction::SeekBy(seek),
+            KeyCode::LBracket if shift => TransportAction::PreviousBookmark,
+            KeyCode::RBracket if shift => TransportAction::NextBookmark,
+            KeyCode::LBracket => TransportAction::PreviousObject,
+            KeyCode::RBracket => TransportAction::NextObject,
+            KeyCode::Comma => TransportAction::StepFrame(-FRAME_STEP),
+            KeyCode::Period => TransportAction::StepFrame(FRAME_STEP),
+            KeyCode::Minus => TransportAction::ChangeRate(-RATE_STEP),
+            KeyCode::Equals => TransportAction::ChangeRate(RATE_STEP),
+            KeyCode::P => TransportAction::TogglePitch,
+            _ => continue,
+        };
+        actions.send(action);
+    }
+}
+
+pub fn button_controls(
+    mut buttons: Query<(&TransportButton, &Interaction, &mut BackgroundColor), Changed<Interaction>>,
+    mut actions: EventWriter<TransportAction>) {
+
+    for (button, interaction, mut colour) in &mut buttons {
+        match interaction {
+            Interaction::Clicked => actions.send(button.0),
+            Interaction::Hovered => *colour = BUTTON_HOVER_COLOUR.into(),
+            Interaction::None => *colour = BUTTON_COLOUR.into(),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transport_system(
+    audio: Res<Audio>,
+    hit_sounds: Res<AudioChannel<HitSoundChannel>>,
+    source: Option<Res<SongSource>>,
+    beatmap: Res<BeatmapInfo>,
+    mut song_handle: Option<ResMut<SongHandle>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    mut transport: ResMut<Transport>,
+    mut clock: ResMut<PlaybackClock>,
+    mut actions: EventReader<TransportAction>,
+    mut seeked: EventWriter<Seeked>) {
+
+    let was_paused = clock.paused();
+
+    //the song played to the end, it stays there until it's played or seeked again
+    if ended(&clock, &audio_instances, song_handle.as_deref()) {
+        clock.set_paused(true);
+    }
+
+    let clock_rate = beatmap.attributes.clock_rate;
+
+    for action in actions.iter() {
+        let position = clock.time();
+        let target = match *action {
+            TransportAction::TogglePause => {
+                let paused = !clock.paused();
+                clock.set_paused(paused);
+                if ended(&clock, &audio_instances, song_handle.as_deref()) && !paused {
+                    //start over if it ended, otherwise carry on from where it was left
+                    let at_end = position >= clock.length() - END_TOLERANCE;
+                    Some(if at_end { 0.0 } else { position })
+                } else {
+                    if let Some(song) = song_handle.as_ref().and_then(|handle| audio_instances.get_mut(&handle.instance)) {
+                        if paused {
+                            song.pause(AudioTween::default());
+                        } else {
+                            song.resume(AudioTween::default());
+                        }
+                    }
+                    None
+                }
+            }
+            TransportAction::SeekBy(amount) => Some(position + amount),
+            TransportAction::SeekTo(time) => Some(time),
+            TransportAction::PreviousObject => previous(&transport.object_times, position),
+            TransportAction::NextObject => next(&transport.object_times, position),
+            TransportAction::PreviousBookmark => previous(&transport.bookmarks, position),
+            TransportAction::NextBookmark => next(&transport.bookmarks, position),
+            TransportAction::ChangeRate(step) => {
+                transport.rate = (transport.rate + step).clamp(MIN_RATE, MAX_RATE);
+                clock.set_rate(clock_rate * transport.rate);
+                //a stretched song keeps playing sped up until the new rate's stretch is ready
+                if let Some(handle) = song_handle.as_deref() {
+                    if let Some(song) = audio_instances.get_mut(&handle.instance) {
+                        song.set_playback_rate(clock.rate() / handle.stretch, AudioTween::default());
+                    }
+                }
+                None
+            }
+            TransportAction::TogglePitch => {
+                transport.preserve_pitch = !transport.preserve_pitch;
+                None
+            }
+            //a frame covers more of the map with DT and the rate on top of it
+            TransportAction::StepFrame(step) if clock.paused() => Some(position + step * clock.rate()),
+            TransportAction::StepFrame(_) => None,
+        };
+
+        if let Some(target) = target {
+            let target = target.clamp(0.0, clock.length());
+            let song_ended = song_handle.as_deref().is_some_and(|handle| song_stopped(&audio_instances, handle));
+
+            if song_ended {
+                //a song that ended can't be seeked, it's started again at the target once it's played
+                if !clock.paused() {
+                    if let (Some(handle), Some(source)) = (song_handle.as_mut(), source.as_ref()) {
+                        //the original, it's stretched again if it needs to be
+                        handle.instance = audio.play(source.0.clone())
+                            .start_from(target / 1000.0)
+                            .with_playback_rate(clock.rate())
+                            .handle();
+                        handle.stretch = 1.0;
+                    }
+                }
+            } else if let Some((song, stretch)) = song_handle.as_ref()
+                .and_then(|handle| Some((audio_instances.get_mut(&handle.instance)?, handle.stretch))) {
+                song.seek_to(target / 1000.0 / stretch);
+            } else if !clock.started() {
+                //the song hasn't started yet
+                continue;
+            }
+
+            clock.seek(target);
+            seeked.send(Seeked(target));
+        }
+    }
+
+    //hit sounds that are already playing, like slider slides, pause with the song
+    if clock.paused() != was_paused {
+        if clock.paused() {
+            hit_sounds.pause();
+        } else {
+            hit_sounds.resume();
+        }
+    }
+}
+
+/// the song played to the end and has to be started again
+fn song_stopped(audio_instances: &Assets<AudioInstance>, song_handle: &SongHandle) -> bool {
+    audio_instances.get(&song_handle.instance).is_some_and(|song| song.state() == PlaybackState::Stopped)
+}
+
+/// the song stopped, or the clock ran past the map without one
+fn ended(clock: &PlaybackClock, audio_instances: &Assets<AudioInstance>, song_handle: Option<&SongHandle>) -> bool {
+    match song_handle.filter(|handle| audio_instances.get(&handle.instance).is_some()) {
+        Some(song_handle) => song_stopped(audio_instances, song_handle),
+        None => clock.started() && clock.time() >= clock.length(),
+    }
+}
+
+/// the last time before `position`, with a little leeway so repeated presses keep going back
+fn previous(times: &[f64], position: f64) -> Option<f64> {
+    times.iter().rev().find(|t| **t < position - 1.0).copied()
+}
+
+fn next(times: &[f64], position: f64) -> Option<f64> {
+    times.iter().find(|t| **t > position + 1.0).copied()
+}
+
+pub fn update_controls_text(
+    clock: Res<PlaybackClock>,
+    transport: Res<Transport>,
+    mut pause_text: Query<&mut Text, (With<PauseText>, Without<RateText>)>,
+    mut rate_text: Query<&mut Text, (With<RateText>, Without<PauseText>)>) {
+
+    for mut text in &mut pause_text {
+        text.sections[0].value = String::from(if clock.paused() { ">" } else { "||" });
+    }
+    for mut text in &mut rate_text {
+        text.sections[0].value = format!("{:.2}x", transport.rate);
+    }
+}